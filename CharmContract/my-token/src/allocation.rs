@@ -0,0 +1,35 @@
+//! Splitting an estate across several contracts
+//!
+//! Each contract is validated on its own on-chain, so only a wallet that sees all
+//! of an owner's contracts can tell whether together they promise too much.
+//! Nothing here is part of the on-chain contract.
+
+use crate::{share_amount, ContractId, FULL_SHARE};
+use serde::{Deserialize, Serialize};
+
+// How an owner's estate is split across the contracts they manage
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct PercentageAllocation {
+    pub contracts: Vec<(ContractId, u16)>,   // (contract, share of the estate in basis points)
+}
+
+/// Checks that an owner's contracts don't promise more than their whole estate
+///
+/// Contracts are validated one at a time on-chain, so nothing there stops an owner
+/// from giving 60% to each of two vaults; wallets check the whole plan here.
+pub fn validate_total_allocation(alloc: &PercentageAllocation) -> bool {
+    let total: u32 = alloc.contracts.iter().map(|(_, share_bps)| *share_bps as u32).sum();
+    total <= FULL_SHARE as u32
+}
+
+/// Splits an estate of `total_sats` across contracts by their shares
+///
+/// Each amount is rounded down; whatever isn't allocated (rounding, or shares
+/// summing to less than 100%) stays with the owner.
+pub fn allocate_across_contracts(total_sats: u64, alloc: &PercentageAllocation) -> Vec<(ContractId, u64)> {
+    alloc
+        .contracts
+        .iter()
+        .map(|(id, share_bps)| (*id, share_amount(total_sats, *share_bps)))
+        .collect()
+}
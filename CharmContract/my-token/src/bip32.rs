@@ -0,0 +1,157 @@
+//! Minimal BIP-32 public key derivation
+//!
+//! Only what the contract needs to check a beneficiary's fresh address against a
+//! committed xpub: parsing extended public keys and non-hardened child derivation.
+//! Pure Rust on top of k256, so it runs inside the zkVM.
+
+use hmac::{Hmac, Mac};
+use k256::elliptic_curve::sec1::ToEncodedPoint;
+use k256::elliptic_curve::PrimeField;
+use k256::{FieldBytes, ProjectivePoint, PublicKey, Scalar};
+use ripemd::Ripemd160;
+use sha2::{Digest, Sha256, Sha512};
+
+/// Child indexes at or above this are hardened and can't be derived from a public key
+pub const HARDENED_INDEX: u32 = 1 << 31;
+
+/// Version bytes of a mainnet extended public key ("xpub")
+pub const XPUB_VERSION: [u8; 4] = [0x04, 0x88, 0xb2, 0x1e];
+
+/// Version bytes of a testnet extended public key ("tpub")
+pub const TPUB_VERSION: [u8; 4] = [0x04, 0x35, 0x87, 0xcf];
+
+// A BIP-32 extended public key
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExtendedPubKey {
+    pub version: [u8; 4],
+    pub depth: u8,
+    pub parent_fingerprint: [u8; 4],
+    pub child_number: u32,
+    pub chain_code: [u8; 32],
+    pub public_key: [u8; 33],    // Compressed SEC1 point
+}
+
+impl ExtendedPubKey {
+    /// Parses a base58check-encoded xpub or tpub, returning None if it isn't a valid one
+    pub fn from_base58(encoded: &str) -> Option<Self> {
+        let payload = bs58::decode(encoded).with_check(None).into_vec().ok()?;
+        if payload.len() != 78 {
+            return None;
+        }
+
+        let version: [u8; 4] = payload[0..4].try_into().ok()?;
+        if version != XPUB_VERSION && version != TPUB_VERSION {
+            return None;
+        }
+        let public_key: [u8; 33] = payload[45..78].try_into().ok()?;
+        PublicKey::from_sec1_bytes(&public_key).ok()?;
+
+        Some(ExtendedPubKey {
+            version,
+            depth: payload[4],
+            parent_fingerprint: payload[5..9].try_into().ok()?,
+            child_number: u32::from_be_bytes(payload[9..13].try_into().ok()?),
+            chain_code: payload[13..45].try_into().ok()?,
+            public_key,
+        })
+    }
+
+    /// Derives the non-hardened child at `index` (CKDpub)
+    ///
+    /// Returns None for hardened indexes, and in the (astronomically unlikely) case
+    /// that the index yields an invalid key, which BIP-32 says to skip.
+    pub fn derive_child(&self, index: u32) -> Option<Self> {
+        if index >= HARDENED_INDEX {
+            return None;
+        }
+
+        let mut mac = Hmac::<Sha512>::new_from_slice(&self.chain_code).ok()?;
+        mac.update(&self.public_key);
+        mac.update(&index.to_be_bytes());
+        let i = mac.finalize().into_bytes();
+        let (il, ir) = i.split_at(32);
+
+        let tweak = Option::<Scalar>::from(Scalar::from_repr(FieldBytes::clone_from_slice(il)))?;
+        let parent = PublicKey::from_sec1_bytes(&self.public_key).ok()?;
+        let child = ProjectivePoint::GENERATOR * tweak + parent.to_projective();
+        let child = PublicKey::from_affine(child.to_affine()).ok()?;
+
+        Some(ExtendedPubKey {
+            version: self.version,
+            depth: self.depth.checked_add(1)?,
+            parent_fingerprint: hash160(&self.public_key)[..4].try_into().ok()?,
+            child_number: index,
+            chain_code: ir.try_into().ok()?,
+            public_key: child.to_encoded_point(true).as_bytes().try_into().ok()?,
+        })
+    }
+}
+
+/// RIPEMD-160 of SHA-256, as used for key fingerprints and P2WPKH programs
+pub fn hash160(data: &[u8]) -> [u8; 20] {
+    Ripemd160::digest(Sha256::digest(data)).into()
+}
+
+/// The P2WPKH scriptPubKey paying to a compressed public key
+pub fn p2wpkh_script(public_key: &[u8; 33]) -> Vec<u8> {
+    let mut script = vec![0x00, 0x14];
+    script.extend(hash160(public_key));
+    script
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // Walks a chain of xpubs from the BIP-32 test vectors, each paired with the
+    // child number it was derived at (unused for the master key): every
+    // non-hardened step must derive exactly the next xpub, and a hardened one must
+    // be refused
+    fn check_vector(chain: &[(u32, &str)]) {
+        let mut keys = chain.iter().map(|(index, xpub)| (*index, ExtendedPubKey::from_base58(xpub).unwrap()));
+        let (_, mut parent) = keys.next().unwrap();
+        for (index, child) in keys {
+            if index < HARDENED_INDEX {
+                assert_eq!(parent.derive_child(index), Some(child.clone()));
+            } else {
+                assert_eq!(parent.derive_child(index), None);
+                assert_eq!(child.parent_fingerprint, hash160(&parent.public_key)[..4]);
+                assert_eq!((child.depth, child.child_number), (parent.depth + 1, index));
+            }
+            parent = child;
+        }
+    }
+
+    #[test]
+    fn test_vector_1() {
+        check_vector(&[
+            (0, "xpub661MyMwAqRbcFtXgS5sYJABqqG9YLmC4Q1Rdap9gSE8NqtwybGhePY2gZ29ESFjqJoCu1Rupje8YtGqsefD265TMg7usUDFdp6W1EGMcet8"),
+            (HARDENED_INDEX, "xpub68Gmy5EdvgibQVfPdqkBBCHxA5htiqg55crXYuXoQRKfDBFA1WEjWgP6LHhwBZeNK1VTsfTFUHCdrfp1bgwQ9xv5ski8PX9rL2dZXvgGDnw"),
+            (1, "xpub6ASuArnXKPbfEwhqN6e3mwBcDTgzisQN1wXN9BJcM47sSikHjJf3UFHKkNAWbWMiGj7Wf5uMash7SyYq527Hqck2AxYysAA7xmALppuCkwQ"),
+            (HARDENED_INDEX + 2, "xpub6D4BDPcP2GT577Vvch3R8wDkScZWzQzMMUm3PWbmWvVJrZwQY4VUNgqFJPMM3No2dFDFGTsxxpG5uJh7n7epu4trkrX7x7DogT5Uv6fcLW5"),
+            (2, "xpub6FHa3pjLCk84BayeJxFW2SP4XRrFd1JYnxeLeU8EqN3vDfZmbqBqaGJAyiLjTAwm6ZLRQUMv1ZACTj37sR62cfN7fe5JnJ7dh8zL4fiyLHV"),
+            (1_000_000_000, "xpub6H1LXWLaKsWFhvm6RVpEL9P4KfRZSW7abD2ttkWP3SSQvnyA8FSVqNTEcYFgJS2UaFcxupHiYkro49S8yGasTvXEYBVPamhGW6cFJodrTHy"),
+        ]);
+    }
+
+    #[test]
+    fn test_vector_2() {
+        check_vector(&[
+            (0, "xpub661MyMwAqRbcFW31YEwpkMuc5THy2PSt5bDMsktWQcFF8syAmRUapSCGu8ED9W6oDMSgv6Zz8idoc4a6mr8BDzTJY47LJhkJ8UB7WEGuduB"),
+            (0, "xpub69H7F5d8KSRgmmdJg2KhpAK8SR3DjMwAdkxj3ZuxV27CprR9LgpeyGmXUbC6wb7ERfvrnKZjXoUmmDznezpbZb7ap6r1D3tgFxHmwMkQTPH"),
+            (HARDENED_INDEX + 2_147_483_647, "xpub6ASAVgeehLbnwdqV6UKMHVzgqAG8Gr6riv3Fxxpj8ksbH9ebxaEyBLZ85ySDhKiLDBrQSARLq1uNRts8RuJiHjaDMBU4Zn9h8LZNnBC5y4a"),
+            (1, "xpub6DF8uhdarytz3FWdA8TvFSvvAh8dP3283MY7p2V4SeE2wyWmG5mg5EwVvmdMVCQcoNJxGoWaU9DCWh89LojfZ537wTfunKau47EL2dhHKon"),
+            (HARDENED_INDEX + 2_147_483_646, "xpub6ERApfZwUNrhLCkDtcHTcxd75RbzS1ed54G1LkBUHQVHQKqhMkhgbmJbZRkrgZw4koxb5JaHWkY4ALHY2grBGRjaDMzQLcgJvLJuZZvRcEL"),
+            (2, "xpub6FnCn6nSzZAw5Tw7cgR9bi15UV96gLZhjDstkXXxvCLsUXBGXPdSnLFbdpq8p9HmGsApME5hQTZ3emM2rnY5agb9rXpVGyy3bdW6EEgAtqt"),
+        ]);
+    }
+
+    // Retention of leading zeros; its only step is hardened
+    #[test]
+    fn test_vector_3() {
+        check_vector(&[
+            (0, "xpub661MyMwAqRbcEZVB4dScxMAdx6d4nFc9nvyvH3v4gJL378CSRZiYmhRoP7mBy6gSPSCYk6SzXPTf3ND1cZAceL7SfJ1Z3GC8vBgp2epUt13"),
+            (HARDENED_INDEX, "xpub68NZiKmJWnxxS6aaHmn81bvJeTESw724CRDs6HbuccFQN9Ku14VQrADWgqbhhTHBaohPX4CjNLf9fq9MYo6oDaPPLPxSb7gwQN3ih19Zm4Y"),
+        ]);
+    }
+}
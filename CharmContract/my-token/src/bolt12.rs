@@ -0,0 +1,204 @@
+//! Minimal BOLT12 offer parsing
+//!
+//! Enough to sanity-check a Lightning beneficiary's offer when the contract is
+//! created: the bech32 encoding, the TLV stream and the fields every offer must
+//! carry. Offers are never paid from here, so blinded paths aren't interpreted.
+
+use k256::PublicKey;
+
+/// Longest offer string accepted, in bytes
+pub const MAX_OFFER_LEN: usize = 2048;
+
+const CHARSET: &[u8; 32] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+
+// TLV types of the offer fields read here
+const OFFER_AMOUNT: u64 = 8;
+const OFFER_DESCRIPTION: u64 = 10;
+const OFFER_PATHS: u64 = 16;
+const OFFER_ISSUER_ID: u64 = 22;
+
+// Every even (mandatory) type an offer may use; readers must reject any other
+const KNOWN_EVEN_TYPES: [u64; 11] = [2, 4, 6, 8, 10, 12, 14, 16, 18, 20, 22];
+
+// The fields of a BOLT12 offer that matter for validation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Offer {
+    pub amount: Option<u64>,          // Requested amount (msat unless a currency is set)
+    pub description: Option<String>,
+    pub issuer_id: Option<[u8; 33]>,  // Node the invoice request goes to, if not via paths
+    pub has_paths: bool,              // Reached through blinded paths instead
+}
+
+impl Offer {
+    /// Parses an `lno1...` offer, returning None if it isn't a well-formed one
+    ///
+    /// Accepts either case (but not both), and offers split with `+` as the spec allows.
+    pub fn parse(encoded: &str) -> Option<Self> {
+        if encoded.len() > MAX_OFFER_LEN {
+            return None;
+        }
+        if encoded.chars().any(char::is_uppercase) && encoded.chars().any(char::is_lowercase) {
+            return None;
+        }
+
+        let joined = join_parts(&encoded.to_ascii_lowercase())?;
+        let bytes = from_base32(joined.strip_prefix("lno1")?)?;
+
+        let mut offer = Offer {
+            amount: None,
+            description: None,
+            issuer_id: None,
+            has_paths: false,
+        };
+        for (kind, value) in parse_tlv(&bytes)? {
+            // Only offer fields (and their experimental range) may appear
+            if kind >= 80 && !(1_000_000_000..2_000_000_000).contains(&kind) {
+                return None;
+            }
+            if kind % 2 == 0 && !KNOWN_EVEN_TYPES.contains(&kind) {
+                return None;
+            }
+
+            match kind {
+                OFFER_AMOUNT => offer.amount = Some(read_tu64(value)?),
+                OFFER_DESCRIPTION => offer.description = Some(String::from_utf8(value.to_vec()).ok()?),
+                OFFER_PATHS => offer.has_paths = !value.is_empty(),
+                OFFER_ISSUER_ID => {
+                    PublicKey::from_sec1_bytes(value).ok()?;
+                    offer.issuer_id = Some(value.try_into().ok()?);
+                }
+                _ => {}
+            }
+        }
+
+        // There must be someone to ask for an invoice, and an amount needs a description
+        if offer.issuer_id.is_none() && !offer.has_paths {
+            return None;
+        }
+        if offer.amount.is_some() && offer.description.is_none() {
+            return None;
+        }
+        Some(offer)
+    }
+}
+
+/// Removes the `+` (and any whitespace after it) that long offers may be split with
+fn join_parts(encoded: &str) -> Option<String> {
+    let mut parts = encoded.split('+');
+    let mut joined = parts.next()?.to_string();
+    for part in parts {
+        let part = part.trim_start();
+        if joined.is_empty() || part.is_empty() {
+            return None;
+        }
+        joined.push_str(part);
+    }
+    Some(joined)
+}
+
+/// Converts bech32 characters to bytes; offers carry no checksum
+fn from_base32(data: &str) -> Option<Vec<u8>> {
+    let mut bytes = Vec::new();
+    let mut acc: u32 = 0;
+    let mut bits = 0;
+    for c in data.bytes() {
+        let value = CHARSET.iter().position(|&x| x == c)? as u32;
+        acc = ((acc << 5) | value) & 0xfff;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((acc >> bits) as u8);
+        }
+    }
+
+    // Whatever is left over is padding, and must be zero
+    (acc & ((1 << bits) - 1) == 0).then_some(bytes)
+}
+
+/// Splits a TLV stream into (type, value) records, which must be in strictly increasing type order
+fn parse_tlv(mut bytes: &[u8]) -> Option<Vec<(u64, &[u8])>> {
+    let mut records = Vec::new();
+    let mut last: Option<u64> = None;
+    while !bytes.is_empty() {
+        let kind = read_bigsize(&mut bytes)?;
+        let len = usize::try_from(read_bigsize(&mut bytes)?).ok()?;
+        if last.is_some_and(|last| kind <= last) || len > bytes.len() {
+            return None;
+        }
+
+        let (value, rest) = bytes.split_at(len);
+        records.push((kind, value));
+        bytes = rest;
+        last = Some(kind);
+    }
+    Some(records)
+}
+
+/// Reads a BigSize integer, rejecting non-minimal encodings
+fn read_bigsize(bytes: &mut &[u8]) -> Option<u64> {
+    let (&first, rest) = bytes.split_first()?;
+    let (width, minimum) = match first {
+        0xfd => (2, 0xfd),
+        0xfe => (4, 0x1_0000),
+        0xff => (8, 0x1_0000_0000),
+        n => {
+            *bytes = rest;
+            return Some(n as u64);
+        }
+    };
+    if rest.len() < width {
+        return None;
+    }
+
+    let (value, rest) = rest.split_at(width);
+    let value = value.iter().fold(0u64, |acc, &b| (acc << 8) | b as u64);
+    *bytes = rest;
+    (value >= minimum).then_some(value)
+}
+
+/// Reads a truncated u64: big-endian with no leading zero bytes
+fn read_tu64(value: &[u8]) -> Option<u64> {
+    if value.len() > 8 || value.first() == Some(&0) {
+        return None;
+    }
+    Some(value.iter().fold(0u64, |acc, &b| (acc << 8) | b as u64))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // Minimal offer from the BOLT12 test vectors: just an issuer id
+    const MINIMAL_OFFER: &str = "lno1zcss9mk8y3wkklfvevcrszlmu23kfrxh49px20665dqwmn4p72pksese";
+
+    #[test]
+    fn test_parse_minimal_offer() {
+        let offer = Offer::parse(MINIMAL_OFFER).unwrap();
+        assert_eq!(offer.issuer_id.unwrap()[..4], [0x02, 0xee, 0xc7, 0x24]);
+        assert_eq!(offer.amount, None);
+        assert!(!offer.has_paths);
+
+        // Either case, and split with '+', is the same offer
+        assert_eq!(Offer::parse(&MINIMAL_OFFER.to_uppercase()), Some(offer.clone()));
+        let (head, tail) = MINIMAL_OFFER.split_at(30);
+        assert_eq!(Offer::parse(&format!("{head}+\n  {tail}")), Some(offer));
+    }
+
+    #[test]
+    fn test_parse_rejects_malformed_offers() {
+        let (head, tail) = MINIMAL_OFFER.split_at(30);
+        let cases = [
+            "".to_string(),
+            "lno1".to_string(),                                  // No issuer id or paths
+            MINIMAL_OFFER.replacen("lno1", "lnx1", 1),           // Not an offer
+            MINIMAL_OFFER.replacen("lno1", "LNO1", 1),           // Mixed case
+            MINIMAL_OFFER[..MINIMAL_OFFER.len() - 2].to_string(), // Truncated TLV
+            format!("{head}b{tail}"),                            // 'b' isn't a bech32 character
+            format!("{head}+"),                                  // Dangling '+'
+            format!("{head} {tail}"),                            // Whitespace without '+'
+        ];
+        for case in cases {
+            assert_eq!(Offer::parse(&case), None, "{case}");
+        }
+    }
+}
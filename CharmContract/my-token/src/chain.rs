@@ -0,0 +1,155 @@
+//! Validating transactions in sequence and in batches
+//!
+//! Wallets and custodians replay a contract's history, or check several contracts
+//! spent by one transaction, by running app_contract over each; what these add is
+//! how the transactions relate to each other. Nothing here is part of the on-chain
+//! contract.
+
+use crate::{
+    app_contract, continuation_nft, operation_witness, ContractError, ContractId, Operation, OperationWitness,
+    WitnessData,
+};
+use charms_sdk::data::{charm_values, App, Charms, Data, Transaction};
+
+// Why a transaction (or a chain of them) failed validation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InheritanceError {
+    InvalidTransaction,                // The contract rejected the transaction
+    Contract(ContractError),           // Rejected, for a reason the wallet can fix (see validate_transaction)
+    ChainBroken { tx_index: usize },   // Input NFT doesn't match the previous transaction's output NFT
+    SharedUtxo,                        // A UTXO carries this contract's NFT together with another's in the batch
+}
+
+// Check-ins of several contracts, made together in one transaction
+//
+// For custodians managing many vaults: each contract is still checked in under its
+// own rules and with its own witness (see validate_batch_checkin).
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchCheckinProof {
+    pub contracts: Vec<(ContractId, WitnessData)>,   // (contract, its check-in witness)
+}
+
+/// Validates a single transaction against the contract
+///
+/// A check-in, update or extension that doesn't carry the NFT on to exactly one
+/// output is reported as such (see continuation_nft); any other rejection is
+/// just InvalidTransaction.
+pub fn validate_transaction(app: &App, tx: &Transaction, x: &Data, w: &Data) -> Result<(), InheritanceError> {
+    if app_contract(app, tx, x, w) {
+        return Ok(());
+    }
+
+    let carries_on = matches!(
+        w.value::<OperationWitness>().map(|witness| witness.operation),
+        Ok(Operation::Checkin
+            | Operation::OracleCheckin
+            | Operation::UpdateBeneficiaries
+            | Operation::EmergencyExtend
+            | Operation::RecordMiss)
+    );
+    match continuation_nft(app, tx) {
+        Err(error) if carries_on => Err(InheritanceError::Contract(error)),
+        _ => Err(InheritanceError::InvalidTransaction),
+    }
+}
+
+/// Validates one transaction operating several contracts at once, one result per contract
+///
+/// `contracts` pairs each contract's app with its witness, and each is validated on
+/// its own. No UTXO the transaction spends or creates may carry the NFTs of two of
+/// them: spending one contract would then move the other outside its own rules.
+pub fn validate_batch(contracts: &[(App, Data)], tx: &Transaction, x: &Data) -> Vec<Result<(), InheritanceError>> {
+    let batch: Vec<&App> = contracts.iter().map(|(app, _)| app).collect();
+
+    contracts
+        .iter()
+        .map(|(app, w)| {
+            if shares_utxo(&batch, tx, app) {
+                Err(InheritanceError::SharedUtxo)
+            } else {
+                validate_transaction(app, tx, x, w)
+            }
+        })
+        .collect()
+}
+
+/// Whether a UTXO `tx` spends or creates carries `app`'s NFT together with that
+/// of another contract in `batch`
+fn shares_utxo(batch: &[&App], tx: &Transaction, app: &App) -> bool {
+    let carries = |charms: &Charms, app: &App| charms.keys().any(|a| a.identity == app.identity);
+    tx.ins
+        .iter()
+        .map(|(_, charms)| charms)
+        .chain(tx.outs.iter())
+        .filter(|charms| carries(charms, app))
+        .any(|charms| batch.iter().filter(|other| carries(charms, other)).count() > 1)
+}
+
+/// Checks each check-in of a batch on its own, returning one verdict per contract
+///
+/// Every contract named must be among `apps`, carry a check-in witness, and pass
+/// as app_contract would check it with the transaction's public input `x`; as in
+/// validate_batch, none may share a UTXO with another contract of the batch. The
+/// transaction is made at one height, so a witness giving any other is rejected.
+pub fn validate_batch_checkin(
+    proofs: &BatchCheckinProof,
+    tx: &Transaction,
+    x: &Data,
+    apps: &[App],
+) -> Vec<(ContractId, bool)> {
+    let app_of = |id: &ContractId| apps.iter().find(|app| ContractId::from(*app) == *id);
+    let batch: Vec<&App> = proofs.contracts.iter().filter_map(|(id, _)| app_of(id)).collect();
+
+    proofs
+        .contracts
+        .iter()
+        .map(|(id, witness)| {
+            let valid = match (app_of(id), witness) {
+                (Some(app), WitnessData::CheckIn { .. }) => {
+                    !shares_utxo(&batch, tx, app)
+                        && app_contract(app, tx, x, &operation_witness(Operation::Checkin, &witness.to_data()))
+                }
+                _ => false,
+            };
+            (*id, valid)
+        })
+        .collect()
+}
+
+/// Encodes a whole batch as one witness blob: each contract paired with the
+/// witness its app_contract reads (see operation_witness), in batch order
+pub fn aggregate_batch_witness(proofs: &BatchCheckinProof) -> Data {
+    let witnesses: Vec<(ContractId, Data)> = proofs
+        .contracts
+        .iter()
+        .map(|(id, witness)| (*id, operation_witness(Operation::Checkin, &witness.to_data())))
+        .collect();
+    Data::from(&witnesses)
+}
+
+/// Replays a complete contract history, returning one result per transaction
+///
+/// Each transaction (with its public inputs and witness) is validated in order, and
+/// the NFT it consumes must be exactly the NFT produced by the previous transaction.
+pub fn chain_validate(transactions: &[(Transaction, Data, Data)], app: &App) -> Vec<Result<(), InheritanceError>> {
+    let mut previous_output: Option<Data> = None;
+
+    transactions
+        .iter()
+        .enumerate()
+        .map(|(tx_index, (tx, x, w))| {
+            let input: Vec<&Data> = charm_values(app, tx.ins.iter().map(|(_, v)| v)).collect();
+            let output: Vec<&Data> = charm_values(app, tx.outs.iter()).collect();
+
+            // Every transaction after the first must spend the state left by its predecessor
+            let result = if tx_index > 0 && (input.len() != 1 || previous_output.as_ref() != Some(input[0])) {
+                Err(InheritanceError::ChainBroken { tx_index })
+            } else {
+                validate_transaction(app, tx, x, w)
+            };
+
+            previous_output = output.first().map(|data| (*data).clone());
+            result
+        })
+        .collect()
+}
@@ -0,0 +1,83 @@
+//! Content diffs, for sharing a change to a contract between machines
+//!
+//! Nothing here is part of the on-chain contract.
+
+use crate::{InheritanceContent, ValidationError};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+// One top-level field of a contract's content, set to a new value (see diff_inheritance)
+//
+// Fields are named as they're serialized. `value: None` clears the field back to the
+// default it's left out of the payload at.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FieldChange {
+    pub field: String,
+    pub value: Option<ciborium::Value>,
+}
+
+// Why a diff couldn't be applied to a contract (see diff_and_patch)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PatchError {
+    ConflictingChanges { field: String },   // Two changes set the same field
+    InvalidContent,                         // A change names no field, or a value the field can't hold
+    Content(ValidationError),               // The patched content isn't a valid contract
+}
+
+/// The fields `new` sets differently from `base`, in field name order
+///
+/// Each change carries the field's whole new value, so a diff can be computed on
+/// one machine and applied with diff_and_patch on another.
+pub fn diff_inheritance(base: &InheritanceContent, new: &InheritanceContent) -> Vec<FieldChange> {
+    let base = content_fields(base);
+    let new = content_fields(new);
+
+    let fields: BTreeSet<&String> = base.keys().chain(new.keys()).collect();
+    fields
+        .into_iter()
+        .filter(|field| base.get(*field) != new.get(*field))
+        .map(|field| FieldChange {
+            field: field.clone(),
+            value: new.get(field).cloned(),
+        })
+        .collect()
+}
+
+/// Applies a diff from diff_inheritance to `base`, in order, and validates the result
+///
+/// The result must pass InheritanceContent::validate_invariants, which live
+/// contracts are held to, so a diff applies to a contract that has been checked
+/// in since its creation. Nothing is applied if two changes set the same field.
+pub fn diff_and_patch(base: &InheritanceContent, diff: &[FieldChange]) -> Result<InheritanceContent, PatchError> {
+    let mut changed = BTreeSet::new();
+    if let Some(change) = diff.iter().find(|change| !changed.insert(&change.field)) {
+        return Err(PatchError::ConflictingChanges { field: change.field.clone() });
+    }
+
+    let mut fields = content_fields(base);
+    for change in diff {
+        match &change.value {
+            Some(value) => fields.insert(change.field.clone(), value.clone()),
+            None => fields.remove(&change.field),
+        };
+    }
+
+    let entries = fields.into_iter().map(|(field, value)| (ciborium::Value::Text(field), value)).collect();
+    let patched: InheritanceContent = ciborium::Value::Map(entries)
+        .deserialized()
+        .map_err(|_| PatchError::InvalidContent)?;
+    patched.validate_invariants().map_err(PatchError::Content)?;
+    Ok(patched)
+}
+
+/// The content's top-level fields, by the names they're serialized under
+fn content_fields(content: &InheritanceContent) -> BTreeMap<String, ciborium::Value> {
+    let value = ciborium::Value::serialized(content).expect("contract types always serialize");
+    let ciborium::Value::Map(entries) = value else {
+        unreachable!("a struct serializes as a map")
+    };
+    entries
+        .into_iter()
+        .filter_map(|(field, value)| Some((field.into_text().ok()?, value)))
+        .collect()
+}
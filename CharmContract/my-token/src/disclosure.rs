@@ -0,0 +1,101 @@
+//! Publishing a contract without its owner's key
+//!
+//! Beneficiaries can be shown everything the contract says about them, and check
+//! it against a key the owner gives them later, without the key itself going
+//! public. Nothing here is part of the on-chain contract.
+
+use crate::validation_rules::RuleSet;
+use crate::{
+    decode_hex, xonly_bytes, Beneficiary, ContractRecoveryMode, DelayDecay, GracePeriod, InheritanceContent,
+    InheritanceStatus, Network, OwnerPubkey, RoundingMode,
+};
+use charms_sdk::data::B32;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+
+// Public view of an inheritance that hides the owner's key behind a commitment
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RedactedInheritanceContent {
+    pub owner_pubkey_commitment: B32,      // SHA-256 of the owner's x-only pubkey bytes
+    pub last_checkin_block: u64,
+    pub trigger_delay_blocks: u64,
+    pub beneficiaries: Vec<Beneficiary>,
+    pub status: InheritanceStatus,
+    pub delay_decay: Option<DelayDecay>,
+    pub rule_set: RuleSet,
+    pub total_locked_sats: u64,
+    pub network: Network,
+    pub trigger_height: Option<u64>,
+    pub fallback_address: Option<String>,
+    pub created_at_block: u64,
+    pub guardians: Vec<String>,
+    pub guardian_threshold: u8,
+    pub rounding_mode: RoundingMode,
+    pub metadata: BTreeMap<String, String>,
+    pub leave_tombstone: bool,
+    pub policy_max_gap: u64,
+    pub recovery_mode: Option<ContractRecoveryMode>,
+    pub min_expected_funding: u64,
+    pub oracle_pubkey: Option<String>,
+    pub will_document_hash: Option<B32>,
+    pub will_locked: bool,
+    pub emergency_contact_pubkey: Option<String>,
+    pub emergency_extension_used: bool,
+    pub grace_period: Option<GracePeriod>,
+    pub allowed_missed_checkins: u8,
+    pub missed_count: u8,
+    pub court_pubkey: Option<OwnerPubkey>,
+    pub guardian_emergency_delay: u64,
+    pub checkin_window_blocks: u64,
+}
+
+/// Produces a copy of the contract that beneficiaries can verify without learning the owner's key
+pub fn redact_owner(inheritance: &InheritanceContent) -> RedactedInheritanceContent {
+    let owner_xonly = decode_hex(&inheritance.owner_pubkey)
+        .map(|bytes| xonly_bytes(&bytes).to_vec())
+        .unwrap_or_default();
+
+    RedactedInheritanceContent {
+        owner_pubkey_commitment: B32(Sha256::digest(owner_xonly).into()),
+        last_checkin_block: inheritance.last_checkin_block,
+        trigger_delay_blocks: inheritance.trigger_delay_blocks,
+        beneficiaries: inheritance.beneficiaries.clone(),
+        status: inheritance.status.clone(),
+        delay_decay: inheritance.delay_decay.clone(),
+        rule_set: inheritance.rule_set.clone(),
+        total_locked_sats: inheritance.total_locked_sats,
+        network: inheritance.network,
+        trigger_height: inheritance.trigger_height,
+        fallback_address: inheritance.fallback_address.clone(),
+        created_at_block: inheritance.created_at_block,
+        guardians: inheritance.guardians.clone(),
+        guardian_threshold: inheritance.guardian_threshold,
+        rounding_mode: inheritance.rounding_mode,
+        metadata: inheritance.metadata.clone(),
+        leave_tombstone: inheritance.leave_tombstone,
+        policy_max_gap: inheritance.policy_max_gap,
+        recovery_mode: inheritance.recovery_mode,
+        min_expected_funding: inheritance.min_expected_funding,
+        oracle_pubkey: inheritance.oracle_pubkey.clone(),
+        will_document_hash: inheritance.will_document_hash,
+        will_locked: inheritance.will_locked,
+        emergency_contact_pubkey: inheritance.emergency_contact_pubkey.clone(),
+        emergency_extension_used: inheritance.emergency_extension_used,
+        grace_period: inheritance.grace_period.clone(),
+        allowed_missed_checkins: inheritance.allowed_missed_checkins,
+        missed_count: inheritance.missed_count,
+        court_pubkey: inheritance.court_pubkey,
+        guardian_emergency_delay: inheritance.guardian_emergency_delay,
+        checkin_window_blocks: inheritance.checkin_window_blocks,
+    }
+}
+
+/// Checks that `owner_pubkey` is the key committed to in a redacted contract
+///
+/// The commitment is over the x-only key, so it matches whether the contract
+/// stored the owner key in x-only or compressed form.
+pub fn verify_owner_commitment(redacted: &RedactedInheritanceContent, owner_pubkey: &OwnerPubkey) -> bool {
+    let commitment = B32(Sha256::digest(xonly_bytes(&owner_pubkey.0)).into());
+    commitment == redacted.owner_pubkey_commitment
+}
@@ -0,0 +1,46 @@
+//! The contract's terms, written out for an estate attorney
+//!
+//! Nothing here is part of the on-chain contract.
+
+use crate::disclosure::redact_owner;
+use crate::{blocks_to_duration_approx, InheritanceContent, SharePpm};
+
+/// Renders the contract's terms as an excerpt for an estate attorney's document
+///
+/// Four sections, each a heading followed by indented "Label: value" lines, and
+/// the distribution as a "|"-separated table. The owner appears only as the
+/// commitment redact_owner publishes. The content doesn't know the app identity
+/// it's held under, so the contract is identified by its content_hash, which
+/// changes with every check-in.
+pub fn format_contract_for_legal_document(inheritance: &InheritanceContent) -> String {
+    let grantor = redact_owner(inheritance).owner_pubkey_commitment;
+    let delay_days = blocks_to_duration_approx(inheritance.trigger_delay_blocks).as_secs() / 86_400;
+
+    let mut lines = vec![
+        "Grantor".to_string(),
+        format!("  Owner key commitment: {grantor}"),
+        String::new(),
+        "Trigger Conditions".to_string(),
+        format!(
+            "  Delay: {} blocks (approximately {delay_days} days) without a check-in",
+            inheritance.trigger_delay_blocks
+        ),
+        format!("  Last check-in: block {}", inheritance.last_checkin_block),
+        format!("  Network: {:?}", inheritance.network),
+        String::new(),
+        "Distribution".to_string(),
+        "  # | Beneficiary | Allocation".to_string(),
+    ];
+    for (index, beneficiary) in inheritance.beneficiaries.iter().enumerate() {
+        let allocation = match beneficiary.allocation.share_ppm() {
+            Some(share_ppm) => SharePpm(share_ppm).to_string(),
+            None => format!("{} sats", beneficiary.allocation.fixed_sats().unwrap_or(0)),
+        };
+        lines.push(format!("  {} | {} | {allocation}", index + 1, beneficiary.payee.label()));
+    }
+    lines.push(String::new());
+    lines.push("Contract ID".to_string());
+    lines.push(format!("  Content hash: {}", inheritance.content_hash()));
+
+    lines.join("\n") + "\n"
+}
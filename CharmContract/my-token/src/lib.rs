@@ -1,346 +1,9353 @@
 use charms_sdk::data::{
-    charm_values, check, App, Data, Transaction, UtxoId, B32, NFT,
+    charm_values, check, App, Data, NativeOutput, Transaction, UtxoId, B32, NFT,
 };
+use k256::elliptic_curve::sec1::ToEncodedPoint;
+use k256::elliptic_curve::PrimeField;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet};
+
+pub mod allocation;
+pub mod bip32;
+pub mod bolt12;
+pub mod chain;
+pub mod diff;
+pub mod disclosure;
+pub mod legal;
+pub mod liveness;
+#[cfg(feature = "wallet")]
+pub mod psbt;
+pub mod qr;
+pub mod scan;
+pub mod schema;
+pub mod simulator;
+pub mod snapshot;
+pub mod storage;
+pub mod validation_rules;
+#[cfg(feature = "watcher")]
+pub mod watcher;
+
+use bip32::{hash160, p2wpkh_script, ExtendedPubKey, HARDENED_INDEX};
+use bolt12::Offer;
+use validation_rules::{validate_trigger_delay_against_network, DelayValidationError, RuleSet};
+
+/// Schema version written into new inheritance content
+///
+/// Version 0 is the original layout, which predates the `version` key.
+/// Version 2 replaced whole-percent shares with basis points.
+/// Version 3 replaced share_bps with an Allocation, so entries can be fixed amounts.
+pub const CONTENT_VERSION: u8 = 3;
+
+/// Upper bound on the serialized size of an inheritance NFT's content, in bytes
+pub const MAX_CONTENT_BYTES: usize = 4096;
+
+/// Maximum length of a beneficiary address string (backs MAX_CONTENT_BYTES per field)
+pub const MAX_ADDRESS_LEN: usize = 100;
+
+/// Maximum length of a raw beneficiary scriptPubKey (Bitcoin's consensus script size limit)
+pub const MAX_SCRIPT_LEN: usize = 10_000;
+
+/// The share representing the whole inheritance, in basis points (100% = 10000 bps)
+pub const FULL_SHARE: u16 = 10_000;
+
+/// The whole inheritance in parts per million, for shares finer than a basis point
+pub const FULL_SHARE_PPM: u32 = 1_000_000;
+
+/// Tag of InheritanceContent::content_hash
+pub const CONTENT_HASH_TAG: &[u8] = b"charmvault/content";
+
+/// Tag of plan_commitment
+pub const PLAN_COMMITMENT_TAG: &[u8] = b"charmvault/plan";
+
+/// Tag of hash_beneficiary_list
+pub const BENEFICIARY_LIST_TAG: &[u8] = b"charmvault/beneficiaries";
+
+/// Outputs below this many sats are non-standard dust and can't be relayed
+pub const DUST_THRESHOLD_SATS: u64 = 546;
+
+/// Most guardians a contract may list
+pub const MAX_GUARDIANS: usize = 15;
+
+/// Most metadata entries a contract may carry
+pub const MAX_METADATA_ENTRIES: usize = 8;
+
+/// Longest metadata key, in bytes (values are bounded by the rule set's max_note_length)
+pub const MAX_METADATA_KEY_LEN: usize = 32;
+
+/// Most validation work a single transaction may ask for (see validation_cost)
+///
+/// Comfortably above the largest legitimate transaction: two contents at the
+/// beneficiary and guardian limits, each paired with a few hundred outputs and
+/// witness entries.
+pub const MAX_VALIDATION_COST: u64 = 250_000;
+
+/// Upper bound on the serialized size of a transaction's witness, in bytes
+///
+/// Checked before the witness is decoded, so an oversized one costs nothing more
+/// than measuring it. A few hundred signatures or derived keys fit comfortably.
+pub const MAX_WITNESS_BYTES: usize = 64 * 1024;
+
+/// Blocks an emergency contact's one-time extension adds to the last check-in (≈ 30 days)
+pub const EMERGENCY_EXTENSION_BLOCKS: u64 = 4320;
 
 // Represents the current state of an inheritance contract
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub enum InheritanceStatus {
+    #[default]
     Active,       // Owner is alive, can check-in and update
     Triggered,    // Deadline passed, ready for distribution
     Distributed,  // Already distributed to beneficiaries (final state)
 }
 
-// Represents one beneficiary who will inherit BTC
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Beneficiary {
-    pub address: String,    // Bitcoin address to receive inheritance
-    pub percentage: u8,     // Percentage of total (0-100)
-}
+impl InheritanceStatus {
+    /// Every status, in lifecycle order
+    pub const ALL: [InheritanceStatus; 3] = [
+        InheritanceStatus::Active,
+        InheritanceStatus::Triggered,
+        InheritanceStatus::Distributed,
+    ];
 
-// The main inheritance contract - stored in the NFT charm
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct InheritanceContent {
-    pub owner_pubkey: String,              // Owner's public key (for authentication)
-    pub last_checkin_block: u64,           // Block height of last check-in
-    pub trigger_delay_blocks: u64,         // Blocks to wait before triggering (e.g., 4320 ≈ 30 days)
-    pub beneficiaries: Vec<Beneficiary>,   // List of beneficiaries with percentages
-    pub status: InheritanceStatus,         // Current state (enum, not string!)
-}
+    /// Whether the contract is over: nothing can be done with it any more
+    pub fn is_terminal(&self) -> bool {
+        matches!(self, InheritanceStatus::Distributed)
+    }
 
-/// Main entry point for the inheritance contract
-/// Called by Charms SDK to validate every transaction that spends an inheritance charm
-///
-/// Returns true if the transaction is valid (one of the 4 operations succeeds)
-/// Returns false if the transaction violates the contract rules
-pub fn app_contract(app: &App, tx: &Transaction, x: &Data, w: &Data) -> bool {
-    // We don't use public inputs for now, so they must be empty
-    let empty = Data::empty();
-    assert_eq!(x, &empty);
-
-    match app.tag {
-        NFT => {
-            // A transaction is valid if ANY of these operations is satisfied:
-            check!(
-                can_create_inheritance(app, tx, w) ||      // 1. Create new inheritance
-                can_checkin(app, tx) ||                    // 2. Owner extends deadline
-                can_update_beneficiaries(app, tx) ||       // 3. Owner modifies beneficiaries
-                can_trigger_distribution(app, tx)          // 4. Distribute to beneficiaries
-            )
-        }
-        _ => {
-            // Only NFT tag is supported for inheritance contracts
-            eprintln!("Unsupported app tag: {:?}", app.tag);
-            return false;
+    /// Lowercase name used by Display and FromStr (the on-chain encoding is the variant name)
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            InheritanceStatus::Active => "active",
+            InheritanceStatus::Triggered => "triggered",
+            InheritanceStatus::Distributed => "distributed",
         }
     }
-    true
 }
 
-//
-// ==================== OPERATION 1: CREATE INHERITANCE ====================
-//
-
-/// Validates creating a new inheritance contract
+/// Whether a contract may move from one status to the next
 ///
-/// Requirements:
-/// - Witness data (w) must contain a UTXO ID, and its hash becomes the app identity
-/// - That UTXO must be spent in this transaction (prevents replay attacks)
-/// - Exactly one NFT must be created in the outputs
-/// - The NFT must have valid InheritanceContent structure
-/// - Beneficiary percentages must sum to 100
-fn can_create_inheritance(app: &App, tx: &Transaction, w: &Data) -> bool {
-    // Extract witness data (should be a UTXO ID string)
-    let w_str: Option<String> = w.value().ok();
-    check!(w_str.is_some());
-    let w_str = w_str.unwrap();
-
-    // Verify the hash of witness data matches the app identity
-    // This ties the inheritance to a specific UTXO
-    check!(hash(&w_str) == app.identity);
+/// `from` is None for a contract being created, which must start Active. Statuses
+/// only move forward: Active can stay Active, be triggered by a partial distribution
+/// or be distributed outright, and Triggered can only stay Triggered or finish.
+pub fn valid_transition(from: Option<&InheritanceStatus>, to: &InheritanceStatus) -> bool {
+    use InheritanceStatus::*;
+    matches!(
+        (from, to),
+        (None, Active)
+            | (Some(Active), Active | Triggered | Distributed)
+            | (Some(Triggered), Triggered | Distributed)
+    )
+}
 
-    // Verify that the UTXO referenced in witness is being spent
-    // This prevents someone from reusing the same witness data
-    let w_utxo_id = UtxoId::from_str(&w_str).unwrap();
-    check!(tx.ins.iter().any(|(utxo_id, _)| utxo_id == &w_utxo_id));
+impl std::fmt::Display for InheritanceStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
 
-    // Get all NFT charms in the outputs
-    let nft_charms = charm_values(app, tx.outs.iter()).collect::<Vec<_>>();
+impl std::str::FromStr for InheritanceStatus {
+    type Err = ParseStatusError;
 
-    // Must create exactly one inheritance NFT
-    check!(nft_charms.len() == 1);
+    /// Parses a status name, ignoring ASCII case
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        InheritanceStatus::ALL
+            .into_iter()
+            .find(|status| status.as_str().eq_ignore_ascii_case(s))
+            .ok_or_else(|| ParseStatusError(s.to_string()))
+    }
+}
 
-    // Verify the NFT has correct structure
-    let inheritance: Result<InheritanceContent, _> = nft_charms[0].value();
-    check!(inheritance.is_ok());
-    let inheritance = inheritance.unwrap();
+// A string that doesn't name any InheritanceStatus
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseStatusError(pub String);
 
-    // Validate business logic
-    check!(validate_inheritance(&inheritance));
+impl std::fmt::Display for ParseStatusError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "unknown inheritance status: {:?}", self.0)
+    }
+}
 
-    true
+// The Bitcoin network a contract lives on
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub enum Network {
+    Mainnet,
+    #[default]
+    Testnet,   // Testnet3 or Testnet4; contracts written before this field are read as testnet
+    Signet,
+    Regtest,
 }
 
+// How much of the vault a beneficiary receives
 //
-// ==================== OPERATION 2: CHECK-IN (EXTEND DEADLINE) ====================
+// Fixed amounts are paid first, in list order; shares split whatever is left after them.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub enum Allocation {
+    Share(u16),   // Share of the remainder in basis points (0-10000)
+    Fixed(u64),   // Exact amount in sats
+    PreciseShare(SharePpm),  // Share of the remainder in parts per million
+}
+
+// A share in parts per million (1_000_000 = 100%)
 //
+// For splits basis points can't express, like thirds. Shares of either precision
+// can be mixed in one contract: basis points count as 100 ppm each.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[serde(transparent)]
+pub struct SharePpm(pub u32);
 
-/// Validates a check-in operation (owner extending the deadline)
-///
-/// Requirements:
-/// - Must have exactly 1 input NFT and 1 output NFT
-/// - Input status must be Active
-/// - Output status must remain Active
-/// - last_checkin_block must be updated (increased)
-/// - All other fields must remain unchanged
-fn can_checkin(app: &App, tx: &Transaction) -> bool {
-    // Get input inheritance state
-    let input_charms: Vec<_> = charm_values(app, tx.ins.iter().map(|(_, v)| v)).collect();
-    check!(input_charms.len() == 1);
+impl SharePpm {
+    /// The same share as `bps` basis points
+    pub fn from_bps(bps: u16) -> Self {
+        SharePpm(bps as u32 * 100)
+    }
 
-    let input_inheritance: Result<InheritanceContent, _> = input_charms[0].value();
-    check!(input_inheritance.is_ok());
-    let input_inheritance = input_inheritance.unwrap();
+    /// A human percentage (e.g. 33.3333), rounded to the nearest ppm; None outside 0-100%
+    pub fn from_percent(percent: f64) -> Option<Self> {
+        if !(0.0..=100.0).contains(&percent) {
+            return None;
+        }
+        Some(SharePpm((percent * 10_000.0).round() as u32))
+    }
 
-    // Must be in Active status to check-in
-    check!(input_inheritance.status == InheritanceStatus::Active);
+    /// The share as a human percentage
+    pub fn to_percent(self) -> f64 {
+        self.0 as f64 / 10_000.0
+    }
+}
 
-    // Get output inheritance state
-    let output_charms: Vec<_> = charm_values(app, tx.outs.iter()).collect();
-    check!(output_charms.len() == 1);
+impl std::fmt::Display for SharePpm {
+    /// Exact percentage, e.g. "33.3334%"
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let whole = self.0 / 10_000;
+        let fraction = self.0 % 10_000;
+        if fraction == 0 {
+            write!(f, "{whole}%")
+        } else {
+            let fraction = format!("{fraction:04}");
+            write!(f, "{whole}.{}%", fraction.trim_end_matches('0'))
+        }
+    }
+}
 
-    let output_inheritance: Result<InheritanceContent, _> = output_charms[0].value();
-    check!(output_inheritance.is_ok());
-    let output_inheritance = output_inheritance.unwrap();
+// How shares of the vault are rounded to whole sats
+//
+// Either way the amounts add up to exactly what's being split.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub enum RoundingMode {
+    #[default]
+    FloorFirst,         // Round every share down; the first entry takes the leftover (the original behaviour)
+    LargestRemainder,   // Round down, then hand the leftover out a sat at a time, largest fraction first
+}
 
-    // Output must also be Active
-    check!(output_inheritance.status == InheritanceStatus::Active);
+impl RoundingMode {
+    /// Whether this is the default mode (which is left out of serialized content)
+    pub fn is_default(&self) -> bool {
+        *self == RoundingMode::default()
+    }
+}
 
-    // last_checkin_block must be updated (owner proved they're alive)
-    check!(output_inheritance.last_checkin_block > input_inheritance.last_checkin_block);
+// Where a beneficiary's inheritance is paid
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[serde(untagged)]
+pub enum Payee {
+    Address(String),      // A fixed Bitcoin address (the original format)
+    Xpub(XpubPayee),      // A fresh address derived at distribution time
+    Script(ScriptPayee),  // A raw scriptPubKey, for outputs with no address form
+    Committed(CommittedPayee),  // An address kept private until distribution
+    Lightning(LightningPayee),  // A BOLT12 offer, settled off-chain
+}
 
-    // All other fields must remain unchanged
-    check!(output_inheritance.owner_pubkey == input_inheritance.owner_pubkey);
-    check!(output_inheritance.trigger_delay_blocks == input_inheritance.trigger_delay_blocks);
-    check!(beneficiaries_equal(&output_inheritance.beneficiaries, &input_inheritance.beneficiaries));
+// A beneficiary who'd rather be paid over Lightning
+//
+// The contract can't see a Lightning payment, so the share is paid on-chain to
+// settlement_script: whoever the heir trusts to pay the offer (their LSP, or an
+// executor). The offer itself is only checked for being well-formed.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[serde(deny_unknown_fields)]
+pub struct LightningPayee {
+    pub offer: String,                // BOLT12 offer (lno1...)
+    pub settlement_script: Vec<u8>,   // Paid on-chain by the distribution, and settles the offer
+}
 
-    true
+// A beneficiary whose address is only committed to on-chain
+//
+// The distribution witness reveals (address, salt) and the contract checks it against
+// the commitment. If the address is never revealed, the share goes to fallback_address,
+// so an heir who lost their salt (or a triggerer who withholds it) can't lock the vault.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[serde(deny_unknown_fields)]
+pub struct CommittedPayee {
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = arbitrary_b32))]
+    #[cfg_attr(feature = "schemars", schemars(with = "String"))]
+    pub commitment: B32,          // commit_payee(address, salt)
+    pub fallback_address: String, // Paid instead when the address isn't revealed
+}
+
+// A beneficiary paid directly to a scriptPubKey (e.g. a P2WSH multisig)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[serde(deny_unknown_fields)]
+pub struct ScriptPayee {
+    pub script_pubkey: Vec<u8>,
 }
 
+// A beneficiary identified by an extended public key instead of a single address
 //
-// ==================== OPERATION 3: UPDATE BENEFICIARIES ====================
+// At distribution the witness names a child index in [first_index, last_index] and
+// the contract checks the paid script is the P2WPKH of xpub/index.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[serde(deny_unknown_fields)]
+pub struct XpubPayee {
+    pub xpub: String,       // Base58 xpub/tpub of the receive chain (e.g. m/84'/0'/0'/0)
+    pub first_index: u32,   // Lowest child index the heir will accept
+    pub last_index: u32,    // Highest child index (must be non-hardened)
+}
+
+// Represents one beneficiary who will inherit BTC
 //
+// Ordered by payee, then allocation, then vesting delay, so a list can be sorted
+// into a canonical form before it's hashed or signed. List order still matters to
+// the contract (fixed amounts are paid in order), so only sort a copy.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[serde(deny_unknown_fields)]
+pub struct Beneficiary {
+    #[serde(rename = "address")]
+    pub payee: Payee,           // Where the inheritance is paid
+    pub allocation: Allocation, // Fixed amount or share of the remainder
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub vesting_delay_blocks: u64, // Blocks after the trigger before this beneficiary can be paid
+}
 
-/// Validates updating the beneficiaries list
-///
-/// Requirements:
-/// - Must have exactly 1 input NFT and 1 output NFT
-/// - Input status must be Active
-/// - Output status must remain Active
-/// - Beneficiaries can be modified
-/// - New beneficiaries must be valid (percentages sum to 100)
-/// - last_checkin_block should be updated (to extend deadline)
-/// - owner_pubkey and trigger_delay_blocks must remain unchanged
-fn can_update_beneficiaries(app: &App, tx: &Transaction) -> bool {
-    // Get input inheritance state
-    let input_charms: Vec<_> = charm_values(app, tx.ins.iter().map(|(_, v)| v)).collect();
-    check!(input_charms.len() == 1);
+// Beneficiary as written in version 2: every entry a basis-point share
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct BeneficiaryV2 {
+    pub address: String,
+    pub share_bps: u16,
+}
 
-    let input_inheritance: Result<InheritanceContent, _> = input_charms[0].value();
-    check!(input_inheritance.is_ok());
-    let input_inheritance = input_inheritance.unwrap();
+// Beneficiary as written before basis points: whole-percent shares
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct BeneficiaryV1 {
+    pub address: String,
+    pub percentage: u8,     // Percentage of total (0-100)
+}
 
-    // Must be in Active status to update
-    check!(input_inheritance.status == InheritanceStatus::Active);
+// The main inheritance contract - stored in the NFT charm
+//
+// Decoding is strict: unknown keys are rejected so two parties can't read different
+// contracts out of the same payload. New fields must be added with #[serde(default)],
+// and the default must mean the feature is off, so older payloads still decode and
+// behave as they did (tests/compat.rs holds minted payloads that must keep working).
+// Payloads written by a newer schema are rejected until this contract is upgraded
+// to understand them.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[serde(deny_unknown_fields)]
+pub struct InheritanceContent {
+    pub owner_pubkey: String,              // Owner's public key (for authentication)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub co_signers: Vec<String>,           // Co-signers' public keys (same encodings as owner_pubkey)
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub multisig_threshold: u8,            // Check-in signatures needed from the owner and co-signers (0 = the owner's alone)
+    pub last_checkin_block: u64,           // Block height of last check-in
+    pub trigger_delay_blocks: u64,         // Blocks to wait before triggering (e.g., 4320 ≈ 30 days)
+    pub beneficiaries: Vec<Beneficiary>,   // List of beneficiaries with their shares
+    pub status: InheritanceStatus,         // Current state (enum, not string!)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delay_decay: Option<DelayDecay>,   // Optional: shrink the delay while the owner stays silent
+    #[serde(default)]
+    pub version: u8,                       // Schema version (0 = written before versioning)
+    #[serde(default, skip_serializing_if = "RuleSet::is_default")]
+    pub rule_set: RuleSet,                 // Limits this contract is validated against
+    #[serde(default)]
+    pub total_locked_sats: u64,            // Sats locked in the vault, as declared at creation
+    #[serde(default)]
+    pub network: Network,                  // Network the vault lives on (bounds the trigger delay)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trigger_height: Option<u64>,       // Block the vault was triggered at (set by the first partial distribution)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fallback_address: Option<String>,  // Where funds no beneficiary can take end up
+    #[serde(default)]
+    pub created_at_block: u64,             // Block height the vault was created at (0 if created before this field)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub guardians: Vec<String>,            // Guardians' public keys (same encodings as owner_pubkey)
+    #[serde(default)]
+    pub guardian_threshold: u8,            // Guardian signatures needed for a quorum (0 without guardians)
+    #[serde(default, skip_serializing_if = "RoundingMode::is_default")]
+    pub rounding_mode: RoundingMode,       // How share amounts are rounded at distribution
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub metadata: BTreeMap<String, String>, // Free-text notes for the heirs (sorted, so serialization is deterministic)
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub leave_tombstone: bool,             // Final distribution leaves a DistributionTombstone instead of burning the NFT
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub policy_max_gap: u64,               // Longest gap between check-ins compliance tools accept (0 = no policy)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recovery_mode: Option<ContractRecoveryMode>, // How heirs can recover the contract if the owner's wallet is lost
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub min_expected_funding: u64,         // Least the owner expects to lock; every share must be payable from it (0 = no policy)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub oracle_pubkey: Option<String>,     // Proof-of-life service whose attestation also counts as a check-in
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = arbitrary_option_b32))]
+    #[cfg_attr(feature = "schemars", schemars(with = "Option<String>"))]
+    pub will_document_hash: Option<B32>,   // Commitment to the off-chain will (e.g. its SHA-256); never revealed on-chain
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub will_locked: bool,                 // will_document_hash can no longer change (set once, never cleared)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub emergency_contact_pubkey: Option<String>, // May push the deadline out once while the owner can't check in
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub emergency_extension_used: bool,    // The emergency contact has used their extension (never reset)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub grace_period: Option<GracePeriod>, // Optional: extra blocks after the deadline before distribution
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub allowed_missed_checkins: u8,       // Lapsed deadlines recorded before distribution is allowed (0 = the first lapse)
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub missed_count: u8,                  // Lapses recorded since the last check-in (see check_record_miss)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[cfg_attr(feature = "schemars", schemars(with = "Option<Vec<u8>>"))]
+    pub court_pubkey: Option<OwnerPubkey>, // Court that may freeze the contract pending legal proceedings
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub guardian_emergency_delay: u64,     // Silence after which a guardian quorum may distribute early (0 = never)
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub checkin_window_blocks: u64,        // Check-ins only count this close to the deadline (0 = any time)
+}
 
-    // Get output inheritance state
-    let output_charms: Vec<_> = charm_values(app, tx.outs.iter()).collect();
-    check!(output_charms.len() == 1);
+// Frozen copy of the version 2 layout: basis-point shares only
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct InheritanceContentV2 {
+    pub owner_pubkey: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub co_signers: Vec<String>,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub multisig_threshold: u8,
+    pub last_checkin_block: u64,
+    pub trigger_delay_blocks: u64,
+    pub beneficiaries: Vec<BeneficiaryV2>,
+    pub status: InheritanceStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delay_decay: Option<DelayDecay>,
+    #[serde(default)]
+    pub version: u8,
+    #[serde(default, skip_serializing_if = "RuleSet::is_default")]
+    pub rule_set: RuleSet,
+}
 
-    let output_inheritance: Result<InheritanceContent, _> = output_charms[0].value();
-    check!(output_inheritance.is_ok());
-    let output_inheritance = output_inheritance.unwrap();
+// Frozen copy of the version 1 layout (and the version 0 layout, which only lacks `version`)
+// Shares are whole percentages; migration scales them to basis points.
+//
+// Kept separate from InheritanceContent so that adding fields to the latest layout
+// never changes how already-minted vaults decode.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct InheritanceContentV1 {
+    pub owner_pubkey: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub co_signers: Vec<String>,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub multisig_threshold: u8,
+    pub last_checkin_block: u64,
+    pub trigger_delay_blocks: u64,
+    pub beneficiaries: Vec<BeneficiaryV1>,
+    pub status: InheritanceStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delay_decay: Option<DelayDecay>,
+    #[serde(default)]
+    pub version: u8,
+}
 
-    // Output must also be Active
-    check!(output_inheritance.status == InheritanceStatus::Active);
+// Every content layout that has ever been minted, newest first
+//
+// Fields added with #[serde(default)] don't need a new variant: older payloads
+// still decode as Latest. Older variants only matter once a change can't be
+// expressed that way.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(untagged)]
+pub enum VersionedContent {
+    Latest(Box<InheritanceContent>),  // Boxed: it's several times the size of the frozen layouts
+    V2(InheritanceContentV2),
+    V1(InheritanceContentV1),  // Versions 0 and 1
+}
 
-    // Validate new beneficiaries
-    check!(validate_beneficiaries(&output_inheritance.beneficiaries));
+// The operations a transaction can perform on an inheritance contract
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Operation {
+    Create = 1,
+    Checkin = 2,
+    UpdateBeneficiaries = 3,
+    TriggerDistribution = 4,
+    Migrate = 5,
+    OracleCheckin = 6,
+    EmergencyExtend = 7,
+    RecordMiss = 8,
+    CourtFreeze = 9,
+    CourtUnfreeze = 10,
+    GuardianEmergencyTrigger = 11,
+}
 
-    // Core fields must remain unchanged
-    check!(output_inheritance.owner_pubkey == input_inheritance.owner_pubkey);
-    check!(output_inheritance.trigger_delay_blocks == input_inheritance.trigger_delay_blocks);
+// Why an NFT's content couldn't be brought up to the latest layout
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MigrationError {
+    Undecodable,              // Payload doesn't match any known layout
+    UnsupportedVersion(u8),   // Payload claims a version this contract doesn't know
+}
 
-    // last_checkin_block should be updated (acts as check-in too)
-    check!(output_inheritance.last_checkin_block >= input_inheritance.last_checkin_block);
+// Witness for migrating a vault to the latest layout
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct MigrateWitness {
+    pub signature: Vec<u8>,   // Owner's BIP-340 signature over signing_message(Migrate, ..)
+}
 
-    true
+// Witness for changing the beneficiaries and the other owner-managed settings
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct UpdateBeneficiariesWitness {
+    pub signature: Vec<u8>,   // Owner's BIP-340 signature over signing_message(UpdateBeneficiaries, ..)
 }
 
+// What a final distribution leaves on-chain when the contract asks for a tombstone
 //
-// ==================== OPERATION 4: TRIGGER DISTRIBUTION ====================
+// Small on purpose: the whole record of who got what is the commitment to the plan,
+// which anyone holding the contract can recompute. The tombstone can never be spent
+// again, so its output should carry no more than dust.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct DistributionTombstone {
+    pub status: InheritanceStatus,   // Always Distributed
+    pub distributed_sats: u64,       // Vault value the final plan was computed from
+    pub plan_commitment: B32,        // plan_commitment of that plan
+}
+
+// A contract a court has frozen pending legal proceedings (see apply_court_freeze)
 //
+// Takes the NFT's place until the court lifts the freeze: nothing but
+// check_unfreeze accepts it, and unfreezing restores `inner` as it was.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct FrozenInheritanceContent {
+    pub inner: InheritanceContent,   // The contract as it was when frozen
+    pub freeze_reason_hash: B32,     // Commitment to the court order (e.g. its SHA-256)
+    pub frozen_at_block: u64,        // Block height the freeze took effect at
+}
 
-/// Validates triggering the inheritance distribution
-///
-/// Requirements:
-/// - Must have exactly 1 input NFT
-/// - Input status must be Active or Triggered
-/// - Deadline must have passed (current block > last_checkin + delay)
-/// - Must create outputs for each beneficiary with correct amounts
-/// - NFT is burned (no NFT in outputs)
-fn can_trigger_distribution(app: &App, tx: &Transaction) -> bool {
-    // Get input inheritance state
-    let input_charms: Vec<_> = charm_values(app, tx.ins.iter().map(|(_, v)| v)).collect();
-    check!(input_charms.len() == 1);
+// Derived facts about a contract at a given height, for wallets and UIs
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ContractSummary {
+    pub status: InheritanceStatus,
+    pub effective_delay_blocks: u64,   // Delay in effect at current_block (after any decay)
+    pub deadline_block: u64,           // Triggerable once the chain is past this height
+    pub blocks_until_deadline: u64,    // 0 once the deadline has been reached
+    pub is_triggerable: bool,          // Deadline passed and not yet distributed
+    pub total_share_bps: u32,          // Sum of share allocations, in basis points
+    pub beneficiary_count: usize,
+}
 
-    let input_inheritance: Result<InheritanceContent, _> = input_charms[0].value();
-    check!(input_inheritance.is_ok());
-    let inheritance = input_inheritance.unwrap();
+// What each beneficiary would receive after an inheritance tax, for estate planning
+//
+// Each list is in beneficiary order, keyed by Payee::label.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TaxEstimate {
+    pub gross_per_beneficiary: Vec<(String, u64)>,  // What the distribution pays them
+    pub tax_per_beneficiary: Vec<(String, u64)>,    // The tax on that (rounded down)
+    pub net_per_beneficiary: Vec<(String, u64)>,    // What they keep
+    pub total_tax_sats: u64,
+}
 
-    // Must be Active or Triggered (not already Distributed)
-    check!(
-        inheritance.status == InheritanceStatus::Active ||
-        inheritance.status == InheritanceStatus::Triggered
-    );
+// How pressing a check-in reminder is, by the share of the trigger delay left
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ReminderUrgency {
+    Routine,   // 50-75% of the delay left
+    Soon,      // 25-50% left
+    Urgent,    // 10-25% left
+    Critical,  // Under 10% left, or already past the deadline
+}
 
-    // TODO: Verify deadline has passed
-    // This requires getting current block height from witness data
-    // For now, we allow distribution anytime (will add block height check later)
+// Structured reminder data for email/push notification services
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CheckinReminder {
+    pub urgency: ReminderUrgency,
+    pub blocks_until_deadline: u64,
+    pub suggested_checkin_block: u64,   // Check in by this height to stay comfortably clear
+    pub message: String,
+}
 
-    // Verify no NFT in outputs (NFT is burned)
-    let output_charms: Vec<_> = charm_values(app, tx.outs.iter()).collect();
-    check!(output_charms.is_empty());
+// How an owner's check-in history measures up to the contract's policy_max_gap
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CheckinPolicyResult {
+    pub compliant: bool,                 // No gap longer than policy_max_gap (always true without a policy)
+    pub longest_gap_blocks: u64,
+    pub average_gap_blocks: f64,         // 0 with fewer than two check-ins
+    pub recommended_trigger_delay: u64,  // Longest gap plus half again, within the network's bounds
+}
 
-    // TODO: Verify outputs match beneficiaries
-    // This requires checking that:
-    // 1. Number of outputs matches number of beneficiaries
-    // 2. Each output amount = total_input * beneficiary_percentage / 100
-    // We'll implement this validation in the next iteration
+// How the heirs can recover a contract from on-chain data alone once the owner's wallet is lost
+//
+// Only BeneficiaryMajority changes what this contract accepts: the heirs holding
+// enough of the estate can trigger distribution before the deadline. The other
+// modes are recorded for recovery tools to act on.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub enum ContractRecoveryMode {
+    FullRecovery {
+        #[cfg_attr(feature = "schemars", schemars(with = "Vec<u8>"))]
+        owner_key: OwnerPubkey,        // Backup key that stands in for the owner's
+    },
+    BeneficiaryMajority {
+        threshold_basis_points: u16,   // Share of the estate whose heirs must sign (more than half)
+    },
+    CoSignerOverride,                  // The guardians act for the owner
+}
 
-    true
+// Dead man's switch with decreasing delay: every missed interval since the last
+// check-in shortens the effective trigger delay by one step, down to a floor
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[serde(deny_unknown_fields)]
+pub struct DelayDecay {
+    pub interval_blocks: u64,  // Blocks of silence that count as one missed interval
+    pub step_blocks: u64,      // Delay reduction per missed interval
+    pub floor_blocks: u64,     // The effective delay never drops below this
 }
 
+// Extra time after the deadline before the vault can be distributed, so an owner
+// who is briefly unreachable (e.g. ill) doesn't set off the distribution
 //
-// ==================== HELPER FUNCTIONS ====================
-//
+// The owner can still check in during the grace period; it only moves the point
+// at which distribution is allowed (see is_distributable).
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[serde(deny_unknown_fields)]
+pub struct GracePeriod {
+    pub extra_blocks: u64,                    // At most a quarter of trigger_delay_blocks
+    pub requires_medical_attestation: bool,   // Recorded for executors; the contract doesn't check attestations
+}
 
-/// Validates the inheritance structure
-fn validate_inheritance(inheritance: &InheritanceContent) -> bool {
-    // Status must be Active when creating
-    check!(inheritance.status == InheritanceStatus::Active);
+// Witness for a distribution: the concrete scripts paying xpub beneficiaries,
+// committed addresses being revealed, and the height vesting is checked against
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct DistributionWitness {
+    #[serde(default)]
+    pub derived: Vec<DerivedPayment>,
+    #[serde(default)]
+    pub revealed: Vec<RevealedPayee>,
+    #[serde(default)]
+    pub current_block: Option<u64>,   // Block height the distribution is made at (for vesting)
+    #[serde(default)]
+    pub beneficiary_signatures: Vec<Vec<u8>>,  // Beneficiary i's signature at i (empty if they didn't sign), for an early trigger
+    #[serde(default)]
+    pub guardian_signatures: Vec<(String, Vec<u8>)>,  // (guardian key, signature), for a guardian emergency trigger
+}
+
+// The opening of one committed beneficiary's address
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct RevealedPayee {
+    pub beneficiary_index: u32,   // Position in the beneficiaries list
+    pub address: String,
+    #[cfg_attr(feature = "schemars", schemars(with = "String"))]
+    pub salt: B32,
+}
 
-    // Validate beneficiaries
-    check!(validate_beneficiaries(&inheritance.beneficiaries));
+// The child key chosen for one xpub beneficiary
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct DerivedPayment {
+    pub beneficiary_index: u32,   // Position in the beneficiaries list
+    pub child_index: u32,         // Index derived from the beneficiary's xpub
+    pub script_pubkey: Vec<u8>,   // Script the distribution pays (P2WPKH of the child key)
+}
 
-    // Delay must be reasonable (at least 1 block)
-    check!(inheritance.trigger_delay_blocks > 0);
+// A contract rule that an inheritance NFT's content, or a transaction, violates
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ContractError {
+    ContentTooLarge { size: usize, max: usize },  // Serialized content exceeds the byte cap
+    MissingOutputNft,                             // The NFT isn't carried on to an output
+    ExtraOutputNfts { count: usize },             // More than one output carries the NFT
+    InvalidPublicInput,                           // The public input is neither empty nor a block height
+    UnsupportedTag,                               // The app isn't an NFT
+    InvalidWitness,                               // The witness doesn't name an operation
+    WitnessTooLarge { size: usize, max: usize },  // Serialized witness exceeds MAX_WITNESS_BYTES
+    TooCostly { cost: u64 },                      // More work than MAX_VALIDATION_COST
+    Frozen,                                       // Spends a frozen contract without lifting the freeze
+    Create(RuleFailed),                           // The first rule each operation's transaction broke
+    Checkin(RuleFailed),
+    OracleCheckin(RuleFailed),
+    UpdateBeneficiaries(RuleFailed),
+    TriggerDistribution(RuleFailed),
+    Migrate(RuleFailed),
+    EmergencyExtend(RuleFailed),
+    RecordMiss(RuleFailed),
+    CourtFreeze(RuleFailed),
+    CourtUnfreeze(RuleFailed),
+    GuardianEmergencyTrigger(RuleFailed),
+}
 
-    true
+impl ContractError {
+    /// The error for `operation` breaking `rule`
+    pub fn rule_failed(operation: Operation, rule: RuleFailed) -> Self {
+        match operation {
+            Operation::Create => ContractError::Create(rule),
+            Operation::Checkin => ContractError::Checkin(rule),
+            Operation::OracleCheckin => ContractError::OracleCheckin(rule),
+            Operation::UpdateBeneficiaries => ContractError::UpdateBeneficiaries(rule),
+            Operation::TriggerDistribution => ContractError::TriggerDistribution(rule),
+            Operation::Migrate => ContractError::Migrate(rule),
+            Operation::EmergencyExtend => ContractError::EmergencyExtend(rule),
+            Operation::RecordMiss => ContractError::RecordMiss(rule),
+            Operation::CourtFreeze => ContractError::CourtFreeze(rule),
+            Operation::CourtUnfreeze => ContractError::CourtUnfreeze(rule),
+            Operation::GuardianEmergencyTrigger => ContractError::GuardianEmergencyTrigger(rule),
+        }
+    }
 }
 
-/// Validates that beneficiaries list is correct
-fn validate_beneficiaries(beneficiaries: &[Beneficiary]) -> bool {
-    // Must have at least one beneficiary
-    check!(!beneficiaries.is_empty());
+// The rule of an operation a transaction broke (see evaluate)
+//
+// Shared between operations: ContractError says which operation it was, and each
+// operation's doc comment lists the rules it checks, in order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RuleFailed {
+    Witness,                   // The operation's own witness doesn't decode
+    IdentityMismatch,          // The funding UTXOs don't hash to the app identity
+    FundingNotSpent,           // A funding UTXO isn't spent by the transaction
+    AlreadyCreated,            // An input already carries the contract
+    AppIdentityInputs,         // Not exactly one input carries the app identity
+    InputNftCount,             // Not exactly one NFT spent
+    InputContent,              // The spent NFT's content doesn't decode
+    OutputNftCount,            // Not exactly one NFT carried on (or created)
+    OutputContent,             // The new NFT's content doesn't decode
+    OutputTooLarge,            // The new content exceeds the size cap
+    InvalidContent,            // The new content breaks a validation rule
+    InvalidBeneficiaries,      // The new beneficiary list breaks a validation rule
+    NotActive,                 // The contract isn't Active
+    OutputNotActive,           // The new content isn't Active
+    AlreadyDistributed,        // The contract was already distributed
+    NotEnabled,                // The contract doesn't allow the operation (no oracle, contact, court, ...)
+    AlreadyUsed,               // A one-time power was already spent
+    HeightNotAttested,         // The public input doesn't attest the height the operation needs
+    LastCheckinNotIncreased,   // last_checkin_block didn't move forward
+    CheckinInFuture,           // last_checkin_block is past the attested height
+    CreatedAtMismatch,         // created_at_block isn't the attested height
+    CheckinTooEarly,           // The check-in window hasn't opened yet
+    FrozenInFuture,            // frozen_at_block is past the attested height
+    MissesNotReset,            // missed_count wasn't reset to 0
+    FieldChanged,              // Something changed that the operation must leave alone
+    WillLocked,                // The locked will document changed
+    DeadlineNotPassed,         // Too early, and nobody who may trigger early signed off
+    DeadlinePassed,            // Too late: the contract is already distributable
+    Tombstone,                 // The tombstone is missing, unwanted or wrong
+    NotVested,                 // A beneficiary hasn't vested yet
+    Underpaid,                 // A beneficiary isn't paid their share
+    PartialDistribution,       // The remaining contract or its payouts are wrong
+    AlreadyLatest,             // The content is already in the latest layout
+    NotLatest,                 // The new content isn't in the latest layout
+    Signature,                 // The required signature (or quorum) is missing or invalid
+}
 
-    // Percentages must sum to 100
-    let total: u32 = beneficiaries.iter().map(|b| b.percentage as u32).sum();
-    check!(total == 100);
+// One rule diagnose checked, and whether the transaction kept it
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RuleCheck {
+    pub rule: RuleFailed,                  // The rule, named by how it's broken
+    pub passed: bool,
+    pub context: Vec<(String, String)>,    // Values it was checked against: (name, Debug form)
+}
 
-    // All addresses must be non-empty
-    check!(beneficiaries.iter().all(|b| !b.address.is_empty()));
+// Every rule of one operation, checked against a transaction
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct OperationReport {
+    pub operation: Operation,
+    pub claimed: bool,                 // The witness names this operation
+    pub broken: Option<RuleFailed>,    // The first rule broken: what evaluate reports for it
+    pub rules: Vec<RuleCheck>,         // In the order the operation checks them
+}
 
-    true
+// Why a transaction is or isn't valid, rule by rule (see diagnose)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ValidationReport {
+    pub outcome: Result<Operation, ContractError>,   // What evaluate returns
+    pub operations: Vec<OperationReport>,            // In DETECTION_ORDER; empty if the transaction fails before any operation
 }
 
-/// Checks if two beneficiary lists are equal
-fn beneficiaries_equal(a: &[Beneficiary], b: &[Beneficiary]) -> bool {
-    if a.len() != b.len() {
-        return false;
+// The first rule a new contract breaks (see InheritanceContent::validate)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValidationError {
+    NotActive,                                       // New content must be Active, with no trigger height
+    CreationHeightMismatch,                          // created_at_block differs from last_checkin_block
+    CheckinBeforeCreation,                           // last_checkin_block is before created_at_block
+    InvalidOwnerPubkey,                              // Not a usable secp256k1 key
+    InvalidCoSigner,                                 // A co-signer's key isn't a usable secp256k1 key
+    InconsistentRuleSet,                             // The rule set can't be satisfied
+    TooFewBeneficiaries { min: usize, provided: usize },
+    TooManyBeneficiaries { max: usize, provided: usize },
+    Beneficiary { index: usize, error: BeneficiaryError },
+    DuplicateAddress { index: usize },               // This entry pays an address an earlier one already pays
+    FixedExceedsVault,                               // Fixed amounts add up to more than total_locked_sats
+    SharesNotWhole,                                  // Shares don't sum to 100% (or fixed amounts alone don't fill the vault)
+    DustAtExpectedFunding { index: usize },          // This share would be dust at min_expected_funding
+    OwnerIsBeneficiary,                              // The rule set forbids paying the owner's own key
+    FallbackAddress(FallbackAddressError),
+    InvalidGuardians,                                // Bad or repeated key, or a threshold out of range
+    InvalidRecoveryMode,                             // See validate_recovery_mode
+    InvalidOraclePubkey,                             // Not a usable secp256k1 key
+    InvalidWillDocumentHash,                         // The all-zero hash commits to nothing
+    InvalidEmergencyContact,                         // Not a usable secp256k1 key, or the extension already used
+    InvalidMetadata,                                 // Too many entries, or a key or value too long
+    UnreachableMultisigThreshold,                    // More check-in signatures needed than there are signers
+    DelayBelowRuleSet { minimum: u64, provided: u64 },
+    Delay(DelayValidationError),                     // Out of the network's bounds
+    InvalidDelayDecay,                               // Zero interval or floor, or a floor above the delay
+    InvalidGracePeriod,                              // Longer than a quarter of the trigger delay
+    InvalidCheckinWindow,                            // Longer than the trigger delay
+    InvalidCourtPubkey,                              // Not a usable secp256k1 key
+    MissesRecorded,                                  // New content can't start with missed check-ins
+    Content(ContractError),                          // Too large once serialized
+}
+
+// Why a single beneficiary entry is unusable
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BeneficiaryError {
+    ShareAboveWhole,   // More than FULL_SHARE basis points
+    FixedBelowDust,    // A fixed amount too small to be paid as its own output
+    InvalidPayee,      // See validate_payee
+    UnsupportedAddress, // Not an address SupportedAddress knows on the contract's network
+}
+
+// Why a contract's fallback address isn't usable
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FallbackAddressError {
+    Malformed,       // Not an address we can pay to
+    WrongNetwork,    // An address for a different network than the contract's
+    IsBeneficiary,   // Pays the same script as one of the beneficiaries
+}
+
+// Why a contract couldn't be created from a hardware wallet's xpub
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum XpubError {
+    InvalidXpub,                  // Not a base58check xpub or tpub
+    DerivationFailed,             // Malformed path, a hardened step past the xpub, or an origin the xpub isn't at
+    Content(ValidationError),     // The derived key and the other arguments don't make a valid contract
+}
+
+// Identifies an inheritance contract: the identity of the app that owns its NFT
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct ContractId(pub B32);
+
+impl From<&App> for ContractId {
+    fn from(app: &App) -> Self {
+        ContractId(app.identity)
     }
+}
 
-    for i in 0..a.len() {
-        if a[i].address != b[i].address || a[i].percentage != b[i].percentage {
-            return false;
-        }
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for ContractId {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(ContractId(arbitrary_b32(u)?))
     }
+}
 
-    true
+#[cfg(feature = "arbitrary")]
+fn arbitrary_b32(u: &mut arbitrary::Unstructured) -> arbitrary::Result<B32> {
+    Ok(B32(u.arbitrary()?))
 }
 
-/// Hash function for creating app identity from UTXO ID
-pub(crate) fn hash(data: &str) -> B32 {
-    let hash = Sha256::digest(data);
-    B32(hash.into())
+#[cfg(feature = "arbitrary")]
+fn arbitrary_option_b32(u: &mut arbitrary::Unstructured) -> arbitrary::Result<Option<B32>> {
+    Ok(u.arbitrary::<Option<[u8; 32]>>()?.map(B32))
 }
 
-//
-// ==================== TESTS ====================
-//
+// Assembles a new contract's content one piece at a time; see InheritanceContent::builder
+#[derive(Debug, Clone)]
+pub struct InheritanceContentBuilder {
+    content: InheritanceContent,
+}
 
-#[cfg(test)]
-mod test {
-    use super::*;
+/// InheritanceContentBuilder, under the name wallet integrations use for it
+pub type ContractBuilder = InheritanceContentBuilder;
 
-    #[test]
-    fn test_hash() {
-        let utxo_id =
-            UtxoId::from_str("dc78b09d767c8565c4a58a95e7ad5ee22b28fc1685535056a395dc94929cdd5f:1")
-                .unwrap();
-        let data = dbg!(utxo_id.to_string());
-        let expected = "f54f6d40bd4ba808b188963ae5d72769ad5212dd1d29517ecc4063dd9f033faa";
-        assert_eq!(&hash(&data).to_string(), expected);
+// A compressed secp256k1 public key (33 bytes) identifying a signer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(try_from = "Vec<u8>", into = "Vec<u8>")]
+pub struct OwnerPubkey(pub [u8; 33]);
+
+impl TryFrom<Vec<u8>> for OwnerPubkey {
+    type Error = &'static str;
+
+    fn try_from(bytes: Vec<u8>) -> Result<Self, Self::Error> {
+        let bytes: [u8; 33] = bytes.try_into().map_err(|_| "pubkey must be 33 bytes")?;
+        Ok(OwnerPubkey(bytes))
     }
+}
 
-    #[test]
-    fn test_validate_beneficiaries_valid() {
-        let beneficiaries = vec![
-            Beneficiary {
-                address: "tb1p123".to_string(),
-                percentage: 60,
-            },
-            Beneficiary {
-                address: "tb1p456".to_string(),
-                percentage: 40,
-            },
-        ];
-        assert!(validate_beneficiaries(&beneficiaries));
+impl From<OwnerPubkey> for Vec<u8> {
+    fn from(pubkey: OwnerPubkey) -> Self {
+        pubkey.0.to_vec()
     }
+}
 
-    #[test]
-    fn test_validate_beneficiaries_invalid_sum() {
-        let beneficiaries = vec![
-            Beneficiary {
-                address: "tb1p123".to_string(),
-                percentage: 60,
-            },
-            Beneficiary {
-                address: "tb1p456".to_string(),
-                percentage: 50,  // Total = 110, should fail
-            },
-        ];
-        assert!(!validate_beneficiaries(&beneficiaries));
+// Generated keys always carry a valid compressed-key prefix (0x02 or 0x03)
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for OwnerPubkey {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        let mut bytes: [u8; 33] = u.arbitrary()?;
+        bytes[0] = if u.arbitrary::<bool>()? { 0x02 } else { 0x03 };
+        Ok(OwnerPubkey(bytes))
+    }
+}
+
+// Witness for creating an inheritance: the funding UTXO(s) whose hash becomes the app identity
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(untagged)]
+pub enum CreateWitness {
+    Single(String),      // One funding UTXO (the original format)
+    Multi(Vec<String>),  // Several UTXOs consolidated into the vault
+}
+
+// A witness as assembled off-chain, before it's encoded for the spell
+#[derive(Debug, Clone, PartialEq)]
+pub enum WitnessData {
+    Create(CreateWitness),
+    CheckIn {
+        signatures: Vec<(OwnerPubkey, Vec<u8>)>,  // (signer pubkey, signature bytes)
+        current_block: u64,                       // Block height the check-in is made at
+    },
+    Distribution(DistributionWitness),
+}
+
+// Why partial witnesses couldn't be merged
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WitnessError {
+    NoParts,                  // Nothing to merge
+    NotCheckIn,               // A part isn't a check-in witness
+    ConflictingBlockHeight,   // Two parts were made at different heights
+}
+
+// Witness for a multi-sig check-in: one signature per co-signer plus the current block height
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+struct MultisigCheckinWitness {
+    #[cfg_attr(feature = "schemars", schemars(with = "Vec<(Vec<u8>, Vec<u8>)>"))]
+    signatures: Vec<(OwnerPubkey, Vec<u8>)>,  // (signer pubkey, signature bytes)
+    current_block: u64,                       // Block height the check-in is made at
+}
+
+// Witness for an oracle check-in: the oracle's attestation that the owner is alive
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct OracleCheckinWitness {
+    pub attestation: Vec<u8>,   // Oracle's BIP-340 signature over signing_message(OracleCheckin, ..)
+    pub current_block: u64,     // Block height the check-in is made at
+}
+
+// Witness for an emergency extension: the contact's signature
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct EmergencyExtendWitness {
+    pub signature: Vec<u8>,   // Emergency contact's BIP-340 signature over signing_message(EmergencyExtend, ..)
+}
+
+// Witness for freezing a contract: the court's signature over the frozen content
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct CourtFreezeWitness {
+    pub court_freeze_sig: Vec<u8>,     // Court's BIP-340 signature over court_order_message(CourtFreeze, ..)
+}
+
+// Witness for lifting a freeze: the court's signature again
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct CourtUnfreezeWitness {
+    pub court_unfreeze_sig: Vec<u8>,   // Court's BIP-340 signature over court_order_message(CourtUnfreeze, ..)
+}
+
+// Witness as the spell carries it: the operation the transaction performs, and
+// that operation's own witness
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct OperationWitness {
+    pub operation: Operation,
+    pub witness: Data,
+}
+
+/// Main entry point for the inheritance contract
+/// Called by Charms SDK to validate every transaction that spends an inheritance charm
+///
+/// The witness names the operation (see operation_witness), and the transaction is
+/// checked against that operation alone.
+/// Returns true if the transaction is valid (the operation it claims succeeds)
+/// Returns false if the transaction violates the contract rules (see evaluate
+/// for which rule)
+///
+/// The public input is the block height the spell is made at (see
+/// block_height_input), or empty. Operations that depend on the height read it
+/// from there, never from the witness alone: a height the witness gives must
+/// match it, and without one they're rejected.
+pub fn app_contract(app: &App, tx: &Transaction, x: &Data, w: &Data) -> bool {
+    evaluate(app, tx, x, w).is_ok()
+}
+
+// check!, naming the rule that broke: returns Err($rule) unless $condition holds
+//
+// When diagnosing (see Rules), the rule is recorded instead and the check carries
+// on, along with any `name = value` pairs given after it; proving never evaluates
+// them. With the tracing feature, a broken rule is also reported as an event.
+macro_rules! rule {
+    ($rules:expr, $condition:expr, $rule:expr $(, $field:ident = $value:expr)* $(,)?) => {
+        check_rule!($rules, false, $condition, $rule $(, $field = $value)*)
+    };
+}
+
+// rule!, for a rule the ones after it depend on (e.g. a count checked before
+// indexing): even diagnosing stops when it's broken
+macro_rules! require {
+    ($rules:expr, $condition:expr, $rule:expr $(, $field:ident = $value:expr)* $(,)?) => {
+        check_rule!($rules, true, $condition, $rule $(, $field = $value)*)
+    };
+}
+
+macro_rules! check_rule {
+    ($rules:expr, $stop:expr, $condition:expr, $rule:expr $(, $field:ident = $value:expr)*) => {{
+        let passed = $condition;
+        #[cfg(feature = "tracing")]
+        if !passed {
+            tracing::debug!(rule = ?$rule, $($field = ?$value,)* "rule failed");
+        }
+        $rules.check($rule, passed, $stop, || vec![$((stringify!($field).to_string(), format!("{:?}", $value))),*])?;
+    }};
+}
+
+// How an operation's rules are checked
+//
+// Proving stops at the first broken rule. Diagnosing records every rule the
+// transaction gets to, and carries on past broken ones unless the rules after
+// them can't be checked without them (see require!).
+struct Rules {
+    checked: Option<Vec<RuleCheck>>,   // Every rule checked so far, when diagnosing
+    stopped: bool,                     // A require! rule was broken
+}
+
+impl Rules {
+    fn proving() -> Self {
+        Rules { checked: None, stopped: false }
+    }
+
+    fn diagnosing() -> Self {
+        Rules { checked: Some(Vec::new()), stopped: false }
+    }
+
+    /// Err(rule) where the check must stop: at any broken rule when proving, only
+    /// at a broken `stop` rule when diagnosing
+    fn check(
+        &mut self,
+        rule: RuleFailed,
+        passed: bool,
+        stop: bool,
+        context: impl FnOnce() -> Vec<(String, String)>,
+    ) -> Result<(), RuleFailed> {
+        let Some(checked) = &mut self.checked else {
+            return if passed { Ok(()) } else { Err(rule) };
+        };
+        checked.push(RuleCheck { rule, passed, context: context() });
+        if passed || !stop {
+            return Ok(());
+        }
+        self.stopped = true;
+        Err(rule)
+    }
+
+    /// Every rule checked, given how the operation's check ended
+    ///
+    /// Rules broken through `?` (content that doesn't decode, a missing witness, ...)
+    /// end the check without being recorded, so they're added here.
+    fn finish(self, outcome: Result<(), RuleFailed>) -> Vec<RuleCheck> {
+        let mut checked = self.checked.unwrap_or_default();
+        if let Err(rule) = outcome {
+            if !self.stopped {
+                checked.push(RuleCheck { rule, passed: false, context: Vec::new() });
+            }
+        }
+        checked
+    }
+}
+
+/// app_contract, saying why: the operation the transaction performs, or the
+/// first rule it breaks
+///
+/// Checks exactly what app_contract does, in the same order. A broken rule of
+/// the operation the witness claims comes back wrapped in that operation's
+/// ContractError variant, e.g. ContractError::Checkin(RuleFailed::LastCheckinNotIncreased).
+pub fn evaluate(app: &App, tx: &Transaction, x: &Data, w: &Data) -> Result<Operation, ContractError> {
+    let (operation, witness, block_height) = claimed_operation(app, tx, x, w)?;
+
+    #[cfg(feature = "tracing")]
+    let _span = tracing::debug_span!("operation", ?operation, ?block_height).entered();
+
+    // A transaction is valid only if the operation it claims is satisfied
+    let outcome = check_operation(&mut Rules::proving(), operation, app, tx, block_height, &witness)
+        .map(|()| operation)
+        .map_err(|rule| ContractError::rule_failed(operation, rule));
+
+    #[cfg(feature = "tracing")]
+    if let Err(error) = &outcome {
+        tracing::debug!(?error, "operation rejected");
+    }
+    outcome
+}
+
+/// Everything evaluate checks before looking at an operation's rules: returns
+/// the operation the witness claims, that operation's own witness, and the block
+/// height the public input attests
+fn claimed_operation(
+    app: &App,
+    tx: &Transaction,
+    x: &Data,
+    w: &Data,
+) -> Result<(Operation, Data, Option<u64>), ContractError> {
+    // The public input, if any, is a block height
+    ensure(*x == Data::empty() || public_block_height(x).is_some(), ContractError::InvalidPublicInput)?;
+    let block_height = public_block_height(x);
+
+    if app.tag != NFT {
+        // Only NFT tag is supported for inheritance contracts
+        #[cfg(feature = "tracing")]
+        tracing::debug!(tag = ?app.tag, "unsupported app tag");
+        return Err(ContractError::UnsupportedTag);
+    }
+
+    // Refuse to do unbounded work before looking at any operation: sizes first, as
+    // they're measured without decoding anything, then the work the contents imply
+    let size = w.bytes().len();
+    ensure(size <= MAX_WITNESS_BYTES, ContractError::WitnessTooLarge { size, max: MAX_WITNESS_BYTES })?;
+    let charms = charm_values(app, tx.ins.iter().map(|(_, v)| v)).chain(charm_values(app, tx.outs.iter()));
+    if let Some(size) = charms.map(|data| data.bytes().len()).max() {
+        ensure(size <= MAX_CONTENT_BYTES, ContractError::ContentTooLarge { size, max: MAX_CONTENT_BYTES })?;
+    }
+
+    let Ok(OperationWitness { operation, witness }) = w.value() else {
+        return Err(ContractError::InvalidWitness);
+    };
+
+    let cost = validation_cost(app, tx, &witness);
+    ensure(cost <= MAX_VALIDATION_COST, ContractError::TooCostly { cost })?;
+
+    // A frozen contract answers to the court alone
+    ensure(!spends_frozen(app, tx) || operation == Operation::CourtUnfreeze, ContractError::Frozen)?;
+
+    Ok((operation, witness, block_height))
+}
+
+/// Checks `operation`'s own rules against the transaction, given its own witness
+fn check_operation(
+    rules: &mut Rules,
+    operation: Operation,
+    app: &App,
+    tx: &Transaction,
+    block_height: Option<u64>,
+    w: &Data,
+) -> Result<(), RuleFailed> {
+    match operation {
+        // 1. Create new inheritance
+        Operation::Create => check_create_inheritance(rules, app, tx, block_height, w),
+        // 2. Owner extends deadline
+        Operation::Checkin => check_checkin_at(rules, app, tx, block_height, w),
+        // 2b. The oracle vouches for the owner instead
+        Operation::OracleCheckin => check_oracle_checkin(rules, app, tx, block_height, w),
+        // 3. Owner modifies beneficiaries
+        Operation::UpdateBeneficiaries => check_update_beneficiaries(rules, app, tx, block_height, w),
+        // 4. Distribute to beneficiaries
+        Operation::TriggerDistribution => check_trigger_distribution_at(rules, app, tx, block_height, w),
+        // 5. Owner upgrades the content layout
+        Operation::Migrate => check_migrate(rules, app, tx, w),
+        // 6. Emergency contact buys the owner time, once
+        Operation::EmergencyExtend => check_emergency_extend(rules, app, tx, block_height, w),
+        // 7. Anyone records a lapsed deadline
+        Operation::RecordMiss => check_record_miss(rules, app, tx, block_height),
+        // 8. A court freezes the contract pending legal proceedings
+        Operation::CourtFreeze => check_court_freeze(rules, app, tx, block_height, w),
+        // 9. ...and lifts the freeze
+        Operation::CourtUnfreeze => check_unfreeze(rules, app, tx, w),
+        // 10. Guardians distribute early for an owner who lost their key
+        Operation::GuardianEmergencyTrigger => check_guardian_emergency_trigger(rules, app, tx, block_height, w),
+    }
+}
+
+/// Operations in the order detect_operation tries them
+///
+/// Narrower operations come first. A check-in only moves last_checkin_block, which
+/// an update that leaves the beneficiaries alone may do too, so a transaction that
+/// satisfies both is a check-in.
+const DETECTION_ORDER: [Operation; 11] = [
+    Operation::Create,
+    Operation::Checkin,
+    Operation::OracleCheckin,
+    Operation::UpdateBeneficiaries,
+    Operation::EmergencyExtend,
+    Operation::RecordMiss,
+    Operation::Migrate,
+    Operation::TriggerDistribution,
+    Operation::GuardianEmergencyTrigger,
+    Operation::CourtFreeze,
+    Operation::CourtUnfreeze,
+];
+
+/// The operation a transaction performs, given the public input it was proved
+/// with and the operation's own witness (without the operation app_contract reads
+/// alongside it, see operation_witness)
+///
+/// Tries every operation through evaluate, in DETECTION_ORDER, and returns the first
+/// one the transaction satisfies, or None if it's valid as none of them. Wallets and
+/// explorers use this to classify spends they didn't make. The public input is
+/// needed because the operations that depend on the height can't be told apart
+/// from invalid ones without it.
+pub fn detect_operation(app: &App, tx: &Transaction, x: &Data, w: &Data) -> Option<Operation> {
+    DETECTION_ORDER
+        .into_iter()
+        .find(|&operation| evaluate(app, tx, x, &operation_witness(operation, w)).is_ok())
+}
+
+/// evaluate, for people: every rule of every operation, checked against the transaction
+///
+/// Where evaluate stops at the first broken rule, this carries on and records
+/// each rule with the values it was checked against, so a wallet can show
+/// everything wrong with a transaction at once. A rule that the ones after it
+/// depend on still ends its operation's list. Every operation is checked with the
+/// witness the transaction's claimed one was given, and the outcome is that of the
+/// claimed operation, so it always agrees with evaluate. Never used for proving.
+pub fn diagnose(app: &App, tx: &Transaction, x: &Data, w: &Data) -> ValidationReport {
+    let (claimed, witness, block_height) = match claimed_operation(app, tx, x, w) {
+        Ok(claimed) => claimed,
+        Err(error) => {
+            return ValidationReport {
+                outcome: Err(error),
+                operations: Vec::new(),
+            }
+        }
+    };
+
+    let operations: Vec<OperationReport> = DETECTION_ORDER
+        .into_iter()
+        .map(|operation| {
+            let mut rules = Rules::diagnosing();
+            let outcome = check_operation(&mut rules, operation, app, tx, block_height, &witness);
+            let rules = rules.finish(outcome);
+            OperationReport {
+                operation,
+                claimed: operation == claimed,
+                broken: rules.iter().find(|check| !check.passed).map(|check| check.rule),
+                rules,
+            }
+        })
+        .collect();
+
+    let broken = operations
+        .iter()
+        .find(|report| report.claimed)
+        .and_then(|report| report.broken);
+    ValidationReport {
+        outcome: broken.map_or(Ok(claimed), |rule| Err(ContractError::rule_failed(claimed, rule))),
+        operations,
+    }
+}
+
+/// The public input app_contract reads for a spell made at `current_block`
+pub fn block_height_input(current_block: u64) -> Data {
+    Data::from(&current_block)
+}
+
+/// The block height a public input attests, if it's one (see block_height_input)
+fn public_block_height(x: &Data) -> Option<u64> {
+    x.value().ok()
+}
+
+/// The height an operation is checked at: the one the public input attests, as
+/// long as the height the witness gives (if it gives one) is the same
+///
+/// Witnesses are chosen by whoever builds the spell, so a height taken from the
+/// witness alone could be anything; None means there's no height to go by.
+fn attested_height(block_height: Option<u64>, stated: Option<u64>) -> Option<u64> {
+    block_height.filter(|&height| stated.is_none_or(|stated| stated == height))
+}
+
+/// Wraps an operation's own witness into the witness app_contract reads
+pub fn operation_witness(operation: Operation, witness: &Data) -> Data {
+    Data::from(&OperationWitness {
+        operation,
+        witness: witness.clone(),
+    })
+}
+
+/// Rough upper bound on the work validating `tx` takes, given the operation's own witness
+///
+/// The expensive checks pair every beneficiary and guardian of every contract the
+/// transaction touches with every output, and with every signature, derived key or
+/// UTXO ID in the witness, so the cost is the product of the two.
+pub fn validation_cost(app: &App, tx: &Transaction, w: &Data) -> u64 {
+    let charms = charm_values(app, tx.ins.iter().map(|(_, v)| v)).chain(charm_values(app, tx.outs.iter()));
+    let parties: usize = charms
+        .map(|data| {
+            migrate_to_latest(data).map_or(1, |content| 1 + content.beneficiaries.len() + content.guardians.len())
+        })
+        .sum();
+    let counterparts = 1 + tx.outs.len() + witness_entries(w);
+
+    (parties as u64).saturating_mul(counterparts as u64)
+}
+
+/// Number of signatures, derived keys, revealed payees or UTXO IDs in a witness,
+/// whichever operation it's for
+fn witness_entries(w: &Data) -> usize {
+    let distribution = w
+        .value::<DistributionWitness>()
+        .map_or(0, |witness| {
+            witness.derived.len()
+                + witness.revealed.len()
+                + witness.beneficiary_signatures.len()
+                + witness.guardian_signatures.len()
+        });
+    let signatures = parse_multisig_checkin_witness(w).map_or(0, |(signatures, _)| signatures.len());
+    let utxo_ids = w
+        .value::<CreateWitness>()
+        .ok()
+        .and_then(|witness| witness.utxo_ids())
+        .map_or(0, |ids| ids.len());
+
+    distribution.max(signatures).max(utxo_ids)
+}
+
+//
+// ==================== OPERATION 1: CREATE INHERITANCE ====================
+//
+
+/// Validates creating a new inheritance contract
+///
+/// Requirements:
+/// - Witness data (w) must contain one or more UTXO IDs, and their composite hash becomes the app identity
+/// - Every one of those UTXOs must be spent in this transaction (prevents replay attacks)
+/// - No input may already carry a charm with this app's identity
+/// - Exactly one NFT must be created in the outputs
+/// - The NFT must have valid InheritanceContent structure
+/// - Beneficiary shares must sum to 10000 bps
+/// - created_at_block (and so the first check-in) must be the current block height,
+///   as attested by the public input
+fn check_create_inheritance(
+    rules: &mut Rules,
+    app: &App,
+    tx: &Transaction,
+    block_height: Option<u64>,
+    w: &Data,
+) -> Result<(), RuleFailed> {
+    // Extract witness data (a UTXO ID string, or a list of them)
+    let witness: CreateWitness = w.value().map_err(|_| RuleFailed::Witness)?;
+    let utxo_ids = witness.utxo_ids().ok_or(RuleFailed::Witness)?;
+
+    // Verify the hash of witness data matches the app identity
+    // This ties the inheritance to specific UTXOs
+    rule!(rules, composite_identity(&utxo_ids) == app.identity, RuleFailed::IdentityMismatch);
+
+    // Verify that every UTXO referenced in witness is being spent
+    // This prevents someone from reusing the same witness data
+    rule!(
+        rules,
+        utxo_ids
+            .iter()
+            .all(|w_utxo_id| tx.ins.iter().any(|(utxo_id, _)| utxo_id == w_utxo_id)),
+        RuleFailed::FundingNotSpent
+    );
+
+    // A contract can't be created on top of existing state: if an input already
+    // holds this app's charm, this is a spend of that contract, not a creation
+    rule!(rules, inputs_with_app_identity(app, tx).is_empty(), RuleFailed::AlreadyCreated);
+
+    // Get all NFT charms in the outputs
+    let nft_charms = charm_values(app, tx.outs.iter()).collect::<Vec<_>>();
+
+    // Must create exactly one inheritance NFT
+    require!(rules, nft_charms.len() == 1, RuleFailed::OutputNftCount, count = nft_charms.len());
+
+    // Verify the NFT has correct structure
+    let inheritance = InheritanceContent::try_from(nft_charms[0]).map_err(|_| RuleFailed::OutputContent)?;
+
+    // Validate business logic
+    rule!(rules, validate_inheritance(&inheritance), RuleFailed::InvalidContent);
+
+    // Creation is the first check-in, so it's recorded at the height it happens at:
+    // a contract created "in the future" would push its first deadline out at will
+    let current_block = block_height.ok_or(RuleFailed::HeightNotAttested)?;
+    rule!(rules, inheritance.created_at_block == current_block, RuleFailed::CreatedAtMismatch);
+
+    Ok(())
+}
+
+//
+// ==================== OPERATION 2: CHECK-IN (EXTEND DEADLINE) ====================
+//
+
+/// Validates a check-in operation (owner extending the deadline), at the block
+/// height the public input attests
+///
+/// Requirements:
+/// - Exactly 1 input may carry this app's identity (the NFT being spent)
+/// - Must have exactly 1 input NFT and 1 output NFT
+/// - Input status must be Active
+/// - Output status must remain Active
+/// - last_checkin_block must be updated (increased), but not past the current
+///   block height, as attested by the public input (see attested_height)
+/// - The current block must be inside the check-in window (see checkin_window_opens)
+/// - missed_count must be reset to 0
+/// - All other fields must remain unchanged (see content_commitment)
+/// - The witness must carry the owner's signature over signing_message(Checkin, tx, output), or
+///   with a multisig_threshold, that many signatures by the owner and co-signers
+fn check_checkin_at(
+    rules: &mut Rules,
+    app: &App,
+    tx: &Transaction,
+    block_height: Option<u64>,
+    w: &Data,
+) -> Result<(), RuleFailed> {
+    let signatures = parse_multisig_checkin_witness(w).map_or_else(Vec::new, |(signatures, _)| signatures);
+    check_checkin_change(rules, app, tx, checkin_block(block_height, w), CheckinAuthority::Owner(&signatures))
+}
+
+/// The height a check-in witness gives, if it parses and the public input attests it
+fn checkin_block(block_height: Option<u64>, w: &Data) -> Option<u64> {
+    let (_, stated) = parse_multisig_checkin_witness(w)?;
+    attested_height(block_height, Some(stated))
+}
+
+// Who vouches for a check-in
+#[derive(Debug, Clone, Copy)]
+enum CheckinAuthority<'a> {
+    Owner(&'a [(OwnerPubkey, Vec<u8>)]),  // Signatures from the check-in witness; the owner's must be among them
+    Oracle,                               // The oracle's attestation, checked by check_oracle_checkin
+}
+
+/// Validates a check-in vouched for by the contract's oracle
+///
+/// Requirements:
+/// - The transaction must be a check-in (see check_checkin_at), made at the height
+///   the public input attests, which the oracle check-in witness must also give
+/// - The output must name an oracle_pubkey
+/// - The witness must carry the oracle's signature over
+///   signing_message(OracleCheckin, tx, output)
+///
+/// This complements the owner's own check-ins rather than replacing them: the
+/// NFT's UTXO still has to be spent, so the oracle needs a way to spend it.
+fn check_oracle_checkin(
+    rules: &mut Rules,
+    app: &App,
+    tx: &Transaction,
+    block_height: Option<u64>,
+    w: &Data,
+) -> Result<(), RuleFailed> {
+    let witness: OracleCheckinWitness = w.value().map_err(|_| RuleFailed::Witness)?;
+    let current_block = attested_height(block_height, Some(witness.current_block));
+    check_checkin_change(rules, app, tx, current_block, CheckinAuthority::Oracle)?;
+
+    // The attestation signs the resulting content, so it can't vouch for any other check-in
+    let output_charms: Vec<_> = charm_values(app, tx.outs.iter()).collect();
+    let output_inheritance =
+        InheritanceContent::try_from(output_charms[0]).map_err(|_| RuleFailed::OutputContent)?;
+
+    let oracle_pubkey = output_inheritance.oracle_pubkey.as_deref().ok_or(RuleFailed::NotEnabled)?;
+    let message = signing_message(Operation::OracleCheckin, tx, &output_inheritance);
+    rule!(rules, verify_owner_signature(oracle_pubkey, &message, &witness.attestation), RuleFailed::Signature);
+
+    Ok(())
+}
+
+/// Checks that `tx` moves the contract's last check-in forward, to no later than
+/// `current_block`, and changes nothing else, with `authority` vouching for it
+fn check_checkin_change(
+    rules: &mut Rules,
+    app: &App,
+    tx: &Transaction,
+    current_block: Option<u64>,
+    authority: CheckinAuthority,
+) -> Result<(), RuleFailed> {
+    // Exactly one input may carry this app's identity: the NFT being spent
+    rule!(rules, inputs_with_app_identity(app, tx).len() == 1, RuleFailed::AppIdentityInputs);
+
+    // Get input inheritance state
+    let input_charms: Vec<_> = charm_values(app, tx.ins.iter().map(|(_, v)| v)).collect();
+    require!(rules, input_charms.len() == 1, RuleFailed::InputNftCount, count = input_charms.len());
+
+    let input_inheritance = InheritanceContent::try_from(input_charms[0]).map_err(|_| RuleFailed::InputContent)?;
+
+    // Must be in Active status to check-in
+    rule!(rules, input_inheritance.status == InheritanceStatus::Active, RuleFailed::NotActive);
+
+    // Get output inheritance state: the NFT must be carried on (see continuation_nft)
+    let output_nft = continuation_nft(app, tx).map_err(|_| RuleFailed::OutputNftCount)?;
+    let output_inheritance = InheritanceContent::try_from(output_nft).map_err(|_| RuleFailed::OutputContent)?;
+
+    // Output must also be Active
+    rule!(rules, output_inheritance.status == InheritanceStatus::Active, RuleFailed::OutputNotActive);
+    rule!(rules, validate_size(&output_inheritance).is_ok(), RuleFailed::OutputTooLarge);
+
+    // last_checkin_block must be updated (owner proved they're alive)
+    rule!(
+        rules,
+        output_inheritance.last_checkin_block > input_inheritance.last_checkin_block,
+        RuleFailed::LastCheckinNotIncreased,
+        last_checkin_block = input_inheritance.last_checkin_block,
+        new_checkin_block = output_inheritance.last_checkin_block,
+    );
+
+    // ...but not into the future, or one check-in could push the deadline out arbitrarily far,
+    // which takes knowing the height the check-in is made at
+    let current_block = current_block.ok_or(RuleFailed::HeightNotAttested)?;
+    rule!(
+        rules,
+        output_inheritance.last_checkin_block <= current_block,
+        RuleFailed::CheckinInFuture,
+        new_checkin_block = output_inheritance.last_checkin_block,
+        current_block = current_block,
+    );
+
+    // Owners who want a steady cadence only check in once the window before the deadline opens
+    let window_opens = checkin_window_opens(&input_inheritance, current_block);
+    rule!(
+        rules,
+        current_block >= window_opens,
+        RuleFailed::CheckinTooEarly,
+        current_block = current_block,
+        window_opens = window_opens,
+    );
+
+    // The owner is alive, so no deadline counts as missed any more
+    rule!(rules, output_inheritance.missed_count == 0, RuleFailed::MissesNotReset);
+
+    // All other fields must remain unchanged
+    rule!(
+        rules,
+        content_commitment(&output_inheritance) == content_commitment(&input_inheritance),
+        RuleFailed::FieldChanged
+    );
+
+    // The owner proves they're alive, or with a multisig_threshold, enough of the owner and
+    // co-signers vouch for it: either way the signatures commit to this exact check-in
+    if let CheckinAuthority::Owner(signatures) = authority {
+        let message = signing_message(Operation::Checkin, tx, &output_inheritance);
+        if input_inheritance.multisig_threshold > 0 {
+            let signers = checkin_signers(&input_inheritance);
+            rule!(
+                rules,
+                multisig_threshold_met(&signers, &message, signatures, input_inheritance.multisig_threshold),
+                RuleFailed::Signature
+            );
+        } else {
+            rule!(
+                rules,
+                signatures
+                    .iter()
+                    .any(|(_, sig)| verify_owner_signature(&input_inheritance.owner_pubkey, &message, sig)),
+                RuleFailed::Signature
+            );
+        }
+    }
+
+    Ok(())
+}
+
+//
+// ==================== OPERATION 3: UPDATE BENEFICIARIES ====================
+//
+
+/// Validates updating the beneficiaries list
+///
+/// Requirements:
+/// - Must have exactly 1 input NFT and 1 output NFT
+/// - Input status must be Active
+/// - Output status must remain Active
+/// - The new content must keep every invariant (see InheritanceContent::validate_invariants)
+/// - Beneficiaries can be modified
+/// - The fallback address can be modified
+/// - Guardians, their threshold and their emergency delay can be modified
+/// - The recovery mode can be modified
+/// - The oracle can be changed or removed
+/// - Metadata can be modified
+/// - The will document hash can be set, changed or removed, and the will locked,
+///   until it's locked
+/// - The emergency contact can be changed or removed, but whether the extension
+///   was used can't change
+/// - missed_count must be reset to 0 (the owner proved they're alive)
+/// - last_checkin_block should be updated (to extend deadline), but not past the
+///   current block height, as attested by the public input (see attested_height),
+///   and only once the check-in window is open (see checkin_window_opens); an
+///   update that leaves it alone needs no height
+/// - Every other field must remain unchanged
+/// - Witness must carry the owner's signature over signing_message(UpdateBeneficiaries, tx, output)
+fn check_update_beneficiaries(
+    rules: &mut Rules,
+    app: &App,
+    tx: &Transaction,
+    block_height: Option<u64>,
+    w: &Data,
+) -> Result<(), RuleFailed> {
+    // Get input inheritance state
+    let input_charms: Vec<_> = charm_values(app, tx.ins.iter().map(|(_, v)| v)).collect();
+    require!(rules, input_charms.len() == 1, RuleFailed::InputNftCount, count = input_charms.len());
+
+    let input_inheritance = InheritanceContent::try_from(input_charms[0]).map_err(|_| RuleFailed::InputContent)?;
+
+    // Must be in Active status to update
+    rule!(rules, input_inheritance.status == InheritanceStatus::Active, RuleFailed::NotActive);
+
+    // Get output inheritance state: the NFT must be carried on (see continuation_nft)
+    let output_nft = continuation_nft(app, tx).map_err(|_| RuleFailed::OutputNftCount)?;
+    let output_inheritance = InheritanceContent::try_from(output_nft).map_err(|_| RuleFailed::OutputContent)?;
+
+    // Output must also be Active
+    rule!(rules, output_inheritance.status == InheritanceStatus::Active, RuleFailed::OutputNotActive);
+
+    // The new content must keep the same invariants creation checked
+    let invariants = output_inheritance.validate_invariants();
+    let broken = match invariants {
+        Err(
+            ValidationError::TooFewBeneficiaries { .. }
+            | ValidationError::TooManyBeneficiaries { .. }
+            | ValidationError::Beneficiary { .. }
+            | ValidationError::DuplicateAddress { .. }
+            | ValidationError::FixedExceedsVault
+            | ValidationError::SharesNotWhole
+            | ValidationError::DustAtExpectedFunding { .. }
+            | ValidationError::OwnerIsBeneficiary,
+        ) => RuleFailed::InvalidBeneficiaries,
+        Err(ValidationError::Content(_)) => RuleFailed::OutputTooLarge,
+        _ => RuleFailed::InvalidContent,
+    };
+    rule!(rules, invariants.is_ok(), broken, error = invariants);
+
+    // Once the will is locked, its hash stays as it is and it stays locked
+    if input_inheritance.will_locked {
+        rule!(
+            rules,
+            output_inheritance.will_locked && output_inheritance.will_document_hash == input_inheritance.will_document_hash,
+            RuleFailed::WillLocked
+        );
+    }
+
+    rule!(rules, output_inheritance.missed_count == 0, RuleFailed::MissesNotReset);
+
+    // Everything an update can't change is carried over as it was, fields added
+    // later included
+    rule!(
+        rules,
+        output_inheritance
+            == InheritanceContent {
+                beneficiaries: output_inheritance.beneficiaries.clone(),
+                fallback_address: output_inheritance.fallback_address.clone(),
+                guardians: output_inheritance.guardians.clone(),
+                guardian_threshold: output_inheritance.guardian_threshold,
+                guardian_emergency_delay: output_inheritance.guardian_emergency_delay,
+                recovery_mode: output_inheritance.recovery_mode,
+                oracle_pubkey: output_inheritance.oracle_pubkey.clone(),
+                metadata: output_inheritance.metadata.clone(),
+                will_document_hash: output_inheritance.will_document_hash,
+                will_locked: output_inheritance.will_locked,
+                emergency_contact_pubkey: output_inheritance.emergency_contact_pubkey.clone(),
+                last_checkin_block: output_inheritance.last_checkin_block,
+                missed_count: 0,
+                ..input_inheritance.clone()
+            },
+        RuleFailed::FieldChanged
+    );
+
+    // last_checkin_block should be updated (acts as check-in too)
+    rule!(
+        rules,
+        output_inheritance.last_checkin_block >= input_inheritance.last_checkin_block,
+        RuleFailed::LastCheckinNotIncreased
+    );
+
+    // Moving it forward is a check-in, so like one it can't reach into the future,
+    // nor come before the check-in window opens
+    if output_inheritance.last_checkin_block > input_inheritance.last_checkin_block {
+        let current_block = attested_height(block_height, None).ok_or(RuleFailed::HeightNotAttested)?;
+        rule!(
+            rules,
+            output_inheritance.last_checkin_block <= current_block,
+            RuleFailed::CheckinInFuture,
+            new_checkin_block = output_inheritance.last_checkin_block,
+            current_block = current_block,
+        );
+        let window_opens = checkin_window_opens(&input_inheritance, current_block);
+        rule!(
+            rules,
+            current_block >= window_opens,
+            RuleFailed::CheckinTooEarly,
+            current_block = current_block,
+            window_opens = window_opens,
+        );
+    }
+
+    // Owner must authorize the change
+    let witness: UpdateBeneficiariesWitness = w.value().map_err(|_| RuleFailed::Witness)?;
+    let message = signing_message(Operation::UpdateBeneficiaries, tx, &output_inheritance);
+    rule!(
+        rules,
+        verify_owner_signature(&input_inheritance.owner_pubkey, &message, &witness.signature),
+        RuleFailed::Signature
+    );
+
+    Ok(())
+}
+
+//
+// ==================== OPERATION 4: TRIGGER DISTRIBUTION ====================
+//
+
+/// Validates triggering the inheritance distribution, at the block height the
+/// public input attests
+///
+/// Requirements:
+/// - Must have exactly 1 input NFT
+/// - Input status must be Active or Triggered
+/// - The public input must attest the block height (see attested_height)
+/// - Deadline and any grace period must have passed (current block >
+///   last_checkin + delay + grace, see is_distributable), unless the beneficiaries
+///   signed off on an early trigger (see beneficiary_majority_authorizes)
+/// - Witness must name the derived script for each xpub beneficiary
+/// - Either the final distribution: every beneficiary is paid their share
+///   (see verify_distribution_completeness) and the NFT is burned, or replaced by a
+///   tombstone if the contract has leave_tombstone set (see verify_tombstone); every
+///   beneficiary must have vested (see is_vested)
+/// - Or a partial one: some beneficiaries are paid and the NFT survives, Triggered,
+///   holding the rest (see verify_partial_distribution)
+fn check_trigger_distribution_at(
+    rules: &mut Rules,
+    app: &App,
+    tx: &Transaction,
+    block_height: Option<u64>,
+    w: &Data,
+) -> Result<(), RuleFailed> {
+    // Get input inheritance state
+    let input_charms: Vec<_> = charm_values(app, tx.ins.iter().map(|(_, v)| v)).collect();
+    require!(rules, input_charms.len() == 1, RuleFailed::InputNftCount, count = input_charms.len());
+
+    let inheritance = InheritanceContent::try_from(input_charms[0]).map_err(|_| RuleFailed::InputContent)?;
+
+    // Must be Active or Triggered (not already Distributed)
+    rule!(
+        rules,
+        inheritance.status == InheritanceStatus::Active || inheritance.status == InheritanceStatus::Triggered,
+        RuleFailed::AlreadyDistributed
+    );
+
+    // The witness must decode: a garbled one must not pass as a witness without derivations
+    let mut witness: DistributionWitness = w.value().map_err(|_| RuleFailed::Witness)?;
+
+    // The deadline and vesting are checked at the attested height, and nothing can be
+    // distributed without one
+    let current_block = attested_height(block_height, witness.current_block).ok_or(RuleFailed::HeightNotAttested)?;
+    witness.current_block = Some(current_block);
+
+    // Before the deadline, only a majority of the beneficiaries can trigger, and only
+    // if the contract opted into it
+    if !is_distributable(&inheritance, current_block) {
+        rule!(
+            rules,
+            beneficiary_majority_authorizes(&inheritance, tx, &witness),
+            RuleFailed::DeadlineNotPassed,
+            current_block = current_block,
+            deadline_block = grace_deadline_block(&inheritance, current_block),
+        );
+    }
+
+    check_payout(rules, app, &inheritance, tx, &witness)
+}
+
+/// Checks that `tx` pays out `inheritance` as a distribution must, whoever triggered it
+///
+/// The second half of check_trigger_distribution_at: a final distribution (the NFT
+/// burned or left as a tombstone, every beneficiary vested and paid) or a
+/// partial one. `witness.current_block` must already be the attested height.
+fn check_payout(
+    rules: &mut Rules,
+    app: &App,
+    inheritance: &InheritanceContent,
+    tx: &Transaction,
+    witness: &DistributionWitness,
+) -> Result<(), RuleFailed> {
+    let output_charms: Vec<_> = charm_values(app, tx.outs.iter()).collect();
+
+    let tombstone: Option<DistributionTombstone> = match output_charms[..] {
+        [data] => data.value().ok(),
+        _ => None,
+    };
+
+    if output_charms.is_empty() || tombstone.is_some() {
+        // Final distribution: the NFT is burned, so every beneficiary must be paid now
+        match tombstone {
+            Some(tombstone) => rule!(rules, verify_tombstone(app, inheritance, tx, &tombstone), RuleFailed::Tombstone),
+            None => rule!(rules, !inheritance.leave_tombstone, RuleFailed::Tombstone),
+        }
+        rule!(
+            rules,
+            inheritance
+                .beneficiaries
+                .iter()
+                .all(|b| is_vested(b, inheritance.trigger_height, witness.current_block)),
+            RuleFailed::NotVested
+        );
+        rule!(rules, verify_distribution_completeness(app, inheritance, tx, witness), RuleFailed::Underpaid);
+    } else {
+        // Partial distribution: the NFT survives for the beneficiaries not yet paid
+        require!(rules, output_charms.len() == 1, RuleFailed::OutputNftCount, count = output_charms.len());
+        let remaining = InheritanceContent::try_from(output_charms[0]).map_err(|_| RuleFailed::OutputContent)?;
+        rule!(
+            rules,
+            verify_partial_distribution(app, inheritance, &remaining, tx, witness),
+            RuleFailed::PartialDistribution
+        );
+    }
+
+    Ok(())
+}
+
+/// Validates guardians distributing early, for an owner who lost their key
+///
+/// With the owner's key gone nobody can check in, and the heirs would wait out
+/// the full delay. A guardian quorum can cut that short, but not below the
+/// contract's own guardian_emergency_delay.
+///
+/// Requirements:
+/// - Must have exactly 1 input NFT, and its status must be Active (a Triggered
+///   contract is distributable anyway)
+/// - The contract must set a guardian_emergency_delay
+/// - The public input must attest a height past last_checkin_block +
+///   guardian_emergency_delay (see attested_height)
+/// - At least guardian_threshold guardians must sign
+///   signing_message(GuardianEmergencyTrigger, ..) over the contract being spent
+///   (see verify_guardian_quorum)
+/// - The transaction must pay out as any distribution does (see check_payout)
+fn check_guardian_emergency_trigger(
+    rules: &mut Rules,
+    app: &App,
+    tx: &Transaction,
+    block_height: Option<u64>,
+    w: &Data,
+) -> Result<(), RuleFailed> {
+    let input_charms: Vec<_> = charm_values(app, tx.ins.iter().map(|(_, v)| v)).collect();
+    require!(rules, input_charms.len() == 1, RuleFailed::InputNftCount, count = input_charms.len());
+    let inheritance = InheritanceContent::try_from(input_charms[0]).map_err(|_| RuleFailed::InputContent)?;
+    rule!(rules, inheritance.status == InheritanceStatus::Active, RuleFailed::NotActive);
+    rule!(rules, inheritance.guardian_emergency_delay > 0, RuleFailed::NotEnabled);
+
+    let mut witness: DistributionWitness = w.value().map_err(|_| RuleFailed::Witness)?;
+    let current_block = attested_height(block_height, witness.current_block).ok_or(RuleFailed::HeightNotAttested)?;
+    witness.current_block = Some(current_block);
+    let emergency_deadline = inheritance.last_checkin_block.saturating_add(inheritance.guardian_emergency_delay);
+    rule!(
+        rules,
+        current_block > emergency_deadline,
+        RuleFailed::DeadlineNotPassed,
+        current_block = current_block,
+        deadline_block = emergency_deadline,
+    );
+
+    let message = signing_message(Operation::GuardianEmergencyTrigger, tx, &inheritance);
+    rule!(
+        rules,
+        verify_guardian_quorum(&inheritance, &message, &witness.guardian_signatures),
+        RuleFailed::Signature
+    );
+
+    check_payout(rules, app, &inheritance, tx, &witness)
+}
+
+//
+// ==================== OPERATION 5: MIGRATE CONTENT LAYOUT ====================
+//
+
+/// Validates rewriting an old-layout NFT in the latest layout
+///
+/// Requirements:
+/// - Exactly 1 input may carry this app's identity, and 1 NFT in and 1 NFT out
+/// - Input must be written in an older version than CONTENT_VERSION
+/// - Output must be written in exactly CONTENT_VERSION
+/// - Output must be the migrated input (semantics preserved, nothing else changes)
+/// - Witness must carry the owner's signature over signing_message(Migrate, tx, output)
+fn check_migrate(rules: &mut Rules, app: &App, tx: &Transaction, w: &Data) -> Result<(), RuleFailed> {
+    rule!(rules, inputs_with_app_identity(app, tx).len() == 1, RuleFailed::AppIdentityInputs);
+
+    let input_charms: Vec<_> = charm_values(app, tx.ins.iter().map(|(_, v)| v)).collect();
+    require!(rules, input_charms.len() == 1, RuleFailed::InputNftCount, count = input_charms.len());
+    let output_charms: Vec<_> = charm_values(app, tx.outs.iter()).collect();
+    require!(rules, output_charms.len() == 1, RuleFailed::OutputNftCount, count = output_charms.len());
+
+    // Input must be an old layout
+    let input_version = content_version(input_charms[0]).ok_or(RuleFailed::InputContent)?;
+    rule!(rules, input_version < CONTENT_VERSION, RuleFailed::AlreadyLatest);
+
+    // Output must be written in the latest layout
+    let output_inheritance: InheritanceContent = output_charms[0].value().map_err(|_| RuleFailed::OutputContent)?;
+    rule!(rules, output_inheritance.version == CONTENT_VERSION, RuleFailed::NotLatest);
+
+    // Nothing but the layout may change
+    rule!(
+        rules,
+        InheritanceContent::try_from(input_charms[0]).is_ok_and(|migrated| migrated == output_inheritance),
+        RuleFailed::FieldChanged
+    );
+
+    // Owner must authorize the rewrite
+    let witness: MigrateWitness = w.value().map_err(|_| RuleFailed::Witness)?;
+    let message = signing_message(Operation::Migrate, tx, &output_inheritance);
+    rule!(
+        rules,
+        verify_owner_signature(&output_inheritance.owner_pubkey, &message, &witness.signature),
+        RuleFailed::Signature
+    );
+
+    Ok(())
+}
+
+//
+// ==================== OPERATION 6: EMERGENCY EXTENSION ====================
+//
+
+/// Validates the emergency contact's one-time extension of the deadline
+///
+/// For an owner who is alive but can't check in (e.g. in hospital): the contact
+/// vouches for them once, and only that once.
+///
+/// Requirements:
+/// - Exactly 1 input may carry this app's identity, and 1 NFT in and 1 NFT out
+/// - Input status must be Active (a Triggered contract can't be pulled back)
+/// - The public input must attest the current block height, and the contract
+///   mustn't be distributable at it: once the deadline and any grace period have
+///   passed, the heirs' claim can't be taken back
+/// - The input must name an emergency contact who hasn't used their extension yet
+/// - Output must be the input with last_checkin_block moved forward by
+///   EMERGENCY_EXTENSION_BLOCKS, but not past the current block (like a check-in,
+///   it can't be dated in the future), and emergency_extension_used set; nothing
+///   else changes
+/// - Witness must carry the contact's signature over signing_message(EmergencyExtend, tx, output)
+fn check_emergency_extend(
+    rules: &mut Rules,
+    app: &App,
+    tx: &Transaction,
+    block_height: Option<u64>,
+    w: &Data,
+) -> Result<(), RuleFailed> {
+    rule!(rules, inputs_with_app_identity(app, tx).len() == 1, RuleFailed::AppIdentityInputs);
+
+    let input_charms: Vec<_> = charm_values(app, tx.ins.iter().map(|(_, v)| v)).collect();
+    require!(rules, input_charms.len() == 1, RuleFailed::InputNftCount, count = input_charms.len());
+    let input_inheritance = InheritanceContent::try_from(input_charms[0]).map_err(|_| RuleFailed::InputContent)?;
+
+    // Only while the contract is Active and not yet distributable, and only once
+    rule!(rules, input_inheritance.status == InheritanceStatus::Active, RuleFailed::NotActive);
+    let current_block = attested_height(block_height, None).ok_or(RuleFailed::HeightNotAttested)?;
+    rule!(rules, !is_distributable(&input_inheritance, current_block), RuleFailed::DeadlinePassed);
+    rule!(rules, !input_inheritance.emergency_extension_used, RuleFailed::AlreadyUsed);
+    let contact = input_inheritance.emergency_contact_pubkey.as_deref().ok_or(RuleFailed::NotEnabled)?;
+
+    let output_nft = continuation_nft(app, tx).map_err(|_| RuleFailed::OutputNftCount)?;
+    let output_inheritance = InheritanceContent::try_from(output_nft).map_err(|_| RuleFailed::OutputContent)?;
+    rule!(rules, validate_size(&output_inheritance).is_ok(), RuleFailed::OutputTooLarge);
+
+    // The extension is a fixed amount, up to the current block, and spends the
+    // contact's power for good
+    let extended = InheritanceContent {
+        last_checkin_block: input_inheritance
+            .last_checkin_block
+            .saturating_add(EMERGENCY_EXTENSION_BLOCKS)
+            .min(current_block),
+        emergency_extension_used: true,
+        ..input_inheritance.clone()
+    };
+    rule!(rules, output_inheritance == extended, RuleFailed::FieldChanged);
+
+    // The contact, not the owner, must sign
+    let witness: EmergencyExtendWitness = w.value().map_err(|_| RuleFailed::Witness)?;
+    let message = signing_message(Operation::EmergencyExtend, tx, &output_inheritance);
+    rule!(rules, verify_owner_signature(contact, &message, &witness.signature), RuleFailed::Signature);
+
+    Ok(())
+}
+
+//
+// ==================== OPERATION 7: RECORD A MISSED CHECK-IN ====================
+//
+
+/// Validates recording that a deadline lapsed without a check-in
+///
+/// Open to anyone: the lapse is a fact about the chain, proven by the height the
+/// public input attests. The n-th miss since the last check-in can only be
+/// recorded once n deadlines have passed (see miss_deadline_block), so one lapse
+/// can't be counted twice.
+///
+/// Requirements:
+/// - Exactly 1 input may carry this app's identity, and 1 NFT in and 1 NFT out
+/// - Input status must be Active, with fewer misses recorded than allowed_missed_checkins
+/// - The public input must attest a height past miss_deadline_block
+/// - Output must be the input with missed_count one higher; nothing else changes
+fn check_record_miss(
+    rules: &mut Rules,
+    app: &App,
+    tx: &Transaction,
+    block_height: Option<u64>,
+) -> Result<(), RuleFailed> {
+    rule!(rules, inputs_with_app_identity(app, tx).len() == 1, RuleFailed::AppIdentityInputs);
+
+    let input_charms: Vec<_> = charm_values(app, tx.ins.iter().map(|(_, v)| v)).collect();
+    require!(rules, input_charms.len() == 1, RuleFailed::InputNftCount, count = input_charms.len());
+    let input_inheritance = InheritanceContent::try_from(input_charms[0]).map_err(|_| RuleFailed::InputContent)?;
+
+    // Only contracts that count misses, and only up to the number they need
+    rule!(rules, input_inheritance.status == InheritanceStatus::Active, RuleFailed::NotActive);
+    require!(
+        rules,
+        input_inheritance.missed_count < input_inheritance.allowed_missed_checkins,
+        RuleFailed::NotEnabled
+    );
+
+    // The deadline this miss is for must have passed
+    let current_block = block_height.ok_or(RuleFailed::HeightNotAttested)?;
+    let miss_deadline = miss_deadline_block(&input_inheritance, current_block);
+    rule!(
+        rules,
+        current_block > miss_deadline,
+        RuleFailed::DeadlineNotPassed,
+        current_block = current_block,
+        deadline_block = miss_deadline,
+    );
+
+    let output_nft = continuation_nft(app, tx).map_err(|_| RuleFailed::OutputNftCount)?;
+    let output_inheritance = InheritanceContent::try_from(output_nft).map_err(|_| RuleFailed::OutputContent)?;
+    let recorded = InheritanceContent {
+        missed_count: input_inheritance.missed_count + 1,
+        ..input_inheritance
+    };
+    rule!(rules, output_inheritance == recorded, RuleFailed::FieldChanged);
+
+    Ok(())
+}
+
+//
+// ==================== OPERATION 8: COURT FREEZE ====================
+//
+
+/// Validates a court freezing the contract pending legal proceedings
+///
+/// Requirements:
+/// - Exactly 1 input may carry this app's identity, and 1 NFT in and 1 NFT out
+/// - The input must name a court_pubkey
+/// - Output must be apply_court_freeze of the input, frozen no later than the
+///   height the public input attests
+/// - Witness must carry the court's signature over court_order_message(CourtFreeze, tx, output)
+fn check_court_freeze(
+    rules: &mut Rules,
+    app: &App,
+    tx: &Transaction,
+    block_height: Option<u64>,
+    w: &Data,
+) -> Result<(), RuleFailed> {
+    rule!(rules, inputs_with_app_identity(app, tx).len() == 1, RuleFailed::AppIdentityInputs);
+
+    let input_charms: Vec<_> = charm_values(app, tx.ins.iter().map(|(_, v)| v)).collect();
+    require!(rules, input_charms.len() == 1, RuleFailed::InputNftCount, count = input_charms.len());
+    let input_inheritance = InheritanceContent::try_from(input_charms[0]).map_err(|_| RuleFailed::InputContent)?;
+    let court = input_inheritance.court_pubkey.as_ref().ok_or(RuleFailed::NotEnabled)?;
+
+    let output_nft = continuation_nft(app, tx).map_err(|_| RuleFailed::OutputNftCount)?;
+    let frozen: FrozenInheritanceContent = output_nft.value().map_err(|_| RuleFailed::OutputContent)?;
+    let current_block = block_height.ok_or(RuleFailed::HeightNotAttested)?;
+    rule!(rules, frozen.frozen_at_block <= current_block, RuleFailed::FrozenInFuture);
+    rule!(
+        rules,
+        frozen == apply_court_freeze(&input_inheritance, frozen.freeze_reason_hash, frozen.frozen_at_block),
+        RuleFailed::FieldChanged
+    );
+
+    let witness: CourtFreezeWitness = w.value().map_err(|_| RuleFailed::Witness)?;
+    let message = court_order_message(Operation::CourtFreeze, tx, &frozen);
+    rule!(
+        rules,
+        verify_owner_signature(&pubkey_hex(court), &message, &witness.court_freeze_sig),
+        RuleFailed::Signature
+    );
+
+    Ok(())
+}
+
+/// Validates a court lifting its freeze
+///
+/// Requirements:
+/// - Exactly 1 input may carry this app's identity, and 1 NFT in and 1 NFT out
+/// - The input must be frozen, and the output must be the contract exactly as it
+///   was when frozen
+/// - Witness must carry the signature of the frozen contract's court_pubkey over
+///   court_order_message(CourtUnfreeze, tx, input)
+fn check_unfreeze(rules: &mut Rules, app: &App, tx: &Transaction, w: &Data) -> Result<(), RuleFailed> {
+    rule!(rules, inputs_with_app_identity(app, tx).len() == 1, RuleFailed::AppIdentityInputs);
+
+    let input_charms: Vec<_> = charm_values(app, tx.ins.iter().map(|(_, v)| v)).collect();
+    require!(rules, input_charms.len() == 1, RuleFailed::InputNftCount, count = input_charms.len());
+    let frozen: FrozenInheritanceContent = input_charms[0].value().map_err(|_| RuleFailed::InputContent)?;
+    let court = frozen.inner.court_pubkey.as_ref().ok_or(RuleFailed::NotEnabled)?;
+
+    let output_nft = continuation_nft(app, tx).map_err(|_| RuleFailed::OutputNftCount)?;
+    let output_inheritance = InheritanceContent::try_from(output_nft).map_err(|_| RuleFailed::OutputContent)?;
+    rule!(rules, output_inheritance == frozen.inner, RuleFailed::FieldChanged);
+
+    let witness: CourtUnfreezeWitness = w.value().map_err(|_| RuleFailed::Witness)?;
+    let message = court_order_message(Operation::CourtUnfreeze, tx, &frozen);
+    rule!(
+        rules,
+        verify_owner_signature(&pubkey_hex(court), &message, &witness.court_unfreeze_sig),
+        RuleFailed::Signature
+    );
+
+    Ok(())
+}
+
+/// Freezes a contract under a court order, as of `frozen_at_block`
+///
+/// The contract is kept whole inside the frozen content, so lifting the freeze
+/// restores it unchanged.
+pub fn apply_court_freeze(
+    inheritance: &InheritanceContent,
+    court_order_hash: B32,
+    frozen_at_block: u64,
+) -> FrozenInheritanceContent {
+    FrozenInheritanceContent {
+        inner: inheritance.clone(),
+        freeze_reason_hash: court_order_hash,
+        frozen_at_block,
+    }
+}
+
+/// Whether the transaction spends a frozen NFT of this app
+fn spends_frozen(app: &App, tx: &Transaction) -> bool {
+    charm_values(app, tx.ins.iter().map(|(_, v)| v)).any(|data| data.value::<FrozenInheritanceContent>().is_ok())
+}
+
+/// The 32-byte message a court signs to freeze or unfreeze a contract
+///
+/// signing_message over the contract inside, extended with the court order and
+/// the freeze height, so neither can be swapped under the court's signature.
+pub fn court_order_message(op: Operation, tx: &Transaction, frozen: &FrozenInheritanceContent) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(signing_message(op, tx, &frozen.inner));
+    hasher.update(frozen.freeze_reason_hash.0);
+    hasher.update(frozen.frozen_at_block.to_le_bytes());
+    hasher.finalize().into()
+}
+
+//
+// ==================== HELPER FUNCTIONS ====================
+//
+
+/// Validates the inheritance structure
+fn validate_inheritance(inheritance: &InheritanceContent) -> bool {
+    inheritance.validate().is_ok()
+}
+
+impl InheritanceContent {
+    /// Content for a new contract created at `current_height`, validated up front
+    ///
+    /// The creation height is also the first check-in; everything else takes its
+    /// default (testnet, default rules, no optional features). Errors name the
+    /// rule broken, and for a bad beneficiary its index.
+    pub fn new(
+        owner_pubkey: &str,
+        delay_blocks: u64,
+        beneficiaries: Vec<Beneficiary>,
+        current_height: u64,
+    ) -> Result<Self, ValidationError> {
+        let content = InheritanceContent {
+            owner_pubkey: owner_pubkey.to_string(),
+            last_checkin_block: current_height,
+            trigger_delay_blocks: delay_blocks,
+            beneficiaries,
+            created_at_block: current_height,
+            ..InheritanceContent::builder().content
+        };
+        content.validate()?;
+        Ok(content)
+    }
+
+    /// Starts building the content of a new contract
+    ///
+    /// The builder starts Active, in the current layout, with every optional
+    /// feature off; it has no way to set a status, so it can only produce content
+    /// a creation would accept.
+    pub fn builder() -> InheritanceContentBuilder {
+        InheritanceContentBuilder {
+            content: InheritanceContent {
+                status: InheritanceStatus::Active,
+                version: CONTENT_VERSION,
+                ..Default::default()
+            },
+        }
+    }
+
+    /// The content as deterministic CBOR (RFC 8949 §4.2.1): map keys sorted,
+    /// integers and lengths in their shortest form
+    ///
+    /// Every digest of the content is taken over these bytes, so other
+    /// implementations only have to agree on the fields, not on their order. The
+    /// NFT payload itself keeps the declaration order it was always written in.
+    pub fn canonical_bytes(&self) -> Vec<u8> {
+        canonical_cbor(self)
+    }
+
+    /// Tagged SHA-256 (tag CONTENT_HASH_TAG) of the canonical bytes
+    ///
+    /// What every owner, guardian and beneficiary signature commits to, through
+    /// signing_message. Any change to a field changes it; the order fields are
+    /// declared in doesn't.
+    pub fn content_hash(&self) -> B32 {
+        B32(tagged_hash(CONTENT_HASH_TAG, &self.canonical_bytes()))
+    }
+
+    /// Checks everything a new contract must satisfy, reporting the first rule broken
+    ///
+    /// This is exactly what the contract checks at creation, so wallets can
+    /// pre-flight content before proving: the invariants of validate_invariants,
+    /// plus what only holds until something happens to the contract.
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        // A new contract can only start Active, so there's no trigger height yet
+        ensure(
+            valid_transition(None, &self.status) && self.trigger_height.is_none(),
+            ValidationError::NotActive,
+        )?;
+
+        // Creation counts as the first check-in, so both record the creation height
+        ensure(
+            self.created_at_block == self.last_checkin_block,
+            ValidationError::CreationHeightMismatch,
+        )?;
+
+        // A new contract's emergency contact still has their extension, and no
+        // deadline has lapsed yet
+        ensure(!self.emergency_extension_used, ValidationError::InvalidEmergencyContact)?;
+        ensure(self.missed_count == 0, ValidationError::MissesRecorded)?;
+
+        self.validate_invariants()
+    }
+
+    /// Checks what content must satisfy at every point of a contract's life,
+    /// reporting the first rule broken
+    ///
+    /// Creation checks these through validate, and every update holds the new
+    /// content to them, so a contract that has since been checked in still passes.
+    pub fn validate_invariants(&self) -> Result<(), ValidationError> {
+        // created_at_block never changes and check-ins only move forward
+        ensure(
+            self.last_checkin_block >= self.created_at_block,
+            ValidationError::CheckinBeforeCreation,
+        )?;
+
+        // Owner key must be a real secp256k1 point, or no signature could ever be checked against it
+        ensure(validate_owner_pubkey(&self.owner_pubkey), ValidationError::InvalidOwnerPubkey)?;
+        ensure(
+            self.co_signers.iter().all(|key| validate_owner_pubkey(key)),
+            ValidationError::InvalidCoSigner,
+        )?;
+
+        // The contract's rules must be satisfiable, and everything is checked against them
+        let rules = &self.rule_set;
+        ensure(rules.is_consistent(), ValidationError::InconsistentRuleSet)?;
+
+        check_beneficiaries(&self.beneficiaries, self.total_locked_sats, self.network, rules)?;
+        if let Some(index) = dust_share_at_expected_funding(self) {
+            return Err(ValidationError::DustAtExpectedFunding { index });
+        }
+
+        // Unless the owner allowed it, no beneficiary may be the owner's own key
+        ensure(validate_owner_collision(self), ValidationError::OwnerIsBeneficiary)?;
+        validate_fallback_address(self).map_err(ValidationError::FallbackAddress)?;
+        ensure(validate_guardians(self), ValidationError::InvalidGuardians)?;
+        ensure(validate_recovery_mode(self), ValidationError::InvalidRecoveryMode)?;
+        ensure(validate_oracle_pubkey(self), ValidationError::InvalidOraclePubkey)?;
+        ensure(validate_metadata(&self.metadata, rules), ValidationError::InvalidMetadata)?;
+        ensure(validate_will_document(self), ValidationError::InvalidWillDocumentHash)?;
+        ensure(validate_emergency_contact(self), ValidationError::InvalidEmergencyContact)?;
+
+        // A check-in threshold must be reachable by the owner and co-signers together
+        ensure(
+            self.multisig_threshold as usize <= checkin_signers(self).len(),
+            ValidationError::UnreachableMultisigThreshold,
+        )?;
+        ensure(validate_court_pubkey(self), ValidationError::InvalidCourtPubkey)?;
+
+        // Delay must be reasonable (at least the rule set's minimum, and within the network's bounds)
+        ensure(
+            self.trigger_delay_blocks >= rules.minimum_delay_blocks,
+            ValidationError::DelayBelowRuleSet {
+                minimum: rules.minimum_delay_blocks,
+                provided: self.trigger_delay_blocks,
+            },
+        )?;
+        validate_trigger_delay_against_network(self.trigger_delay_blocks, self.network).map_err(ValidationError::Delay)?;
+
+        // A decay policy must have a real interval and a non-zero floor below the full delay
+        if let Some(decay) = &self.delay_decay {
+            ensure(
+                decay.interval_blocks > 0 && decay.floor_blocks > 0 && decay.floor_blocks <= self.trigger_delay_blocks,
+                ValidationError::InvalidDelayDecay,
+            )?;
+        }
+
+        // A grace period extends the delay by at most a quarter
+        if let Some(grace) = &self.grace_period {
+            ensure(grace.extra_blocks <= self.trigger_delay_blocks / 4, ValidationError::InvalidGracePeriod)?;
+        }
+
+        // A check-in window can't open before the last check-in
+        ensure(
+            self.checkin_window_blocks <= self.trigger_delay_blocks,
+            ValidationError::InvalidCheckinWindow,
+        )?;
+
+        // Content must fit within the size cap
+        validate_size(self).map_err(ValidationError::Content)
+    }
+}
+
+impl Beneficiary {
+    /// Checks the entry on its own: a sane allocation and a usable payee
+    ///
+    /// Rules about the list as a whole (count, totals) are checked by
+    /// InheritanceContent::validate.
+    pub fn validate(&self) -> Result<(), BeneficiaryError> {
+        match self.allocation {
+            Allocation::Share(share_bps) => ensure(share_bps <= FULL_SHARE, BeneficiaryError::ShareAboveWhole)?,
+            Allocation::Fixed(sats) => ensure(sats >= DUST_THRESHOLD_SATS, BeneficiaryError::FixedBelowDust)?,
+            Allocation::PreciseShare(share) => ensure(share.0 <= FULL_SHARE_PPM, BeneficiaryError::ShareAboveWhole)?,
+        }
+        ensure(validate_payee(&self.payee), BeneficiaryError::InvalidPayee)
+    }
+}
+
+fn ensure<E>(condition: bool, error: E) -> Result<(), E> {
+    if condition {
+        Ok(())
+    } else {
+        Err(error)
+    }
+}
+
+/// Rejects content whose serialized form exceeds MAX_CONTENT_BYTES
+///
+/// Fields can each be within their own limits and still add up to a charm
+/// too large to prove cheaply, so the total is checked separately.
+pub fn validate_size(content: &InheritanceContent) -> Result<(), ContractError> {
+    validate_size_with_cap(content, MAX_CONTENT_BYTES)
+}
+
+/// Same as validate_size, but with a caller-provided byte cap
+pub fn validate_size_with_cap(content: &InheritanceContent, max: usize) -> Result<(), ContractError> {
+    let size = content_size(content);
+    if size > max {
+        return Err(ContractError::ContentTooLarge { size, max });
+    }
+    Ok(())
+}
+
+/// The one NFT a check-in or update carries the contract on in
+///
+/// Wallets that build the spell by hand easily forget the continuation output,
+/// so its absence gets an error of its own rather than a bare rejection.
+pub fn continuation_nft<'a>(app: &'a App, tx: &'a Transaction) -> Result<&'a Data, ContractError> {
+    let output_charms: Vec<&Data> = charm_values(app, tx.outs.iter()).collect();
+    match output_charms[..] {
+        [data] => Ok(data),
+        [] => Err(ContractError::MissingOutputNft),
+        _ => Err(ContractError::ExtraOutputNfts { count: output_charms.len() }),
+    }
+}
+
+/// Size in bytes of the content as serialized into the NFT charm
+pub fn content_size(content: &InheritanceContent) -> usize {
+    Data::from(content).bytes().len()
+}
+
+/// Hash of everything a check-in must leave unchanged
+///
+/// The content_hash with last_checkin_block and missed_count zeroed, so a
+/// check-in is checked with one comparison, and fields added later are covered
+/// without anyone remembering to compare them.
+pub fn content_commitment(content: &InheritanceContent) -> B32 {
+    let committed = InheritanceContent {
+        last_checkin_block: 0,
+        missed_count: 0,
+        ..content.clone()
+    };
+    committed.content_hash()
+}
+
+/// BIP-340 tagged hash: SHA-256(SHA-256(tag) || SHA-256(tag) || data)
+///
+/// The tag keeps hashes made for one purpose from being valid for another.
+fn tagged_hash(tag: &[u8], data: &[u8]) -> [u8; 32] {
+    let tag = Sha256::digest(tag);
+    Sha256::new().chain_update(tag).chain_update(tag).chain_update(data).finalize().into()
+}
+
+/// `value` as deterministic CBOR (see InheritanceContent::canonical_bytes)
+fn canonical_cbor<T: Serialize>(value: &T) -> Vec<u8> {
+    let mut value = ciborium::Value::serialized(value).expect("contract types always serialize");
+    canonicalize(&mut value);
+    let mut bytes = Vec::new();
+    ciborium::into_writer(&value, &mut bytes).expect("writing to a Vec can't fail");
+    bytes
+}
+
+/// Sorts every map in `value`, at any depth, into deterministic CBOR key order
+///
+/// RFC 8949 §4.2.1: keys are ordered by their own encoded bytes, so shorter text
+/// keys come first and keys of equal length compare bytewise.
+fn canonicalize(value: &mut ciborium::Value) {
+    match value {
+        ciborium::Value::Map(entries) => {
+            for (key, value) in entries.iter_mut() {
+                canonicalize(key);
+                canonicalize(value);
+            }
+            entries.sort_by_cached_key(|(key, _)| {
+                let mut encoded = Vec::new();
+                ciborium::into_writer(key, &mut encoded).expect("writing to a Vec can't fail");
+                encoded
+            });
+        }
+        ciborium::Value::Array(items) => items.iter_mut().for_each(canonicalize),
+        ciborium::Value::Tag(_, inner) => canonicalize(inner),
+        _ => {}
+    }
+}
+
+// Identifies a beneficiary list as written, order included (see BeneficiarySetHash::of)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct BeneficiarySetHash {
+    pub beneficiary_hash: B32,   // SHA-256 of the list's serialized bytes
+}
+
+impl BeneficiarySetHash {
+    /// Hash of `beneficiaries` in the order they're listed
+    ///
+    /// Order is part of a contract (entry i of the distribution plan is what
+    /// beneficiary i is owed), so the same heirs listed in another order hash
+    /// differently.
+    pub fn of(beneficiaries: &[Beneficiary]) -> Self {
+        BeneficiarySetHash {
+            beneficiary_hash: B32(Sha256::digest(Data::from(&beneficiaries).bytes()).into()),
+        }
+    }
+}
+
+/// Whether two beneficiary lists are the same, in the same order
+#[deprecated(since = "0.2.0", note = "Use BeneficiarySetHash::eq instead")]
+pub fn beneficiaries_equal(a: &[Beneficiary], b: &[Beneficiary]) -> bool {
+    BeneficiarySetHash::of(a) == BeneficiarySetHash::of(b)
+}
+
+/// Hash of a beneficiary list that doesn't depend on the order it's written in
+///
+/// The canonical order is ASCII lexicographic on each payee's sanitized address
+/// (see sanitize_beneficiary_address; payees without an address sort by their
+/// Payee::label), ties broken by the Beneficiary ordering. The sorted list, with
+/// addresses in sanitized form, is hashed as deterministic CBOR with the tagged
+/// SHA-256 of BENEFICIARY_LIST_TAG. Changing any of this changes every hash, so it
+/// must come with a new SCHEMA_VERSION.
+///
+/// Unlike BeneficiarySetHash, which identifies a list as written, this only says
+/// who is owed what.
+pub fn hash_beneficiary_list(beneficiaries: &[Beneficiary]) -> B32 {
+    let mut sorted: Vec<Beneficiary> = beneficiaries
+        .iter()
+        .cloned()
+        .map(|mut beneficiary| {
+            if let Payee::Address(address) = &mut beneficiary.payee {
+                *address = sanitize_beneficiary_address(address);
+            }
+            beneficiary
+        })
+        .collect();
+    sorted.sort_by_cached_key(|beneficiary| (beneficiary.payee.label(), beneficiary.clone()));
+    B32(tagged_hash(BENEFICIARY_LIST_TAG, &canonical_cbor(&sorted)))
+}
+
+/// Whether two beneficiary lists name the same beneficiaries, in any order
+///
+/// For comparisons where position doesn't matter (e.g. whether two parties are
+/// signing off on the same heirs); the contract itself compares lists in order.
+pub fn same_beneficiaries_unordered(a: &[Beneficiary], b: &[Beneficiary]) -> bool {
+    let mut a = a.to_vec();
+    let mut b = b.to_vec();
+    a.sort();
+    b.sort();
+    a == b
+}
+
+/// Commitment to a distribution plan: tagged SHA-256 (tag PLAN_COMMITMENT_TAG)
+/// of its deterministic CBOR encoding
+///
+/// Order matters, as it does in the plan: entry i is what beneficiary i was owed.
+pub fn plan_commitment(plan: &[(Payee, u64)]) -> B32 {
+    B32(tagged_hash(PLAN_COMMITMENT_TAG, &canonical_cbor(&plan)))
+}
+
+/// Checks that a final distribution's tombstone records exactly what was paid out
+///
+/// Only contracts that asked for a tombstone may leave one.
+pub fn verify_tombstone(
+    app: &App,
+    inheritance: &InheritanceContent,
+    tx: &Transaction,
+    tombstone: &DistributionTombstone,
+) -> bool {
+    check!(inheritance.leave_tombstone);
+    check!(tombstone.status.is_terminal());
+
+    let Some(total_sats) = vault_input_sats(app, inheritance, tx) else {
+        return false;
+    };
+    check!(tombstone.distributed_sats == total_sats);
+    check!(tombstone.plan_commitment == plan_commitment(&compute_distribution_plan(inheritance, total_sats)));
+
+    true
+}
+
+/// Validates the owner's public key encoding
+///
+/// Accepts 64 hex chars (x-only, BIP-340) or 66 hex chars (compressed SEC1),
+/// and the decoded key must be a valid point on secp256k1.
+pub fn validate_owner_pubkey(owner_pubkey: &str) -> bool {
+    let Some(bytes) = decode_hex(owner_pubkey) else {
+        return false;
+    };
+
+    match bytes.len() {
+        32 => k256::schnorr::VerifyingKey::from_bytes(&bytes).is_ok(),
+        33 => k256::PublicKey::from_sec1_bytes(&bytes).is_ok(),
+        _ => false,
+    }
+}
+
+/// Decodes a hex string (either case), returning None on odd length or non-hex chars
+fn decode_hex(s: &str) -> Option<Vec<u8>> {
+    if !s.len().is_multiple_of(2) {
+        return None;
+    }
+
+    s.as_bytes()
+        .chunks(2)
+        .map(|pair| {
+            let hi = (pair[0] as char).to_digit(16)?;
+            let lo = (pair[1] as char).to_digit(16)?;
+            Some((hi * 16 + lo) as u8)
+        })
+        .collect()
+}
+
+/// Checks that a beneficiary list is correct for a vault holding `total_locked_sats`,
+/// reporting the first rule broken
+fn check_beneficiaries(
+    beneficiaries: &[Beneficiary],
+    total_locked_sats: u64,
+    network: Network,
+    rules: &RuleSet,
+) -> Result<(), ValidationError> {
+    // Must have at least one beneficiary, and as many as the rules require
+    let provided = beneficiaries.len();
+    let min = rules.min_beneficiaries.max(1);
+    ensure(provided >= min, ValidationError::TooFewBeneficiaries { min, provided })?;
+    ensure(
+        provided <= rules.max_beneficiaries,
+        ValidationError::TooManyBeneficiaries { max: rules.max_beneficiaries, provided },
+    )?;
+
+    // Each entry must be individually sane (no share above the whole, no unpayable
+    // fixed amount, a usable payee), and any address it pays must be a kind we know
+    // how to pay on the contract's network, as sanitize_beneficiary_address leaves it
+    for (index, beneficiary) in beneficiaries.iter().enumerate() {
+        beneficiary
+            .validate()
+            .map_err(|error| ValidationError::Beneficiary { index, error })?;
+
+        let address = match &beneficiary.payee {
+            Payee::Address(address) => Some(address),
+            Payee::Committed(committed) => Some(&committed.fallback_address),
+            _ => None,
+        };
+        if address.is_some_and(|address| SupportedAddress::classify(address, network).is_none()) {
+            let error = BeneficiaryError::UnsupportedAddress;
+            return Err(ValidationError::Beneficiary { index, error });
+        }
+    }
+
+    // Each address is listed once. They're compared sanitized, so case or stray
+    // whitespace doesn't make a second heir.
+    let mut addresses = BTreeSet::new();
+    for (index, beneficiary) in beneficiaries.iter().enumerate() {
+        if let Payee::Address(address) = &beneficiary.payee {
+            ensure(
+                addresses.insert(sanitize_beneficiary_address(address)),
+                ValidationError::DuplicateAddress { index },
+            )?;
+        }
+    }
+
+    // Fixed amounts are paid first, so together they must fit in the vault
+    let fixed_total = fixed_allocation_total(beneficiaries);
+    ensure(
+        fixed_total.is_some_and(|total| total <= total_locked_sats),
+        ValidationError::FixedExceedsVault,
+    )?;
+
+    // Shares split the remainder and must sum to exactly 100% (10000 bps, or
+    // 1_000_000 ppm). Without any, the fixed amounts must account for the whole
+    // vault: there is no share entry to take what they leave over.
+    let shares: Vec<u32> = beneficiaries.iter().filter_map(|b| b.allocation.share_ppm()).collect();
+    let whole = if shares.is_empty() {
+        fixed_total == Some(total_locked_sats)
+    } else {
+        shares.iter().map(|&share_ppm| share_ppm as u64).sum::<u64>() == FULL_SHARE_PPM as u64
+    };
+    ensure(whole, ValidationError::SharesNotWhole)
+}
+
+/// The first share that would be dust if the vault held only min_expected_funding
+///
+/// Catches splits that could never pay an heir before the vault is funded. Shares
+/// split what the fixed amounts leave, so those come off first. Without an
+/// expectation (0) nothing is checked.
+fn dust_share_at_expected_funding(content: &InheritanceContent) -> Option<usize> {
+    if content.min_expected_funding == 0 {
+        return None;
+    }
+    let fixed_total = fixed_allocation_total(&content.beneficiaries).unwrap_or(u64::MAX);
+    let remainder = content.min_expected_funding.saturating_sub(fixed_total);
+
+    content.beneficiaries.iter().position(|b| {
+        b.allocation.share_ppm().is_some_and(|share_ppm| {
+            let amount = remainder as u128 * share_ppm as u128 / FULL_SHARE_PPM as u128;
+            amount < DUST_THRESHOLD_SATS as u128
+        })
+    })
+}
+
+/// Validates a payee: a non-empty address of bounded length, a parseable xpub
+/// with a non-empty, non-hardened index range, or a non-empty script of bounded length
+///
+/// Addresses are judged as sanitize_beneficiary_address leaves them, which is also
+/// how they're decoded at distribution. Scripts aren't checked for standardness:
+/// an heir may need a script type that doesn't exist yet.
+fn validate_payee(payee: &Payee) -> bool {
+    match payee {
+        Payee::Address(address) => {
+            let address = sanitize_beneficiary_address(address);
+            !address.is_empty() && address.len() <= MAX_ADDRESS_LEN
+        }
+        Payee::Script(script) => !script.script_pubkey.is_empty() && script.script_pubkey.len() <= MAX_SCRIPT_LEN,
+        Payee::Committed(committed) => validate_payee(&Payee::Address(committed.fallback_address.clone())),
+        Payee::Lightning(lightning) => {
+            Offer::parse(&lightning.offer).is_some()
+                && validate_payee(&Payee::Script(ScriptPayee {
+                    script_pubkey: lightning.settlement_script.clone(),
+                }))
+        }
+        Payee::Xpub(xpub) => {
+            xpub.first_index <= xpub.last_index
+                && xpub.last_index < HARDENED_INDEX
+                && ExtendedPubKey::from_base58(&xpub.xpub).is_some()
+        }
+    }
+}
+
+/// Checks the contract's fallback address, if it has one
+///
+/// It must be an address on the contract's network, and distinct from every
+/// beneficiary: funds routed to it shouldn't quietly top up one of them.
+pub fn validate_fallback_address(inheritance: &InheritanceContent) -> Result<(), FallbackAddressError> {
+    let Some(address) = &inheritance.fallback_address else {
+        return Ok(());
+    };
+    if address.len() > MAX_ADDRESS_LEN {
+        return Err(FallbackAddressError::Malformed);
+    }
+    let Some(script) = address_to_script(address) else {
+        return Err(FallbackAddressError::Malformed);
+    };
+    if !address_on_network(address, inheritance.network) {
+        return Err(FallbackAddressError::WrongNetwork);
+    }
+    if inheritance.beneficiaries.iter().any(|b| b.payee.fixed_script() == Some(script.clone())) {
+        return Err(FallbackAddressError::IsBeneficiary);
+    }
+    Ok(())
+}
+
+/// Validates the guardian list and its threshold
+///
+/// Every key must be usable for signatures, and no key may be listed twice (in
+/// either encoding) so one guardian can't count towards the quorum more than once.
+/// With guardians the threshold must be between 1 and their count; without any it must be 0.
+/// An emergency delay needs guardians to use it, and must be shorter than the
+/// trigger delay it cuts short.
+fn validate_guardians(inheritance: &InheritanceContent) -> bool {
+    let guardians = &inheritance.guardians;
+    check!(guardians.len() <= MAX_GUARDIANS);
+    check!(guardians.iter().all(|key| validate_owner_pubkey(key)));
+
+    let mut keys: Vec<Vec<u8>> = guardians
+        .iter()
+        .filter_map(|key| decode_hex(key))
+        .map(|bytes| xonly_bytes(&bytes).to_vec())
+        .collect();
+    keys.sort();
+    keys.dedup();
+    check!(keys.len() == guardians.len());
+
+    let threshold = inheritance.guardian_threshold as usize;
+    if guardians.is_empty() {
+        check!(threshold == 0);
+    } else {
+        check!((1..=guardians.len()).contains(&threshold));
+    }
+
+    let emergency_delay = inheritance.guardian_emergency_delay;
+    check!(emergency_delay == 0 || (!guardians.is_empty() && emergency_delay < inheritance.trigger_delay_blocks));
+
+    true
+}
+
+/// Validates the contract's recovery mode, if it has one
+///
+/// A beneficiary majority must be more than half the estate (and no more than all of
+/// it), a full-recovery key must be usable for signatures, and a co-signer override
+/// needs guardians to do the co-signing.
+fn validate_recovery_mode(inheritance: &InheritanceContent) -> bool {
+    match &inheritance.recovery_mode {
+        None => true,
+        Some(ContractRecoveryMode::FullRecovery { owner_key }) => validate_owner_pubkey(&pubkey_hex(owner_key)),
+        Some(ContractRecoveryMode::BeneficiaryMajority { threshold_basis_points }) => {
+            (FULL_SHARE / 2 + 1..=FULL_SHARE).contains(threshold_basis_points)
+        }
+        Some(ContractRecoveryMode::CoSignerOverride) => !inheritance.guardians.is_empty(),
+    }
+}
+
+/// The oracle's key, if the contract has one, must be usable for signatures
+fn validate_oracle_pubkey(inheritance: &InheritanceContent) -> bool {
+    inheritance.oracle_pubkey.as_deref().is_none_or(validate_owner_pubkey)
+}
+
+/// The court's key, if the contract has one, must be usable for signatures
+fn validate_court_pubkey(inheritance: &InheritanceContent) -> bool {
+    inheritance.court_pubkey.as_ref().is_none_or(|court| validate_owner_pubkey(&pubkey_hex(court)))
+}
+
+/// The emergency contact's key, if the contract has one, must be usable for signatures
+fn validate_emergency_contact(inheritance: &InheritanceContent) -> bool {
+    inheritance.emergency_contact_pubkey.as_deref().is_none_or(validate_owner_pubkey)
+}
+
+/// The will document hash, if the contract has one, mustn't be all zeros
+///
+/// A zeroed hash is what a wallet writes when it forgot to hash the will, and
+/// no document hashes to it.
+fn validate_will_document(inheritance: &InheritanceContent) -> bool {
+    inheritance.will_document_hash.is_none_or(|hash| hash != B32([0; 32]))
+}
+
+/// Checks the metadata map's bounds: at most MAX_METADATA_ENTRIES non-empty keys of
+/// up to MAX_METADATA_KEY_LEN bytes, each value no longer than the rules' max_note_length
+fn validate_metadata(metadata: &BTreeMap<String, String>, rules: &RuleSet) -> bool {
+    check!(metadata.len() <= MAX_METADATA_ENTRIES);
+    check!(metadata
+        .iter()
+        .all(|(key, value)| !key.is_empty() && key.len() <= MAX_METADATA_KEY_LEN && value.len() <= rules.max_note_length));
+
+    true
+}
+
+/// Checks that enough distinct guardians signed `msg` to meet the contract's threshold
+///
+/// `signatures` pairs a guardian's key, as listed in the contract, with their
+/// BIP-340 signature. Keys that aren't guardians, bad signatures and repeat
+/// signers are ignored. A contract without guardians never has a quorum.
+pub fn verify_guardian_quorum(content: &InheritanceContent, msg: &[u8; 32], signatures: &[(String, Vec<u8>)]) -> bool {
+    if content.guardian_threshold == 0 {
+        return false;
+    }
+
+    let mut signers: Vec<usize> = signatures
+        .iter()
+        .filter(|(key, sig)| verify_owner_signature(key, msg, sig))
+        .filter_map(|(key, _)| content.guardians.iter().position(|guardian| guardian == key))
+        .collect();
+    signers.sort();
+    signers.dedup();
+
+    signers.len() >= content.guardian_threshold as usize
+}
+
+/// Basis points of the estate held by beneficiaries who signed `msg`
+///
+/// `sigs[i]` is beneficiary i's BIP-340 signature (empty if they didn't sign).
+/// Only beneficiaries paid to a taproot key can sign: their output key is the
+/// key checked. Fixed allocations carry no basis points (and parts-per-million
+/// shares are added up before rounding down to basis points), and a message that
+/// isn't a 32-byte digest has no signers.
+pub fn verify_beneficiary_signatures(beneficiaries: &[Beneficiary], sigs: &[Vec<u8>], msg: &[u8]) -> usize {
+    let Ok(msg) = <&[u8; 32]>::try_from(msg) else {
+        return 0;
+    };
+
+    let signed_ppm: usize = beneficiaries
+        .iter()
+        .zip(sigs)
+        .filter(|(beneficiary, sig)| {
+            beneficiary_signing_key(&beneficiary.payee).is_some_and(|key| verify_owner_signature(&key, msg, sig))
+        })
+        .filter_map(|(beneficiary, _)| beneficiary.allocation.share_ppm())
+        .map(|share_ppm| share_ppm as usize)
+        .sum();
+    signed_ppm / 100
+}
+
+/// Whether the beneficiaries may trigger `tx` before the deadline
+///
+/// Only in BeneficiaryMajority mode, and only if the beneficiaries who signed
+/// signing_message(TriggerDistribution, ..) over the contract being spent hold at
+/// least the mode's threshold (see verify_beneficiary_signatures).
+fn beneficiary_majority_authorizes(inheritance: &InheritanceContent, tx: &Transaction, witness: &DistributionWitness) -> bool {
+    let Some(ContractRecoveryMode::BeneficiaryMajority { threshold_basis_points }) = inheritance.recovery_mode else {
+        return false;
+    };
+    let msg = signing_message(Operation::TriggerDistribution, tx, inheritance);
+    verify_beneficiary_signatures(&inheritance.beneficiaries, &witness.beneficiary_signatures, &msg)
+        >= threshold_basis_points as usize
+}
+
+/// The x-only key (hex) a beneficiary signs with: the output key of a taproot payee
+fn beneficiary_signing_key(payee: &Payee) -> Option<String> {
+    let script = match payee {
+        Payee::Address(_) | Payee::Script(_) => payee.fixed_script()?,
+        _ => return None,
+    };
+    match script.as_slice() {
+        [0x51, 0x20, key @ ..] if key.len() == 32 => Some(key.iter().map(|b| format!("{b:02x}")).collect()),
+        _ => None,
+    }
+}
+
+/// Rejects beneficiaries paid to the owner's key if the contract's rules say so
+///
+/// Off by default: some owners list themselves on purpose, e.g. to recover part
+/// of the vault in a joint arrangement.
+fn validate_owner_collision(inheritance: &InheritanceContent) -> bool {
+    !inheritance.rule_set.reject_owner_beneficiary || owner_beneficiaries(inheritance).is_empty()
+}
+
+/// Positions of the beneficiaries paid to a script spendable by the owner's key
+///
+/// Only fixed addresses and raw scripts are compared; xpub and committed payees
+/// can't be linked to the owner without knowing more. Wallets can use this to
+/// warn about self-payments even when the contract allows them.
+pub fn owner_beneficiaries(inheritance: &InheritanceContent) -> Vec<usize> {
+    let owner_scripts = owner_scripts(&inheritance.owner_pubkey);
+    inheritance
+        .beneficiaries
+        .iter()
+        .enumerate()
+        .filter(|(_, b)| b.payee.fixed_script().is_some_and(|script| owner_scripts.contains(&script)))
+        .map(|(index, _)| index)
+        .collect()
+}
+
+/// The single-key scripts the owner's key can spend: P2PKH, P2WPKH and BIP-86 P2TR
+///
+/// An x-only owner key says nothing about its parity, so both compressed forms are
+/// tried for the key-hash scripts. Empty if the key doesn't decode.
+pub fn owner_scripts(owner_pubkey: &str) -> Vec<Vec<u8>> {
+    let Some(bytes) = decode_hex(owner_pubkey) else {
+        return Vec::new();
+    };
+    let xonly = xonly_bytes(&bytes);
+    let compressed: Vec<[u8; 33]> = match bytes.len() {
+        33 => bytes.as_slice().try_into().into_iter().collect(),
+        32 => [0x02, 0x03]
+            .iter()
+            .filter_map(|&parity| [&[parity][..], xonly].concat().try_into().ok())
+            .collect(),
+        _ => return Vec::new(),
+    };
+
+    let mut scripts = Vec::new();
+    for key in &compressed {
+        scripts.push([&[0x76, 0xa9, 0x14][..], &hash160(key), &[0x88, 0xac]].concat());
+        scripts.push(p2wpkh_script(key));
+    }
+    scripts.extend(p2tr_key_path_script(xonly));
+    scripts
+}
+
+/// The P2TR scriptPubKey for an x-only key with no script tree (BIP-86)
+fn p2tr_key_path_script(xonly: &[u8]) -> Option<Vec<u8>> {
+    let internal = k256::PublicKey::from_sec1_bytes(&[&[0x02][..], xonly].concat()).ok()?;
+
+    let tweak = tagged_hash(b"TapTweak", xonly);
+    let tweak = Option::<k256::Scalar>::from(k256::Scalar::from_repr(tweak.into()))?;
+
+    let output = k256::ProjectivePoint::GENERATOR * tweak + internal.to_projective();
+    let output = k256::PublicKey::from_affine(output.to_affine()).ok()?;
+
+    let mut script = vec![0x51, 0x20];
+    script.extend(&output.to_encoded_point(true).as_bytes()[1..]);
+    Some(script)
+}
+
+/// The keys that may sign a check-in: the owner followed by the co-signers
+fn checkin_signers(inheritance: &InheritanceContent) -> Vec<String> {
+    std::iter::once(inheritance.owner_pubkey.clone())
+        .chain(inheritance.co_signers.iter().cloned())
+        .collect()
+}
+
+fn is_zero<T: Default + PartialEq>(n: &T) -> bool {
+    *n == T::default()
+}
+
+/// Sum of all fixed allocations, or None if it overflows
+fn fixed_allocation_total(beneficiaries: &[Beneficiary]) -> Option<u64> {
+    beneficiaries
+        .iter()
+        .filter_map(|b| b.allocation.fixed_sats())
+        .try_fold(0u64, |total, sats| total.checked_add(sats))
+}
+
+impl Payee {
+    /// The address, if this payee is a fixed address
+    pub fn as_address(&self) -> Option<&str> {
+        match self {
+            Payee::Address(address) => Some(address),
+            _ => None,
+        }
+    }
+
+    /// A string identifying the payee in reports: the address, xpub or BOLT12
+    /// offer, the hex commitment of a committed payee, or a raw script in hex
+    pub fn label(&self) -> String {
+        match self {
+            Payee::Address(address) => address.clone(),
+            Payee::Xpub(xpub) => xpub.xpub.clone(),
+            Payee::Script(script) => script.script_pubkey.iter().map(|byte| format!("{byte:02x}")).collect(),
+            Payee::Committed(committed) => committed.commitment.to_string(),
+            Payee::Lightning(lightning) => lightning.offer.clone(),
+        }
+    }
+
+    /// The script this payee is always paid at: addresses are converted, raw
+    /// scripts used as-is, Lightning payees paid to their settlement script.
+    /// None for xpub and committed payees, whose script is chosen at distribution.
+    pub fn fixed_script(&self) -> Option<Vec<u8>> {
+        match self {
+            Payee::Address(address) => address_to_script(address),
+            Payee::Script(script) => Some(script.script_pubkey.clone()),
+            Payee::Lightning(lightning) => Some(lightning.settlement_script.clone()),
+            Payee::Xpub(_) | Payee::Committed(_) => None,
+        }
+    }
+}
+
+/// Commitment to a private beneficiary address: SHA-256(address || salt)
+///
+/// The salt keeps the address from being brute-forced out of the commitment;
+/// it should be 32 random bytes, given to the heir alongside the address.
+pub fn commit_payee(address: &str, salt: &B32) -> B32 {
+    let mut hasher = Sha256::new();
+    hasher.update(address.as_bytes());
+    hasher.update(salt.0);
+    B32(hasher.finalize().into())
+}
+
+impl From<&str> for Payee {
+    fn from(address: &str) -> Self {
+        Payee::Address(address.to_string())
+    }
+}
+
+impl Allocation {
+    /// The share in basis points, if this is a share allocation
+    pub fn share_bps(&self) -> Option<u16> {
+        match *self {
+            Allocation::Share(share_bps) => Some(share_bps),
+            Allocation::Fixed(_) | Allocation::PreciseShare(_) => None,
+        }
+    }
+
+    /// The share in parts per million, if this is a share allocation of either precision
+    pub fn share_ppm(&self) -> Option<u32> {
+        match *self {
+            Allocation::Share(share_bps) => Some(SharePpm::from_bps(share_bps).0),
+            Allocation::PreciseShare(share) => Some(share.0),
+            Allocation::Fixed(_) => None,
+        }
+    }
+
+    /// The amount in sats, if this is a fixed allocation
+    pub fn fixed_sats(&self) -> Option<u64> {
+        match *self {
+            Allocation::Fixed(sats) => Some(sats),
+            Allocation::Share(_) | Allocation::PreciseShare(_) => None,
+        }
+    }
+}
+
+impl CreateWitness {
+    /// Parses the funding UTXO IDs
+    ///
+    /// Returns None if the list is empty, contains duplicates, or any entry is not a valid UTXO ID.
+    pub fn utxo_ids(&self) -> Option<Vec<UtxoId>> {
+        let encoded = match self {
+            CreateWitness::Single(utxo_id) => vec![utxo_id.clone()],
+            CreateWitness::Multi(utxo_ids) => utxo_ids.clone(),
+        };
+
+        let utxo_ids = encoded
+            .iter()
+            .map(|s| UtxoId::from_str(s).ok())
+            .collect::<Option<Vec<_>>>()?;
+        if utxo_ids.is_empty() {
+            return None;
+        }
+
+        let mut unique: Vec<String> = utxo_ids.iter().map(|utxo_id| utxo_id.to_string()).collect();
+        unique.sort();
+        unique.dedup();
+        if unique.len() != utxo_ids.len() {
+            return None;
+        }
+
+        Some(utxo_ids)
+    }
+}
+
+/// Derives the app identity from the funding UTXOs
+///
+/// Hashes the sorted canonical encodings joined by ','. A single UTXO hashes to
+/// exactly `hash(utxo_id)`, so single-input vaults keep their original identity,
+/// and any strict subset of the list yields a different identity.
+pub fn composite_identity(utxo_ids: &[UtxoId]) -> B32 {
+    let mut encoded: Vec<String> = utxo_ids.iter().map(|utxo_id| utxo_id.to_string()).collect();
+    encoded.sort();
+    hash(&encoded.join(","))
+}
+
+/// Finds which of `candidates` is the funding UTXO a contract's identity was derived from
+///
+/// Identities are hashes, so this can only check guesses: it returns the first
+/// candidate whose hash matches. Vaults funded by several UTXOs won't match any
+/// single candidate; use composite_identity on the full set instead.
+pub fn decode_app_identity(identity: &B32, candidates: &[UtxoId]) -> Option<UtxoId> {
+    candidates
+        .iter()
+        .find(|utxo_id| hash(&utxo_id.to_string()) == *identity)
+        .cloned()
+}
+
+/// Returns the inputs that carry a charm with this app's identity
+///
+/// Unlike charm_values this matches on identity alone, so a same-identity charm
+/// under a different tag or vk is still counted.
+fn inputs_with_app_identity<'a>(app: &App, tx: &'a Transaction) -> Vec<&'a UtxoId> {
+    tx.ins
+        .iter()
+        .filter(|(_, charms)| charms.keys().any(|a| a.identity == app.identity))
+        .map(|(utxo_id, _)| utxo_id)
+        .collect()
+}
+
+/// Trigger delay in effect at `current_block`
+///
+/// Without a decay policy this is just trigger_delay_blocks. With one, the delay
+/// drops by step_blocks for every full interval_blocks of silence since the last
+/// check-in, never going below floor_blocks.
+pub fn effective_delay(content: &InheritanceContent, current_block: u64) -> u64 {
+    let Some(decay) = &content.delay_decay else {
+        return content.trigger_delay_blocks;
+    };
+
+    let silent_blocks = current_block.saturating_sub(content.last_checkin_block);
+    let missed_intervals = silent_blocks.checked_div(decay.interval_blocks).unwrap_or(0);
+    let reduction = missed_intervals.saturating_mul(decay.step_blocks);
+
+    let floor = decay.floor_blocks.min(content.trigger_delay_blocks);
+    content.trigger_delay_blocks.saturating_sub(reduction).max(floor)
+}
+
+/// Amount owed for a share of `total_sats`, rounded down
+pub fn share_amount(total_sats: u64, share_bps: u16) -> u64 {
+    (total_sats as u128 * share_bps as u128 / FULL_SHARE as u128) as u64
+}
+
+/// Whether a share of `total_sats` would be too small to pay out as its own output
+pub fn is_dust_share(total_sats: u64, share_bps: u16) -> bool {
+    share_amount(total_sats, share_bps) < DUST_THRESHOLD_SATS
+}
+
+/// Block height after which the inheritance can be triggered, as seen at `current_block`
+pub fn deadline_block(inheritance: &InheritanceContent, current_block: u64) -> u64 {
+    inheritance
+        .last_checkin_block
+        .saturating_add(effective_delay(inheritance, current_block))
+}
+
+/// Earliest block height a check-in counts at: checkin_window_blocks before the
+/// deadline, or the last check-in itself for contracts without a window
+///
+/// Owner and oracle check-ins both wait for the window, as does an update that
+/// moves the last check-in forward. One that leaves it alone can be made any time.
+pub fn checkin_window_opens(inheritance: &InheritanceContent, current_block: u64) -> u64 {
+    if inheritance.checkin_window_blocks == 0 {
+        return inheritance.last_checkin_block;
+    }
+    deadline_block(inheritance, current_block).saturating_sub(inheritance.checkin_window_blocks)
+}
+
+/// Checks whether the deadline has passed (current block > last_checkin + delay)
+pub fn is_expired(inheritance: &InheritanceContent, current_block: u64) -> bool {
+    current_block > deadline_block(inheritance, current_block)
+}
+
+/// Block height after which the inheritance can be distributed: the deadline,
+/// plus the grace period if the contract has one
+pub fn grace_deadline_block(inheritance: &InheritanceContent, current_block: u64) -> u64 {
+    let extra_blocks = inheritance.grace_period.as_ref().map_or(0, |grace| grace.extra_blocks);
+    deadline_block(inheritance, current_block).saturating_add(extra_blocks)
+}
+
+/// Block height after which the next miss can be recorded (see check_record_miss)
+///
+/// The n-th miss since the last check-in is for the n-th deadline after it.
+pub fn miss_deadline_block(inheritance: &InheritanceContent, current_block: u64) -> u64 {
+    let periods = inheritance.missed_count as u64 + 1;
+    inheritance
+        .last_checkin_block
+        .saturating_add(effective_delay(inheritance, current_block).saturating_mul(periods))
+}
+
+/// Whether the vault can be distributed at `current_block`
+///
+/// The one place the status, deadline and grace rules are combined: an Active
+/// vault becomes distributable once the deadline and its grace period have
+/// passed (the last block of either is still the owner's to check in) and the
+/// allowed_missed_checkins have been recorded, a Triggered one stays
+/// distributable, and a Distributed one never is.
+pub fn is_distributable(content: &InheritanceContent, current_block: u64) -> bool {
+    match content.status {
+        InheritanceStatus::Active => {
+            content.missed_count >= content.allowed_missed_checkins
+                && current_block > grace_deadline_block(content, current_block)
+        }
+        InheritanceStatus::Triggered => true,
+        InheritanceStatus::Distributed => false,
+    }
+}
+
+/// Whether `beneficiary` can be paid at `current_block` given when the vault was triggered
+///
+/// Beneficiaries without a vesting delay can always be paid. The others need the vault
+/// to have been triggered at least `vesting_delay_blocks` before `current_block`; with
+/// either height unknown they can't be paid yet.
+pub fn is_vested(beneficiary: &Beneficiary, trigger_height: Option<u64>, current_block: Option<u64>) -> bool {
+    if beneficiary.vesting_delay_blocks == 0 {
+        return true;
+    }
+    match (trigger_height, current_block) {
+        (Some(trigger), Some(current)) => current >= trigger.saturating_add(beneficiary.vesting_delay_blocks),
+        _ => false,
+    }
+}
+
+fn is_false(b: &bool) -> bool {
+    !*b
+}
+/// Digest that app identities are derived with
+///
+/// Identities are fixed when a vault is created, so a hasher's output must never
+/// change. Switching DefaultIdentityHasher to a different digest is a breaking
+/// change and must come with a new VERSION.
+pub trait IdentityHasher {
+    /// Identifies the digest, bumped whenever the default hasher changes
+    const VERSION: u8;
+
+    fn hash(data: &[u8]) -> B32;
+}
+
+/// Plain SHA-256, the digest every existing vault identity was derived with
+pub struct Sha256Hasher;
+
+impl IdentityHasher for Sha256Hasher {
+    const VERSION: u8 = 0;
+
+    fn hash(data: &[u8]) -> B32 {
+        B32(Sha256::digest(data).into())
+    }
+}
+
+/// The hasher used by `hash()`
+pub type DefaultIdentityHasher = Sha256Hasher;
+
+/// Hash function for creating app identity from UTXO ID
+pub(crate) fn hash(data: &str) -> B32 {
+    DefaultIdentityHasher::hash(data.as_bytes())
+}
+
+/// Hex encoding of a key, as the contract's string-keyed fields hold them
+fn pubkey_hex(pubkey: &OwnerPubkey) -> String {
+    pubkey.0.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// Drops the parity prefix from a compressed key, leaving the 32-byte x coordinate
+fn xonly_bytes(pubkey: &[u8]) -> &[u8] {
+    if pubkey.len() == 33 {
+        &pubkey[1..]
+    } else {
+        pubkey
+    }
+}
+
+//
+// ==================== VERSIONED CONTENT ====================
+//
+
+impl From<InheritanceContentV1> for InheritanceContent {
+    fn from(v1: InheritanceContentV1) -> Self {
+        InheritanceContent {
+            owner_pubkey: v1.owner_pubkey,
+            co_signers: v1.co_signers,
+            multisig_threshold: v1.multisig_threshold,
+            last_checkin_block: v1.last_checkin_block,
+            trigger_delay_blocks: v1.trigger_delay_blocks,
+            beneficiaries: v1.beneficiaries.into_iter().map(Beneficiary::from).collect(),
+            status: v1.status,
+            delay_decay: v1.delay_decay,
+            version: CONTENT_VERSION,
+            rule_set: RuleSet::default(),
+            total_locked_sats: 0,
+            network: Network::default(),
+            trigger_height: None,
+            fallback_address: None,
+            created_at_block: 0,
+            guardians: Vec::new(),
+            guardian_threshold: 0,
+            rounding_mode: RoundingMode::default(),
+            metadata: BTreeMap::new(),
+            leave_tombstone: false,
+            policy_max_gap: 0,
+            recovery_mode: None,
+            min_expected_funding: 0,
+            oracle_pubkey: None,
+            will_document_hash: None,
+            will_locked: false,
+            emergency_contact_pubkey: None,
+            emergency_extension_used: false,
+            grace_period: None,
+            allowed_missed_checkins: 0,
+            missed_count: 0,
+            court_pubkey: None,
+            guardian_emergency_delay: 0,
+            checkin_window_blocks: 0,
+        }
+    }
+}
+
+impl From<InheritanceContentV2> for InheritanceContent {
+    fn from(v2: InheritanceContentV2) -> Self {
+        InheritanceContent {
+            owner_pubkey: v2.owner_pubkey,
+            co_signers: v2.co_signers,
+            multisig_threshold: v2.multisig_threshold,
+            last_checkin_block: v2.last_checkin_block,
+            trigger_delay_blocks: v2.trigger_delay_blocks,
+            beneficiaries: v2.beneficiaries.into_iter().map(Beneficiary::from).collect(),
+            status: v2.status,
+            delay_decay: v2.delay_decay,
+            version: CONTENT_VERSION,
+            rule_set: v2.rule_set,
+            total_locked_sats: 0,
+            network: Network::default(),
+            trigger_height: None,
+            fallback_address: None,
+            created_at_block: 0,
+            guardians: Vec::new(),
+            guardian_threshold: 0,
+            rounding_mode: RoundingMode::default(),
+            metadata: BTreeMap::new(),
+            leave_tombstone: false,
+            policy_max_gap: 0,
+            recovery_mode: None,
+            min_expected_funding: 0,
+            oracle_pubkey: None,
+            will_document_hash: None,
+            will_locked: false,
+            emergency_contact_pubkey: None,
+            emergency_extension_used: false,
+            grace_period: None,
+            allowed_missed_checkins: 0,
+            missed_count: 0,
+            court_pubkey: None,
+            guardian_emergency_delay: 0,
+            checkin_window_blocks: 0,
+        }
+    }
+}
+
+impl From<BeneficiaryV1> for Beneficiary {
+    fn from(v1: BeneficiaryV1) -> Self {
+        Beneficiary {
+            payee: Payee::Address(v1.address),
+            allocation: Allocation::Share(v1.percentage as u16 * 100),
+            vesting_delay_blocks: 0,
+        }
+    }
+}
+
+impl From<BeneficiaryV2> for Beneficiary {
+    fn from(v2: BeneficiaryV2) -> Self {
+        Beneficiary {
+            payee: Payee::Address(v2.address),
+            allocation: Allocation::Share(v2.share_bps),
+            vesting_delay_blocks: 0,
+        }
+    }
+}
+
+/// Decodes an NFT's content in whatever layout it was minted with and brings it up to date
+///
+/// Every operation reads content through here, so vaults minted under an older
+/// layout keep validating after new fields are added.
+pub fn migrate_to_latest(data: &Data) -> Result<InheritanceContent, MigrationError> {
+    let versioned: VersionedContent = data.value().map_err(|_| MigrationError::Undecodable)?;
+
+    match versioned {
+        VersionedContent::Latest(content) => {
+            if !schema::is_compatible_version(content.version) {
+                return Err(MigrationError::UnsupportedVersion(content.version));
+            }
+            Ok(InheritanceContent {
+                version: CONTENT_VERSION,
+                ..*content
+            })
+        }
+        VersionedContent::V2(v2) => {
+            if v2.version != 2 {
+                return Err(MigrationError::UnsupportedVersion(v2.version));
+            }
+            Ok(v2.into())
+        }
+        VersionedContent::V1(v1) => {
+            if v1.version > 1 {
+                return Err(MigrationError::UnsupportedVersion(v1.version));
+            }
+            Ok(v1.into())
+        }
+    }
+}
+
+impl TryFrom<&Data> for InheritanceContent {
+    type Error = MigrationError;
+
+    /// Decodes an NFT's content the one way every operation does: strictly, in any
+    /// supported layout, migrated to the latest (see migrate_to_latest)
+    fn try_from(data: &Data) -> Result<Self, Self::Error> {
+        migrate_to_latest(data)
+    }
+}
+
+/// Version the content was written with, without migrating it
+fn content_version(data: &Data) -> Option<u8> {
+    match data.value::<VersionedContent>().ok()? {
+        VersionedContent::Latest(content) => Some(content.version),
+        VersionedContent::V2(v2) => Some(v2.version),
+        VersionedContent::V1(v1) => Some(v1.version),
+    }
+}
+
+/// The canonical 32-byte message an owner signs to authorize an operation
+///
+/// Commits to the operation, every input being spent (in order) and the resulting
+/// content. The inputs can only be spent once, so a signature can't be replayed
+/// against a later state. The off-chain signer must use exactly this function.
+pub fn signing_message(op: Operation, tx: &Transaction, content: &InheritanceContent) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(b"charmvault/signing-message");
+    hasher.update([op as u8]);
+
+    hasher.update((tx.ins.len() as u32).to_le_bytes());
+    for (utxo_id, _) in &tx.ins {
+        let encoded = utxo_id.to_string();
+        hasher.update((encoded.len() as u32).to_le_bytes());
+        hasher.update(encoded.as_bytes());
+    }
+
+    hasher.update(content.content_hash().0);
+    hasher.finalize().into()
+}
+
+/// Verifies a BIP-340 Schnorr signature by the owner over a 32-byte message
+///
+/// Compressed owner keys are verified through their x-only form.
+pub fn verify_owner_signature(owner_pubkey: &str, msg: &[u8; 32], sig: &[u8]) -> bool {
+    let Some(bytes) = decode_hex(owner_pubkey) else {
+        return false;
+    };
+    let Ok(key) = k256::schnorr::VerifyingKey::from_bytes(xonly_bytes(&bytes)) else {
+        return false;
+    };
+    // try_from panics on fewer bytes than half a signature, so check the length first
+    if sig.len() != k256::schnorr::Signature::BYTE_SIZE {
+        return false;
+    }
+    let Ok(sig) = k256::schnorr::Signature::try_from(sig) else {
+        return false;
+    };
+
+    key.verify_raw(msg, &sig).is_ok()
+}
+
+//
+// ==================== DISTRIBUTION ====================
+//
+
+/// Splits `total_sats` by basis-point shares: each share rounded down, with the
+/// leftover sats placed as `rounding` says so the amounts always sum to the total
+///
+/// Under LargestRemainder ties go to the earlier entry. If the shares sum to less
+/// than 100%, whatever is left after one sat per entry still goes to the first.
+/// Shares summing to more than 100% would pay out more than `total_sats`, so they
+/// get no amounts at all (an empty Vec); validate_inheritance rejects such contracts.
+pub fn allocate_amounts(total_sats: u64, shares_bps: &[u16], rounding: RoundingMode) -> Vec<u64> {
+    let shares_ppm: Vec<u32> = shares_bps.iter().map(|&share_bps| SharePpm::from_bps(share_bps).0).collect();
+    allocate_amounts_ppm(total_sats, &shares_ppm, rounding)
+}
+
+/// allocate_amounts for shares in parts per million
+pub fn allocate_amounts_ppm(total_sats: u64, shares_ppm: &[u32], rounding: RoundingMode) -> Vec<u64> {
+    let mut amounts: Vec<u64> = shares_ppm
+        .iter()
+        .map(|&share_ppm| (total_sats as u128 * share_ppm as u128 / FULL_SHARE_PPM as u128) as u64)
+        .collect();
+    let Some(mut leftover) = amounts
+        .iter()
+        .try_fold(0u64, |sum, &amount| sum.checked_add(amount))
+        .and_then(|paid| total_sats.checked_sub(paid))
+    else {
+        return Vec::new();
+    };
+
+    if rounding == RoundingMode::LargestRemainder {
+        let remainder = |i: usize| total_sats as u128 * shares_ppm[i] as u128 % FULL_SHARE_PPM as u128;
+        let mut order: Vec<usize> = (0..amounts.len()).collect();
+        order.sort_by_key(|&i| std::cmp::Reverse(remainder(i)));
+        for i in order {
+            if leftover == 0 {
+                break;
+            }
+            amounts[i] += 1;
+            leftover -= 1;
+        }
+    }
+
+    if let Some(first) = amounts.first_mut() {
+        *first += leftover;
+    }
+    amounts
+}
+
+/// Pays out `total_sats` over a list of allocations
+///
+/// The waterfall: fixed amounts are paid first, in list order, each taking as much
+/// of what's left as it's owed. Shares then split the remainder. If nothing takes
+/// the remainder (no shares), it goes to the first entry. The amounts always add up
+/// to `total_sats` for a non-empty list whose shares sum to at most 100%; over that,
+/// the shares are paid nothing (see allocate_amounts).
+pub fn waterfall(total_sats: u64, allocations: &[Allocation], rounding: RoundingMode) -> Vec<u64> {
+    let mut remaining = total_sats;
+    let mut amounts: Vec<u64> = allocations
+        .iter()
+        .map(|allocation| match *allocation {
+            Allocation::Fixed(sats) => {
+                let paid = sats.min(remaining);
+                remaining -= paid;
+                paid
+            }
+            Allocation::Share(_) | Allocation::PreciseShare(_) => 0,
+        })
+        .collect();
+
+    let shares: Vec<u32> = allocations.iter().filter_map(|a| a.share_ppm()).collect();
+    if shares.is_empty() {
+        if let Some(first) = amounts.first_mut() {
+            *first += remaining;
+        }
+        return amounts;
+    }
+
+    let mut share_amounts = allocate_amounts_ppm(remaining, &shares, rounding).into_iter();
+    for (amount, allocation) in amounts.iter_mut().zip(allocations) {
+        if allocation.share_ppm().is_some() {
+            *amount = share_amounts.next().unwrap_or(0);
+        }
+    }
+    amounts
+}
+
+/// Computes what each beneficiary is owed out of a vault holding `total_sats`
+///
+/// Follows the waterfall, so if the vault holds less than its fixed allocations add
+/// up to (its declared total_locked_sats was wrong), earlier fixed entries are paid
+/// first and the vault can still be distributed.
+pub fn compute_distribution_plan(inheritance: &InheritanceContent, total_sats: u64) -> Vec<(Payee, u64)> {
+    let allocations: Vec<Allocation> = inheritance.beneficiaries.iter().map(|b| b.allocation).collect();
+
+    inheritance
+        .beneficiaries
+        .iter()
+        .zip(waterfall(total_sats, &allocations, inheritance.rounding_mode))
+        .map(|(b, amount)| (b.payee.clone(), amount))
+        .collect()
+}
+
+/// Estimates what beneficiaries keep of a `total_sats` distribution after an
+/// inheritance tax of `rate_basis_points`
+///
+/// Gross amounts come from compute_distribution_plan; each beneficiary's tax is
+/// rounded down, so the net amounts always add back up to the gross ones.
+/// Nothing here is enforced by the contract.
+pub fn compute_inheritance_tax_estimate(
+    inheritance: &InheritanceContent,
+    total_sats: u64,
+    rate_basis_points: u16,
+) -> TaxEstimate {
+    let gross: Vec<(String, u64)> = compute_distribution_plan(inheritance, total_sats)
+        .into_iter()
+        .map(|(payee, amount)| (payee.label(), amount))
+        .collect();
+    let tax: Vec<(String, u64)> = gross
+        .iter()
+        .map(|(label, amount)| (label.clone(), share_amount(*amount, rate_basis_points)))
+        .collect();
+    let net = gross
+        .iter()
+        .zip(&tax)
+        .map(|((label, amount), (_, tax))| (label.clone(), amount - tax))
+        .collect();
+
+    TaxEstimate {
+        total_tax_sats: tax.iter().map(|(_, tax)| tax).sum(),
+        gross_per_beneficiary: gross,
+        tax_per_beneficiary: tax,
+        net_per_beneficiary: net,
+    }
+}
+
+/// Checks that a distribution transaction pays every beneficiary their share
+///
+/// The vault's value is the native amount of the input carrying this inheritance
+/// under `app`. Outputs are matched to beneficiaries by address, not position:
+/// everything the transaction pays to an address must add up to at least what that
+/// address is owed, so wallets can order outputs freely and add change. Amounts below
+/// DUST_THRESHOLD_SATS can't be paid as their own output and are not required.
+/// Xpub beneficiaries are paid at the script the witness derived for them.
+pub fn verify_distribution_completeness(
+    app: &App,
+    inheritance: &InheritanceContent,
+    tx: &Transaction,
+    witness: &DistributionWitness,
+) -> bool {
+    let Some(total_sats) = vault_input_sats(app, inheritance, tx) else {
+        return false;
+    };
+    let Some(coin_outs) = tx.coin_outs.as_ref() else {
+        return false;
+    };
+    let tombstone = usize::from(inheritance.leave_tombstone);
+    check!(coin_outs.len() <= max_distribution_outputs(inheritance, inheritance.beneficiaries.len(), tombstone));
+
+    let plan: Vec<(usize, Payee, u64)> = compute_distribution_plan(inheritance, total_sats)
+        .into_iter()
+        .enumerate()
+        .map(|(index, (payee, amount))| (index, payee, amount))
+        .collect();
+    let payouts: Vec<&NativeOutput> = coin_outs.iter().collect();
+
+    pays_all(&plan, &payouts, witness)
+}
+
+/// Checks a partial distribution, which pays some beneficiaries and leaves the NFT for the rest
+///
+/// Every beneficiary's amount comes from the plan for the whole input vault. The
+/// surviving NFT must be Triggered and list the unpaid beneficiaries, in order, each
+/// with a Fixed allocation of exactly their planned amount, so later distributions
+/// can't change what they're owed. The NFT's output must hold at least that much,
+/// and everyone left off the list must be paid by the other outputs.
+///
+/// Beneficiaries keep their vesting delay and can only be paid once it has elapsed.
+/// The partial distribution of an Active vault is what triggers it: it records the
+/// witness's current block as the trigger height, and may pay no one at all.
+pub fn verify_partial_distribution(
+    app: &App,
+    inheritance: &InheritanceContent,
+    remaining: &InheritanceContent,
+    tx: &Transaction,
+    witness: &DistributionWitness,
+) -> bool {
+    check!(remaining.status == InheritanceStatus::Triggered);
+
+    // The NFT becomes Triggered, and nothing but the beneficiaries, what's locked for
+    // them and the trigger height (checked below) may change, fields added later included
+    check!(
+        *remaining
+            == InheritanceContent {
+                beneficiaries: remaining.beneficiaries.clone(),
+                total_locked_sats: remaining.total_locked_sats,
+                status: InheritanceStatus::Triggered,
+                trigger_height: remaining.trigger_height,
+                ..inheritance.clone()
+            }
+    );
+
+    // The first partial distribution triggers the vault and records the height it
+    // happened at; after that the trigger height is fixed
+    let trigger_height = inheritance.trigger_height.or(witness.current_block);
+    check!(remaining.trigger_height == trigger_height);
+    check!(trigger_height.is_some() || remaining.beneficiaries.iter().all(|b| b.vesting_delay_blocks == 0));
+
+    // At least one beneficiary must be left for later, and at least one paid now,
+    // except when this transaction is what triggers the vault
+    check!(!remaining.beneficiaries.is_empty());
+    check!(
+        remaining.beneficiaries.len() < inheritance.beneficiaries.len() ||
+        inheritance.status == InheritanceStatus::Active
+    );
+
+    let Some(total_sats) = vault_input_sats(app, inheritance, tx) else {
+        return false;
+    };
+    let Some(coin_outs) = tx.coin_outs.as_ref() else {
+        return false;
+    };
+    let Some(vault_out) = tx.outs.iter().position(|charms| charms.contains_key(app)) else {
+        return false;
+    };
+
+    // Split the plan into the beneficiaries carried over and the ones paid now
+    let mut carried = remaining.beneficiaries.iter().peekable();
+    let mut paid = Vec::new();
+    let plan = compute_distribution_plan(inheritance, total_sats);
+    for (index, ((payee, amount), beneficiary)) in plan.into_iter().zip(&inheritance.beneficiaries).enumerate() {
+        match carried.peek() {
+            Some(b) if b.payee == payee &&
+                b.allocation == Allocation::Fixed(amount) &&
+                b.vesting_delay_blocks == beneficiary.vesting_delay_blocks => {
+                carried.next();
+            }
+            _ => {
+                // Only beneficiaries whose vesting delay has elapsed can be paid
+                check!(is_vested(beneficiary, trigger_height, witness.current_block));
+                paid.push((index, payee, amount));
+            }
+        }
+    }
+    check!(carried.next().is_none());
+    check!(coin_outs.len() <= max_distribution_outputs(inheritance, paid.len(), 1));
+
+    // The surviving NFT must hold what the carried beneficiaries are owed
+    check!(fixed_allocation_total(&remaining.beneficiaries) == Some(remaining.total_locked_sats));
+    check!(coin_outs.get(vault_out).is_some_and(|out| out.amount >= remaining.total_locked_sats));
+
+    let payouts: Vec<&NativeOutput> = coin_outs
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| *i != vault_out)
+        .map(|(_, out)| out)
+        .collect();
+    check!(pays_all(&paid, &payouts, witness));
+
+    true
+}
+
+/// Most outputs a distribution paying `paid` beneficiaries may have
+///
+/// One per beneficiary paid, one for the contract's fallback address if it has one,
+/// one for change, and `charm_outputs` carrying the surviving NFT or its tombstone.
+/// Anything more could only be padding to bury the real payments among.
+fn max_distribution_outputs(inheritance: &InheritanceContent, paid: usize, charm_outputs: usize) -> usize {
+    paid + usize::from(inheritance.fallback_address.is_some()) + 1 + charm_outputs
+}
+
+/// Checks that `payouts` cover every (beneficiary index, payee, amount) entry
+///
+/// A script owed by several entries is owed their sum, and totals below
+/// DUST_THRESHOLD_SATS are not required.
+fn pays_all(owed: &[(usize, Payee, u64)], payouts: &[&NativeOutput], witness: &DistributionWitness) -> bool {
+    let mut by_script: Vec<(Vec<u8>, u64)> = Vec::new();
+    for (index, payee, amount) in owed {
+        let Some(script) = payee_script(payee, *index, witness) else {
+            return false;
+        };
+        match by_script.iter_mut().find(|(s, _)| *s == script) {
+            Some((_, total)) => *total += amount,
+            None => by_script.push((script, *amount)),
+        }
+    }
+
+    by_script
+        .iter()
+        .filter(|(_, amount)| *amount >= DUST_THRESHOLD_SATS)
+        .all(|(script, amount)| paid_to_script(payouts, script) >= *amount)
+}
+
+/// The script a payee is paid at, for beneficiary `index`
+fn payee_script(payee: &Payee, index: usize, witness: &DistributionWitness) -> Option<Vec<u8>> {
+    match payee {
+        Payee::Xpub(xpub) => xpub_script(xpub, index, witness),
+        Payee::Committed(committed) => committed_script(committed, index, witness),
+        _ => payee.fixed_script(),
+    }
+}
+
+/// The script the witness chose for an xpub beneficiary, accepted only if it really
+/// is xpub/child_index for an index in the allowed range
+fn xpub_script(xpub: &XpubPayee, index: usize, witness: &DistributionWitness) -> Option<Vec<u8>> {
+    let derived = witness
+        .derived
+        .iter()
+        .find(|d| d.beneficiary_index as usize == index)?;
+    if derived.child_index < xpub.first_index || derived.child_index > xpub.last_index {
+        return None;
+    }
+
+    let child = ExtendedPubKey::from_base58(&xpub.xpub)?.derive_child(derived.child_index)?;
+    let script = p2wpkh_script(&child.public_key);
+    (script == derived.script_pubkey).then_some(script)
+}
+
+/// The script for a committed beneficiary: the revealed address if the witness
+/// opens the commitment, the fallback if it doesn't reveal one at all. A reveal
+/// that doesn't match the commitment invalidates the distribution.
+fn committed_script(committed: &CommittedPayee, index: usize, witness: &DistributionWitness) -> Option<Vec<u8>> {
+    let Some(revealed) = witness
+        .revealed
+        .iter()
+        .find(|r| r.beneficiary_index as usize == index)
+    else {
+        return address_to_script(&committed.fallback_address);
+    };
+
+    if commit_payee(&revealed.address, &revealed.salt) != committed.commitment {
+        return None;
+    }
+    address_to_script(&revealed.address)
+}
+
+/// Total paid to a scriptPubKey across all of a transaction's outputs
+fn paid_to_script(payouts: &[&NativeOutput], script: &[u8]) -> u64 {
+    payouts
+        .iter()
+        .filter(|out| out.dest == script)
+        .fold(0u64, |total, out| total.saturating_add(out.amount))
+}
+
+/// Native amount of the input that carries this inheritance's NFT
+///
+/// Only this app's charm counts: another app's charm holding the same content
+/// can't stand in for the vault.
+fn vault_input_sats(app: &App, inheritance: &InheritanceContent, tx: &Transaction) -> Option<u64> {
+    let coin_ins = tx.coin_ins.as_ref()?;
+
+    tx.ins
+        .iter()
+        .zip(coin_ins)
+        .find(|((_, charms), _)| {
+            charms
+                .get(app)
+                .is_some_and(|data| migrate_to_latest(data).ok().as_ref() == Some(inheritance))
+        })
+        .map(|(_, coin)| coin.amount)
+}
+
+/// Normalizes an address as users paste it
+///
+/// Surrounding whitespace is trimmed. The human-readable part of a segwit address
+/// is lowercased; an all-uppercase one (as QR codes carry them, see BIP-173) is
+/// lowercased whole, since bech32 rejects mixed case and the lowercase form is the
+/// same address. Base58 addresses are case-sensitive and kept exactly.
+pub fn sanitize_beneficiary_address(addr: &str) -> String {
+    let addr = addr.trim();
+    let Some(separator) = addr.rfind('1') else {
+        return addr.to_string();
+    };
+    let (hrp, data) = addr.split_at(separator);
+    if ![bech32::hrp::BC, bech32::hrp::TB, bech32::hrp::BCRT]
+        .iter()
+        .any(|known| hrp.eq_ignore_ascii_case(known.as_str()))
+    {
+        return addr.to_string();
+    }
+
+    if data.chars().any(|c| c.is_ascii_lowercase()) {
+        format!("{}{data}", hrp.to_ascii_lowercase())
+    } else {
+        addr.to_ascii_lowercase()
+    }
+}
+
+// The kinds of address a beneficiary can be paid at
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SupportedAddress {
+    P2pkh,    // Legacy pay-to-pubkey-hash (1... on mainnet, m... or n... elsewhere)
+    P2sh,     // Pay-to-script-hash (3... on mainnet, 2... elsewhere)
+    P2wpkh,   // Segwit v0 key hash (bc1q..., 20-byte program)
+    P2wsh,    // Segwit v0 script hash (bc1q..., 32-byte program)
+    P2tr,     // Taproot (bc1p..., 32-byte program)
+}
+
+impl SupportedAddress {
+    /// The kind of address `address` is on `network`, after sanitize_beneficiary_address
+    ///
+    /// Read off the script it pays to (see address_to_script). None for anything
+    /// else: an address for another network (see address_on_network), or a
+    /// witness version or program length nothing pays to yet.
+    pub fn classify(address: &str, network: Network) -> Option<Self> {
+        if !address_on_network(address, network) {
+            return None;
+        }
+        match address_to_script(address)?.as_slice() {
+            [0x76, 0xa9, 0x14, hash @ .., 0x88, 0xac] if hash.len() == 20 => Some(Self::P2pkh),
+            [0xa9, 0x14, hash @ .., 0x87] if hash.len() == 20 => Some(Self::P2sh),
+            [0x00, 0x14, program @ ..] if program.len() == 20 => Some(Self::P2wpkh),
+            [0x00, 0x20, program @ ..] if program.len() == 32 => Some(Self::P2wsh),
+            [0x51, 0x20, program @ ..] if program.len() == 32 => Some(Self::P2tr),
+            _ => None,
+        }
+    }
+}
+
+/// Converts a Bitcoin address into the scriptPubKey it pays to
+///
+/// Supports segwit (bech32/bech32m) and legacy base58 P2PKH/P2SH addresses on
+/// mainnet, testnet and regtest, after sanitize_beneficiary_address. Returns None
+/// for anything else.
+pub fn address_to_script(address: &str) -> Option<Vec<u8>> {
+    let address = sanitize_beneficiary_address(address);
+    let address = address.as_str();
+    if let Ok((_, version, program)) = bech32::segwit::decode(address) {
+        let version = version.to_u8();
+        let opcode = if version == 0 { 0x00 } else { 0x50 + version };
+
+        let mut script = vec![opcode, program.len() as u8];
+        script.extend(program);
+        return Some(script);
+    }
+
+    let payload = bs58::decode(address).with_check(None).into_vec().ok()?;
+    if payload.len() != 21 {
+        return None;
+    }
+    let (prefix, hash) = (payload[0], &payload[1..]);
+
+    match prefix {
+        // P2PKH: OP_DUP OP_HASH160 <hash> OP_EQUALVERIFY OP_CHECKSIG
+        0x00 | 0x6f => Some([&[0x76, 0xa9, 0x14][..], hash, &[0x88, 0xac]].concat()),
+        // P2SH: OP_HASH160 <hash> OP_EQUAL
+        0x05 | 0xc4 => Some([&[0xa9, 0x14][..], hash, &[0x87]].concat()),
+        _ => None,
+    }
+}
+
+/// Whether an address is encoded for `network`
+///
+/// Signet and regtest share testnet's base58 prefixes, so legacy addresses can
+/// only be told apart as mainnet or not.
+pub fn address_on_network(address: &str, network: Network) -> bool {
+    let address = sanitize_beneficiary_address(address);
+    let address = address.as_str();
+    if let Ok((hrp, _, _)) = bech32::segwit::decode(address) {
+        let expected = match network {
+            Network::Mainnet => bech32::hrp::BC,
+            Network::Testnet | Network::Signet => bech32::hrp::TB,
+            Network::Regtest => bech32::hrp::BCRT,
+        };
+        return hrp == expected;
+    }
+
+    match bs58::decode(address).with_check(None).into_vec().ok().and_then(|payload| payload.first().copied()) {
+        Some(0x00 | 0x05) => network == Network::Mainnet,
+        Some(0x6f | 0xc4) => network != Network::Mainnet,
+        _ => false,
+    }
+}
+
+//
+// ==================== CONTRACT SUMMARY ====================
+//
+
+/// Computes every derived fact a wallet needs about a contract in one call
+pub fn summarize(content: &InheritanceContent, current_block: u64) -> ContractSummary {
+    let deadline = deadline_block(content, current_block);
+
+    ContractSummary {
+        status: content.status.clone(),
+        effective_delay_blocks: effective_delay(content, current_block),
+        deadline_block: deadline,
+        blocks_until_deadline: deadline.saturating_sub(current_block),
+        is_triggerable: is_distributable(content, current_block),
+        total_share_bps: content
+            .beneficiaries
+            .iter()
+            .filter_map(|b| b.allocation.share_ppm())
+            .sum::<u32>()
+            / 100,
+        beneficiary_count: content.beneficiaries.len(),
+    }
+}
+
+//
+// ==================== CHECK-IN REMINDERS ====================
+//
+
+/// Measures the gaps between past check-ins against the contract's policy_max_gap
+///
+/// `history` holds the heights of past check-ins, in any order. The recommended
+/// delay gives an owner who keeps their habits half their longest gap again as
+/// margin; it's advice only, and never enforced.
+pub fn validate_checkin_frequency_policy(inheritance: &InheritanceContent, history: &[u64]) -> CheckinPolicyResult {
+    let mut heights = history.to_vec();
+    heights.sort();
+    let gaps: Vec<u64> = heights.windows(2).map(|pair| pair[1] - pair[0]).collect();
+
+    let longest_gap_blocks = gaps.iter().copied().max().unwrap_or(0);
+    let average_gap_blocks = if gaps.is_empty() {
+        0.0
+    } else {
+        gaps.iter().sum::<u64>() as f64 / gaps.len() as f64
+    };
+
+    let (min_delay, max_delay) = validation_rules::delay_bounds(inheritance.network);
+    let recommended_trigger_delay = longest_gap_blocks
+        .saturating_add(longest_gap_blocks / 2)
+        .clamp(min_delay, max_delay);
+
+    CheckinPolicyResult {
+        compliant: inheritance.policy_max_gap == 0 || longest_gap_blocks <= inheritance.policy_max_gap,
+        longest_gap_blocks,
+        average_gap_blocks,
+        recommended_trigger_delay,
+    }
+}
+
+/// Builds a reminder for the owner to check in, or None if the deadline is still far away
+///
+/// Urgency comes from the share of trigger_delay_blocks left before the deadline;
+/// nothing is sent while more than 75% remains. Only active contracts get reminders.
+pub fn generate_checkin_reminder(inheritance: &InheritanceContent, current_block: u64) -> Option<CheckinReminder> {
+    if inheritance.status != InheritanceStatus::Active {
+        return None;
+    }
+
+    let blocks_until_deadline = deadline_block(inheritance, current_block).saturating_sub(current_block);
+    let percent_left = (blocks_until_deadline as u128 * 100)
+        .checked_div(inheritance.trigger_delay_blocks as u128)
+        .unwrap_or(0);
+
+    let urgency = match percent_left {
+        76.. => return None,
+        50..=75 => ReminderUrgency::Routine,
+        25..=49 => ReminderUrgency::Soon,
+        10..=24 => ReminderUrgency::Urgent,
+        _ => ReminderUrgency::Critical,
+    };
+
+    let message = match urgency {
+        ReminderUrgency::Routine => format!("Routine check-in due: {blocks_until_deadline} blocks until your vault can be triggered"),
+        ReminderUrgency::Soon => format!("Check in soon: {blocks_until_deadline} blocks until your vault can be triggered"),
+        ReminderUrgency::Urgent => format!("Check in now: only {blocks_until_deadline} blocks until your vault can be triggered"),
+        ReminderUrgency::Critical => format!("Critical: {blocks_until_deadline} blocks left before your vault can be triggered"),
+    };
+
+    Some(CheckinReminder {
+        urgency,
+        blocks_until_deadline,
+        // Halfway to the deadline leaves room for a stuck transaction
+        suggested_checkin_block: current_block + blocks_until_deadline / 2,
+        message,
+    })
+}
+
+//
+// ==================== TIME ESTIMATES =============//
+
+/// Average time between blocks the chain targets
+pub const TARGET_BLOCK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(600);
+
+/// Roughly how long `blocks` blocks take to be mined, at TARGET_BLOCK_INTERVAL each
+pub fn blocks_to_duration_approx(blocks: u64) -> std::time::Duration {
+    TARGET_BLOCK_INTERVAL.saturating_mul(blocks.min(u32::MAX as u64) as u32)
+}
+
+/// Roughly how long the owner has left to check in, for UIs that show time
+/// rather than blocks, with blocks `avg_block_secs` apart
+///
+/// Counts down to deadline_block; None once the chain is past it. An estimate
+/// only: real block intervals vary widely.
+pub fn estimated_time_remaining(
+    content: &InheritanceContent,
+    current_block: u64,
+    avg_block_secs: u64,
+) -> Option<std::time::Duration> {
+    let blocks_left = deadline_block(content, current_block).checked_sub(current_block)?;
+    Some(std::time::Duration::from_secs(blocks_left.saturating_mul(avg_block_secs)))
+}
+
+//
+// ==================== CONTENT BUILDER ====================
+//
+
+impl InheritanceContentBuilder {
+    /// The owner's compressed public key, hex-encoded
+    pub fn owner_pubkey(mut self, owner_pubkey: &str) -> Self {
+        self.content.owner_pubkey = owner_pubkey.to_string();
+        self
+    }
+
+    /// Blocks without a check-in before the vault can be distributed
+    pub fn delay_blocks(mut self, trigger_delay_blocks: u64) -> Self {
+        self.content.trigger_delay_blocks = trigger_delay_blocks;
+        self
+    }
+
+    /// Adds a beneficiary receiving `share_bps` basis points of what's left after fixed amounts
+    pub fn beneficiary(mut self, address: &str, share_bps: u16) -> Self {
+        self.content.beneficiaries.push(Beneficiary {
+            payee: Payee::from(address),
+            allocation: Allocation::Share(share_bps),
+            vesting_delay_blocks: 0,
+        });
+        self
+    }
+
+    /// Adds a beneficiary receiving a fixed `sats`, paid before any share
+    pub fn fixed_beneficiary(mut self, address: &str, sats: u64) -> Self {
+        self.content.beneficiaries.push(Beneficiary {
+            payee: Payee::from(address),
+            allocation: Allocation::Fixed(sats),
+            vesting_delay_blocks: 0,
+        });
+        self
+    }
+
+    /// Network the contract's addresses and delay bounds are checked against (testnet by default)
+    pub fn network(mut self, network: Network) -> Self {
+        self.content.network = network;
+        self
+    }
+
+    /// Height the contract is created at, which also counts as its first check-in
+    pub fn created_at_block(mut self, block: u64) -> Self {
+        self.content.created_at_block = block;
+        self.content.last_checkin_block = block;
+        self
+    }
+
+    /// Sats the vault is funded with (needed once any beneficiary gets a fixed amount)
+    pub fn total_locked_sats(mut self, sats: u64) -> Self {
+        self.content.total_locked_sats = sats;
+        self
+    }
+
+    /// Limits the content is validated against (RuleSet::default() otherwise)
+    pub fn rule_set(mut self, rule_set: RuleSet) -> Self {
+        self.content.rule_set = rule_set;
+        self
+    }
+
+    /// Commitment to the off-chain will, changeable by the owner until it's locked
+    pub fn will_document_hash(mut self, hash: B32) -> Self {
+        self.content.will_document_hash = Some(hash);
+        self
+    }
+
+    /// Replaces the beneficiary list with entries built elsewhere
+    pub fn beneficiaries(mut self, beneficiaries: Vec<Beneficiary>) -> Self {
+        self.content.beneficiaries = beneficiaries;
+        self
+    }
+
+    /// Validates the assembled content, returning it only if a creation would accept it
+    pub fn build(self) -> Result<InheritanceContent, ValidationError> {
+        self.content.validate()?;
+        Ok(self.content)
+    }
+}
+
+/// Content for a new contract whose owner key is derived from a hardware wallet's xpub
+///
+/// A path starting with "m" is from the wallet's master key: its first `depth`
+/// steps are the xpub's own origin (the last of them must be the xpub's child
+/// number) and are skipped. Any other path is relative to the xpub. Either way,
+/// the steps left to derive can't be hardened, as a public key can't derive those.
+pub fn create_inheritance_from_xpub(
+    xpub: &str,
+    derivation_path: &str,
+    beneficiaries: Vec<Beneficiary>,
+    trigger_delay: u64,
+) -> Result<InheritanceContent, XpubError> {
+    let xpub = ExtendedPubKey::from_base58(xpub).ok_or(XpubError::InvalidXpub)?;
+    let steps = parse_derivation_path(derivation_path).ok_or(XpubError::DerivationFailed)?;
+
+    let steps = if derivation_path.starts_with('m') {
+        let depth = xpub.depth as usize;
+        let origin = steps.get(..depth).ok_or(XpubError::DerivationFailed)?;
+        ensure(origin.last().is_none_or(|&step| step == xpub.child_number), XpubError::DerivationFailed)?;
+        &steps[depth..]
+    } else {
+        &steps[..]
+    };
+    let child = steps
+        .iter()
+        .try_fold(xpub, |key, &index| key.derive_child(index))
+        .ok_or(XpubError::DerivationFailed)?;
+
+    let owner = OwnerPubkey(child.public_key);
+    InheritanceContent::builder()
+        .owner_pubkey(&pubkey_hex(&owner))
+        .delay_blocks(trigger_delay)
+        .beneficiaries(beneficiaries)
+        .build()
+        .map_err(XpubError::Content)
+}
+
+/// Child indexes of a BIP-32 path like "m/44'/0'/0'/0/0" (or "0/0"), with
+/// hardened steps marked by ', h or H
+fn parse_derivation_path(path: &str) -> Option<Vec<u32>> {
+    let path = path.strip_prefix('m').map_or(path, |rest| rest.strip_prefix('/').unwrap_or(rest));
+    if path.is_empty() {
+        return Some(Vec::new());
+    }
+
+    path.split('/')
+        .map(|step| {
+            let (index, hardened) = match step.strip_suffix(['\'', 'h', 'H']) {
+                Some(index) => (index, true),
+                None => (step, false),
+            };
+            let index: u32 = index.parse().ok()?;
+            if index >= HARDENED_INDEX {
+                return None;
+            }
+            Some(if hardened { index + HARDENED_INDEX } else { index })
+        })
+        .collect()
+}
+
+//
+// ==================== MULTI-SIG WITNESS ====================
+//
+
+/// Builds the witness for a multi-sig check-in
+///
+/// Signatures are usually collected asynchronously (e.g. a hardware signing ceremony),
+/// so this assembles the collected (pubkey, signature) pairs together with the
+/// block height into the witness Data passed to the check-in spell.
+pub fn create_multisig_checkin_witness(sigs: &[(OwnerPubkey, Vec<u8>)], current_block: u64) -> Data {
+    let witness = MultisigCheckinWitness {
+        signatures: sigs.to_vec(),
+        current_block,
+    };
+    Data::from(&witness)
+}
+
+/// (signer pubkey, signature bytes) pairs, as a multi-sig check-in witness carries them
+pub type SignerSignatures = Vec<(OwnerPubkey, Vec<u8>)>;
+
+/// Inverse of `create_multisig_checkin_witness`
+///
+/// Returns None if the witness is not a multi-sig check-in witness.
+pub fn parse_multisig_checkin_witness(w: &Data) -> Option<(SignerSignatures, u64)> {
+    let witness: MultisigCheckinWitness = w.value().ok()?;
+    Some((witness.signatures, witness.current_block))
+}
+
+impl WitnessData {
+    /// Encodes the witness in the format the matching operation reads
+    pub fn to_data(&self) -> Data {
+        match self {
+            WitnessData::Create(create) => Data::from(create),
+            WitnessData::CheckIn { signatures, current_block } => {
+                create_multisig_checkin_witness(signatures, *current_block)
+            }
+            WitnessData::Distribution(distribution) => Data::from(distribution),
+        }
+    }
+}
+
+/// Combines check-in witnesses collected separately from each co-signer
+///
+/// Every part must be a check-in made at the same block height. Signatures are
+/// kept in the order their signers first appear; if a signer shows up more than
+/// once, the later signature wins.
+pub fn merge_partial_witnesses(parts: &[WitnessData]) -> Result<WitnessData, WitnessError> {
+    let mut merged_block = None;
+    let mut merged: Vec<(OwnerPubkey, Vec<u8>)> = Vec::new();
+
+    for part in parts {
+        let WitnessData::CheckIn { signatures, current_block } = part else {
+            return Err(WitnessError::NotCheckIn);
+        };
+        if *merged_block.get_or_insert(*current_block) != *current_block {
+            return Err(WitnessError::ConflictingBlockHeight);
+        }
+
+        for (pubkey, sig) in signatures {
+            match merged.iter_mut().find(|(signer, _)| signer == pubkey) {
+                Some(entry) => entry.1 = sig.clone(),
+                None => merged.push((*pubkey, sig.clone())),
+            }
+        }
+    }
+
+    let current_block = merged_block.ok_or(WitnessError::NoParts)?;
+    Ok(WitnessData::CheckIn {
+        signatures: merged,
+        current_block,
+    })
+}
+
+/// Checks that at least `threshold` distinct keys out of `signers` signed `msg`
+///
+/// `sigs` pairs a signer's key with their BIP-340 signature; a key is matched to
+/// `signers` (hex, in any encoding verify_owner_signature takes) by its x-only
+/// form. Keys that aren't listed, bad signatures and repeat signers are ignored,
+/// as in verify_guardian_quorum, and a threshold of 0 is never met.
+pub fn multisig_threshold_met(signers: &[String], msg: &[u8; 32], sigs: &[(OwnerPubkey, Vec<u8>)], threshold: u8) -> bool {
+    if threshold == 0 {
+        return false;
+    }
+
+    let mut signed: Vec<usize> = sigs
+        .iter()
+        .filter_map(|(pubkey, sig)| {
+            signers.iter().position(|signer| {
+                decode_hex(signer).is_some_and(|key| xonly_bytes(&key) == xonly_bytes(&pubkey.0))
+                    && verify_owner_signature(signer, msg, sig)
+            })
+        })
+        .collect();
+    signed.sort();
+    signed.dedup();
+
+    signed.len() >= threshold as usize
+}
+
+//
+// ==================== TESTS ====================
+//
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use allocation::{allocate_across_contracts, validate_total_allocation, PercentageAllocation};
+    use chain::{
+        aggregate_batch_witness, chain_validate, validate_batch, validate_batch_checkin, validate_transaction,
+        BatchCheckinProof, InheritanceError,
+    };
+    use charms_sdk::data::{Charms, TxId};
+    use disclosure::{redact_owner, verify_owner_commitment};
+    use legal::format_contract_for_legal_document;
+    use liveness::{check_contract_liveness, simulate_inheritance_timeline, LivenessStatus};
+    use qr::{decode_inheritance_qr, encode_inheritance_qr, QrDecodeError};
+    use scan::find_inheritance_utxos;
+    use snapshot::{create_snapshot, restore_snapshot, ContractSnapshot};
+    use storage::{ContractStorage, InMemoryStorage};
+
+    fn validate_beneficiaries(beneficiaries: &[Beneficiary], total_locked_sats: u64, rules: &RuleSet) -> bool {
+        check_beneficiaries(beneficiaries, total_locked_sats, Network::Testnet, rules).is_ok()
+    }
+
+    // Each operation's rules as a yes or no, without going through evaluate
+
+    // Takes the public input to attest the height the new contract records
+    fn can_create_inheritance(app: &App, tx: &Transaction, w: &Data) -> bool {
+        let created_at = charm_values(app, tx.outs.iter())
+            .next()
+            .and_then(|data| InheritanceContent::try_from(data).ok())
+            .map(|content| content.created_at_block);
+        check_create_inheritance(&mut Rules::proving(), app, tx, created_at, w).is_ok()
+    }
+
+    // Takes the public input to attest the height the check-in witness gives
+    fn can_checkin(app: &App, tx: &Transaction, w: &Data) -> bool {
+        let stated = parse_multisig_checkin_witness(w).map(|(_, current_block)| current_block);
+        can_checkin_at(app, tx, stated, w)
+    }
+
+    fn can_checkin_at(app: &App, tx: &Transaction, block_height: Option<u64>, w: &Data) -> bool {
+        check_checkin_at(&mut Rules::proving(), app, tx, block_height, w).is_ok()
+    }
+
+    fn can_oracle_checkin(app: &App, tx: &Transaction, block_height: Option<u64>, w: &Data) -> bool {
+        check_oracle_checkin(&mut Rules::proving(), app, tx, block_height, w).is_ok()
+    }
+
+    // Takes the public input to attest CURRENT_BLOCK
+    fn can_update_beneficiaries(app: &App, tx: &Transaction, w: &Data) -> bool {
+        check_update_beneficiaries(&mut Rules::proving(), app, tx, Some(CURRENT_BLOCK), w).is_ok()
+    }
+
+    // Takes the public input to attest the height the witness gives, if it gives one
+    fn can_trigger_distribution(app: &App, tx: &Transaction, w: &Data) -> bool {
+        let stated = w.value::<DistributionWitness>().ok().and_then(|witness| witness.current_block);
+        can_trigger_distribution_at(app, tx, stated, w)
+    }
+
+    fn can_trigger_distribution_at(app: &App, tx: &Transaction, block_height: Option<u64>, w: &Data) -> bool {
+        check_trigger_distribution_at(&mut Rules::proving(), app, tx, block_height, w).is_ok()
+    }
+
+    fn can_guardian_emergency_trigger(app: &App, tx: &Transaction, block_height: Option<u64>, w: &Data) -> bool {
+        check_guardian_emergency_trigger(&mut Rules::proving(), app, tx, block_height, w).is_ok()
+    }
+
+    fn can_migrate(app: &App, tx: &Transaction, w: &Data) -> bool {
+        check_migrate(&mut Rules::proving(), app, tx, w).is_ok()
+    }
+
+    fn can_emergency_extend(app: &App, tx: &Transaction, block_height: Option<u64>, w: &Data) -> bool {
+        check_emergency_extend(&mut Rules::proving(), app, tx, block_height, w).is_ok()
+    }
+
+    fn can_record_miss(app: &App, tx: &Transaction, block_height: Option<u64>) -> bool {
+        check_record_miss(&mut Rules::proving(), app, tx, block_height).is_ok()
+    }
+
+    fn test_app() -> App {
+        App {
+            tag: NFT,
+            identity: hash("dc78b09d767c8565c4a58a95e7ad5ee22b28fc1685535056a395dc94929cdd5f:1"),
+            vk: B32([7; 32]),
+        }
+    }
+
+    fn test_inheritance() -> InheritanceContent {
+        InheritanceContent {
+            owner_pubkey: "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798".to_string(),
+            co_signers: vec![],
+            multisig_threshold: 0,
+            last_checkin_block: 100,
+            trigger_delay_blocks: 4320,
+            beneficiaries: vec![
+                Beneficiary {
+                    payee: Payee::from("tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx"),
+                    allocation: Allocation::Share(6000),
+                    vesting_delay_blocks: 0,
+                },
+                Beneficiary {
+                    payee: Payee::from("tb1pqqqqp399et2xygdj5xreqhjjvcmzhxw4aywxecjdzew6hylgvsesf3hn0c"),
+                    allocation: Allocation::Share(4000),
+                    vesting_delay_blocks: 0,
+                },
+            ],
+            status: InheritanceStatus::Active,
+            version: CONTENT_VERSION,
+            created_at_block: 100,
+            ..Default::default()
+        }
+    }
+
+    // The i-th of a run of distinct testnet taproot addresses, for tests that need many heirs
+    fn test_address(i: usize) -> String {
+        let mut program = [0u8; 32];
+        program[..8].copy_from_slice(&(i as u64).to_be_bytes());
+        bech32::segwit::encode(bech32::hrp::TB, bech32::segwit::VERSION_1, &program).unwrap()
+    }
+
+    fn owner_key() -> OwnerPubkey {
+        OwnerPubkey(decode_hex(&test_inheritance().owner_pubkey).unwrap().try_into().unwrap())
+    }
+
+    // Chain height check-ins in tests are made at, unless the height is the point
+    pub(super) const CURRENT_BLOCK: u64 = 850_000;
+
+    // Check-in witness at `current_block`, signed by the owner over each contract `tx` outputs
+    fn checkin_witness(tx: &Transaction, current_block: u64) -> Data {
+        let signatures: SignerSignatures = tx.outs.iter().flat_map(|charms| charms.values())
+            .filter_map(|data| InheritanceContent::try_from(data).ok())
+            .map(|output| (owner_key(), owner_sign(&signing_message(Operation::Checkin, tx, &output))))
+            .collect();
+        create_multisig_checkin_witness(&signatures, current_block)
+    }
+
+    // Update witness, signed by the owner over the first contract `tx` outputs
+    fn update_witness(tx: &Transaction) -> Data {
+        let output = tx.outs.iter().flat_map(|charms| charms.values()).find_map(|data| InheritanceContent::try_from(data).ok());
+        Data::from(&UpdateBeneficiariesWitness {
+            signature: output.map_or_else(Vec::new, |output| owner_sign(&signing_message(Operation::UpdateBeneficiaries, tx, &output))),
+        })
+    }
+
+    fn test_utxo_id(i: usize) -> UtxoId {
+        UtxoId(TxId([i as u8; 32]), i as u32)
+    }
+
+    fn nft_charms(app: &App, inheritance: &InheritanceContent) -> Charms {
+        Charms::from([(app.clone(), Data::from(inheritance))])
+    }
+
+    fn test_tx(ins: Vec<Charms>, outs: Vec<Charms>) -> Transaction {
+        Transaction {
+            ins: ins
+                .into_iter()
+                .enumerate()
+                .map(|(i, charms)| (test_utxo_id(i), charms))
+                .collect(),
+            refs: vec![],
+            outs,
+            coin_ins: None,
+            coin_outs: None,
+        }
+    }
+
+    // Distribution witness for vaults without xpub beneficiaries
+    fn no_derivations() -> Data {
+        Data::from(&DistributionWitness::default())
+    }
+
+    // Spends the vault (holding `total_sats`) into the given (address, amount) payouts
+    fn distribution_tx(
+        app: &App,
+        inheritance: &InheritanceContent,
+        total_sats: u64,
+        payouts: &[(Payee, u64)],
+    ) -> Transaction {
+        let mut tx = test_tx(vec![nft_charms(app, inheritance)], vec![Charms::new(); payouts.len()]);
+        tx.coin_ins = Some(vec![NativeOutput {
+            amount: total_sats,
+            dest: vec![0x51, 0x20, 0xaa],
+        }]);
+        tx.coin_outs = Some(
+            payouts
+                .iter()
+                .map(|(payee, amount)| NativeOutput {
+                    amount: *amount,
+                    dest: payee.fixed_script().unwrap(),
+                })
+                .collect(),
+        );
+        tx
+    }
+
+    #[test]
+    fn test_hash() {
+        let utxo_id =
+            UtxoId::from_str("dc78b09d767c8565c4a58a95e7ad5ee22b28fc1685535056a395dc94929cdd5f:1")
+                .unwrap();
+        let data = dbg!(utxo_id.to_string());
+        let expected = "f54f6d40bd4ba808b188963ae5d72769ad5212dd1d29517ecc4063dd9f033faa";
+        assert_eq!(&hash(&data).to_string(), expected);
+    }
+
+    #[test]
+    fn test_default_identity_hasher_matches_vector() {
+        let data = "dc78b09d767c8565c4a58a95e7ad5ee22b28fc1685535056a395dc94929cdd5f:1";
+        let expected = "f54f6d40bd4ba808b188963ae5d72769ad5212dd1d29517ecc4063dd9f033faa";
+        assert_eq!(Sha256Hasher::hash(data.as_bytes()).to_string(), expected);
+        assert_eq!(DefaultIdentityHasher::VERSION, 0);
+    }
+
+    #[test]
+    fn test_validate_beneficiaries_valid() {
+        let beneficiaries = vec![
+            Beneficiary {
+                payee: Payee::Address(test_address(0)),
+                allocation: Allocation::Share(6000),
+                vesting_delay_blocks: 0,
+            },
+            Beneficiary {
+                payee: Payee::Address(test_address(1)),
+                allocation: Allocation::Share(4000),
+                vesting_delay_blocks: 0,
+            },
+        ];
+        assert!(validate_beneficiaries(&beneficiaries, 0, &RuleSet::default()));
+    }
+
+    #[test]
+    fn test_validate_beneficiaries_invalid_sum() {
+        let beneficiaries = vec![
+            Beneficiary {
+                payee: Payee::Address(test_address(0)),
+                allocation: Allocation::Share(6000),
+                vesting_delay_blocks: 0,
+            },
+            Beneficiary {
+                payee: Payee::Address(test_address(1)),
+                allocation: Allocation::Share(5000),  // Total = 110%, should fail
+                vesting_delay_blocks: 0,
+            },
+        ];
+        assert!(!validate_beneficiaries(&beneficiaries, 0, &RuleSet::default()));
+    }
+
+    #[test]
+    fn test_validate_beneficiaries_duplicate_address() {
+        let heir = "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx";
+        let mut inheritance = test_inheritance();
+
+        // The same heir written three ways is still one heir
+        for duplicate in [heir.to_string(), heir.to_uppercase(), format!(" {heir}")] {
+            inheritance.beneficiaries[1].payee = Payee::Address(duplicate);
+            assert_eq!(inheritance.validate(), Err(ValidationError::DuplicateAddress { index: 1 }));
+        }
+
+        // ...and an update can't list them either
+        let app = test_app();
+        let input = test_inheritance();
+        let tx = test_tx(vec![nft_charms(&app, &input)], vec![nft_charms(&app, &inheritance)]);
+        assert!(!can_update_beneficiaries(&app, &tx, &update_witness(&tx)));
+    }
+
+    const MULTISIG_MESSAGE: [u8; 32] = [7; 32];
+
+    // Signers 1, 2 and 3 (secret keys 1, 2 and 3), listed by their compressed keys
+    fn test_signer_key(i: u8) -> OwnerPubkey {
+        let mut secret = [0u8; 32];
+        secret[31] = i;
+        let signing_key = k256::schnorr::SigningKey::from_bytes(&secret).unwrap();
+        let mut pubkey = [0x02; 33];
+        pubkey[1..].copy_from_slice(&signing_key.verifying_key().to_bytes());
+        OwnerPubkey(pubkey)
+    }
+
+    fn test_signer_keys() -> Vec<String> {
+        (1..=3).map(|i| test_signer_key(i).0.iter().map(|byte| format!("{byte:02x}")).collect()).collect()
+    }
+
+    // Each signer's signature over MULTISIG_MESSAGE
+    fn test_signers() -> SignerSignatures {
+        (1..=3u8)
+            .map(|i| {
+                let mut secret = [0u8; 32];
+                secret[31] = i;
+                let signing_key = k256::schnorr::SigningKey::from_bytes(&secret).unwrap();
+                let sig = signing_key.sign_raw(&MULTISIG_MESSAGE, &[0u8; 32]).unwrap().to_bytes().to_vec();
+                (test_signer_key(i), sig)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_multisig_checkin_witness_round_trip() {
+        let sigs = test_signers();
+        let w = create_multisig_checkin_witness(&sigs, 850_000);
+
+        let (parsed_sigs, current_block) = parse_multisig_checkin_witness(&w).unwrap();
+        assert_eq!(parsed_sigs, sigs);
+        assert_eq!(current_block, 850_000);
+        assert!(multisig_threshold_met(&test_signer_keys(), &MULTISIG_MESSAGE, &parsed_sigs, 3));
+    }
+
+    #[test]
+    fn test_multisig_threshold_counts_valid_distinct_signers() {
+        let signers = test_signer_keys();
+        let sigs = test_signers();
+        assert!(multisig_threshold_met(&signers, &MULTISIG_MESSAGE, &sigs, 3));
+        assert!(!multisig_threshold_met(&signers, &MULTISIG_MESSAGE, &sigs, 4));
+        assert!(!multisig_threshold_met(&signers, &MULTISIG_MESSAGE, &sigs, 0));
+
+        // Signatures over another message don't count
+        assert!(!multisig_threshold_met(&signers, &[8; 32], &sigs, 1));
+
+        // Neither do forged ones
+        let mut forged = sigs.clone();
+        forged[0].1[0] ^= 1;
+        forged[1].1 = vec![0x11; 64];
+        forged[2].1[63] ^= 1;
+        assert!(!multisig_threshold_met(&signers, &MULTISIG_MESSAGE, &forged, 1));
+
+        // One signer repeating their signature still counts once
+        let repeated = vec![sigs[0].clone(); 3];
+        assert!(multisig_threshold_met(&signers, &MULTISIG_MESSAGE, &repeated, 1));
+        assert!(!multisig_threshold_met(&signers, &MULTISIG_MESSAGE, &repeated, 2));
+
+        // A valid signature by a key that isn't listed counts for nobody
+        assert!(!multisig_threshold_met(&signers[1..], &MULTISIG_MESSAGE, &sigs[..1], 1));
+
+        // Nor does a listed key paired with another signer's signature
+        let swapped = vec![(sigs[0].0, sigs[1].1.clone())];
+        assert!(!multisig_threshold_met(&signers, &MULTISIG_MESSAGE, &swapped, 1));
+    }
+
+    #[test]
+    fn test_merge_partial_witnesses() {
+        let sigs = test_signers();
+        let part = |sigs: &[(OwnerPubkey, Vec<u8>)], current_block| WitnessData::CheckIn {
+            signatures: sigs.to_vec(),
+            current_block,
+        };
+
+        let merged = merge_partial_witnesses(&[part(&sigs[..1], 850_000), part(&sigs[1..2], 850_000)]).unwrap();
+        assert_eq!(merged, part(&sigs[..2], 850_000));
+
+        let (parsed_sigs, current_block) = parse_multisig_checkin_witness(&merged.to_data()).unwrap();
+        assert_eq!(parsed_sigs, sigs[..2]);
+        assert_eq!(current_block, 850_000);
+        assert!(multisig_threshold_met(&test_signer_keys(), &MULTISIG_MESSAGE, &parsed_sigs, 2));
+    }
+
+    #[test]
+    fn test_merge_partial_witnesses_later_signature_wins() {
+        let sigs = test_signers();
+        let mut resigned = sigs[0].clone();
+        resigned.1 = vec![0xee; 64];
+        let parts = [
+            WitnessData::CheckIn { signatures: sigs[..2].to_vec(), current_block: 850_000 },
+            WitnessData::CheckIn { signatures: vec![resigned.clone()], current_block: 850_000 },
+        ];
+
+        let WitnessData::CheckIn { signatures, .. } = merge_partial_witnesses(&parts).unwrap() else {
+            panic!("expected a check-in witness");
+        };
+        assert_eq!(signatures, vec![resigned, sigs[1].clone()]);
+    }
+
+    #[test]
+    fn test_merge_partial_witnesses_rejects_mismatch() {
+        let sigs = test_signers();
+        let parts = [
+            WitnessData::CheckIn { signatures: sigs[..1].to_vec(), current_block: 850_000 },
+            WitnessData::CheckIn { signatures: sigs[1..].to_vec(), current_block: 850_001 },
+        ];
+        assert_eq!(merge_partial_witnesses(&parts), Err(WitnessError::ConflictingBlockHeight));
+
+        let parts = [WitnessData::Distribution(DistributionWitness::default())];
+        assert_eq!(merge_partial_witnesses(&parts), Err(WitnessError::NotCheckIn));
+        assert_eq!(merge_partial_witnesses(&[]), Err(WitnessError::NoParts));
+    }
+
+    #[test]
+    fn test_multisig_checkin_witness_missing_signer() {
+        let mut sigs = test_signers();
+        sigs.pop();
+        let w = create_multisig_checkin_witness(&sigs, 850_000);
+
+        // Parsing still succeeds, but 2 signatures don't meet a 3-of-3 threshold
+        let (parsed_sigs, _) = parse_multisig_checkin_witness(&w).unwrap();
+        assert_eq!(parsed_sigs.len(), 2);
+        assert!(!multisig_threshold_met(&test_signer_keys(), &MULTISIG_MESSAGE, &parsed_sigs, 3));
+    }
+
+    #[test]
+    fn test_checkin_requires_owner_signature() {
+        let app = test_app();
+        let input = test_inheritance();
+        let mut output = input.clone();
+        output.last_checkin_block += 10;
+        let tx = test_tx(vec![nft_charms(&app, &input)], vec![nft_charms(&app, &output)]);
+        let message = signing_message(Operation::Checkin, &tx, &output);
+
+        // Unsigned
+        assert!(!can_checkin(&app, &tx, &create_multisig_checkin_witness(&[], CURRENT_BLOCK)));
+
+        // A corrupted owner signature
+        let mut sig = owner_sign(&message);
+        sig[5] ^= 1;
+        assert!(!can_checkin(&app, &tx, &create_multisig_checkin_witness(&[(owner_key(), sig)], CURRENT_BLOCK)));
+
+        // The owner's signature over another check-in
+        let mut other = output.clone();
+        other.last_checkin_block += 1;
+        let sig = owner_sign(&signing_message(Operation::Checkin, &tx, &other));
+        assert!(!can_checkin(&app, &tx, &create_multisig_checkin_witness(&[(owner_key(), sig)], CURRENT_BLOCK)));
+
+        // A valid signature by someone other than the owner
+        let mut secret = [0u8; 32];
+        secret[31] = 2;
+        let signing_key = k256::schnorr::SigningKey::from_bytes(&secret).unwrap();
+        let sig = signing_key.sign_raw(&message, &[0u8; 32]).unwrap().to_bytes().to_vec();
+        assert!(!can_checkin(&app, &tx, &create_multisig_checkin_witness(&[(test_signer_key(2), sig)], CURRENT_BLOCK)));
+
+        // The owner's own signature passes
+        assert!(can_checkin(&app, &tx, &checkin_witness(&tx, CURRENT_BLOCK)));
+    }
+
+    // The owner (signer 1) and co-signers 2 and 3, any two of whom must sign a check-in
+    fn cosigned_inheritance() -> InheritanceContent {
+        InheritanceContent {
+            co_signers: test_signer_keys()[1..].to_vec(),
+            multisig_threshold: 2,
+            ..test_inheritance()
+        }
+    }
+
+    fn cosigned_witness(tx: &Transaction, output: &InheritanceContent, signers: &[u8]) -> Data {
+        let message = signing_message(Operation::Checkin, tx, output);
+        let sigs: SignerSignatures = signers
+            .iter()
+            .map(|&i| {
+                let mut secret = [0u8; 32];
+                secret[31] = i;
+                let signing_key = k256::schnorr::SigningKey::from_bytes(&secret).unwrap();
+                (test_signer_key(i), signing_key.sign_raw(&message, &[0u8; 32]).unwrap().to_bytes().to_vec())
+            })
+            .collect();
+        create_multisig_checkin_witness(&sigs, CURRENT_BLOCK)
+    }
+
+    #[test]
+    fn test_checkin_meets_multisig_threshold() {
+        let app = test_app();
+        let input = cosigned_inheritance();
+        let mut output = input.clone();
+        output.last_checkin_block += 10;
+        let tx = test_tx(vec![nft_charms(&app, &input)], vec![nft_charms(&app, &output)]);
+
+        // The owner alone no longer meets the threshold
+        assert!(!can_checkin(&app, &tx, &cosigned_witness(&tx, &output, &[1])));
+        assert!(!can_checkin(&app, &tx, &cosigned_witness(&tx, &output, &[3])));
+
+        // Any two of the owner and co-signers do
+        assert!(can_checkin(&app, &tx, &cosigned_witness(&tx, &output, &[1, 2])));
+        assert!(can_checkin(&app, &tx, &cosigned_witness(&tx, &output, &[2, 3])));
+    }
+
+    #[test]
+    fn test_checkin_keeps_multisig_signers() {
+        let app = test_app();
+        let input = cosigned_inheritance();
+
+        // Dropping the threshold on a check-in would let the owner check in alone afterwards
+        let mut output = input.clone();
+        output.last_checkin_block += 10;
+        output.multisig_threshold = 1;
+        let tx = test_tx(vec![nft_charms(&app, &input)], vec![nft_charms(&app, &output)]);
+        assert!(!can_checkin(&app, &tx, &cosigned_witness(&tx, &output, &[1, 2])));
+
+        let mut output = input.clone();
+        output.last_checkin_block += 10;
+        output.co_signers.pop();
+        let tx = test_tx(vec![nft_charms(&app, &input)], vec![nft_charms(&app, &output)]);
+        assert!(!can_checkin(&app, &tx, &cosigned_witness(&tx, &output, &[1, 2])));
+    }
+
+    #[test]
+    fn test_validate_multisig_threshold_reachable() {
+        let mut inheritance = cosigned_inheritance();
+        assert!(validate_inheritance(&inheritance));
+
+        inheritance.multisig_threshold = 3;
+        assert!(validate_inheritance(&inheritance));
+
+        inheritance.multisig_threshold = 4;
+        assert!(!validate_inheritance(&inheritance));
+    }
+
+    #[test]
+    fn test_checkin_single_input() {
+        let app = test_app();
+        let input = test_inheritance();
+        let mut output = input.clone();
+        output.last_checkin_block += 10;
+
+        let tx = test_tx(vec![nft_charms(&app, &input)], vec![nft_charms(&app, &output)]);
+        assert!(can_checkin(&app, &tx, &checkin_witness(&tx, CURRENT_BLOCK)));
+    }
+
+    #[test]
+    fn test_content_commitment_covers_every_field() {
+        let content = test_inheritance();
+        let commitment = content_commitment(&content);
+
+        let mut checked_in = content.clone();
+        checked_in.last_checkin_block += 1;
+        checked_in.missed_count = 1;
+        assert_eq!(content_commitment(&checked_in), commitment);
+
+        let changes: Vec<fn(&mut InheritanceContent)> = vec![
+            |c| c.owner_pubkey = "02c6047f9441ed7d6d3045406e95c07cd85c778e4b8cef3ca7abac09b95c709ee5".to_string(),
+            |c| c.trigger_delay_blocks += 1,
+            |c| c.beneficiaries[0].allocation = Allocation::Share(5000),
+            |c| c.beneficiaries[1].vesting_delay_blocks = 1,
+            |c| c.status = InheritanceStatus::Triggered,
+            |c| c.delay_decay = Some(DelayDecay { interval_blocks: 1, step_blocks: 1, floor_blocks: 1 }),
+            |c| c.version += 1,
+            |c| c.rule_set.max_beneficiaries -= 1,
+            |c| c.total_locked_sats += 1,
+            |c| c.network = Network::Mainnet,
+            |c| c.trigger_height = Some(1),
+            |c| c.recovery_mode = Some(ContractRecoveryMode::CoSignerOverride),
+            |c| c.min_expected_funding = 1,
+            |c| c.oracle_pubkey = Some(GUARDIAN_KEYS[0].to_string()),
+            |c| c.will_document_hash = Some(B32([1; 32])),
+            |c| c.will_locked = true,
+            |c| c.emergency_contact_pubkey = Some(GUARDIAN_KEYS[1].to_string()),
+            |c| c.emergency_extension_used = true,
+            |c| c.grace_period = Some(GracePeriod { extra_blocks: 1, requires_medical_attestation: false }),
+            |c| c.allowed_missed_checkins = 1,
+            |c| c.court_pubkey = Some(OwnerPubkey([2; 33])),
+            |c| c.guardian_emergency_delay = 1,
+            |c| c.checkin_window_blocks = 1,
+        ];
+        for change in changes {
+            let mut changed = content.clone();
+            change(&mut changed);
+            assert_ne!(content_commitment(&changed), commitment, "{changed:?}");
+        }
+    }
+
+    #[test]
+    fn test_checkin_rejects_reordered_beneficiaries() {
+        let app = test_app();
+        let input = test_inheritance();
+        let mut output = input.clone();
+        output.last_checkin_block += 10;
+        output.beneficiaries.reverse();
+
+        // Same beneficiaries, different order: a different contract
+        assert_ne!(content_commitment(&output), content_commitment(&input));
+        let tx = test_tx(vec![nft_charms(&app, &input)], vec![nft_charms(&app, &output)]);
+        assert!(!can_checkin(&app, &tx, &checkin_witness(&tx, CURRENT_BLOCK)));
+    }
+
+    #[test]
+    fn test_beneficiary_set_hash_is_order_sensitive() {
+        let beneficiaries = test_inheritance().beneficiaries;
+        let mut reversed = beneficiaries.clone();
+        reversed.reverse();
+
+        // The same heirs in another order are a different list
+        assert_eq!(BeneficiarySetHash::of(&beneficiaries), BeneficiarySetHash::of(&beneficiaries.clone()));
+        assert_ne!(BeneficiarySetHash::of(&reversed), BeneficiarySetHash::of(&beneficiaries));
+
+        // Kept for wallets still calling it, and agrees with the hash
+        #[allow(deprecated)]
+        {
+            assert!(beneficiaries_equal(&beneficiaries, &beneficiaries.clone()));
+            assert!(!beneficiaries_equal(&reversed, &beneficiaries));
+            assert!(!beneficiaries_equal(&beneficiaries[..1], &beneficiaries));
+        }
+    }
+
+    #[test]
+    fn test_checkin_rejects_two_same_identity_inputs() {
+        let app = test_app();
+        let input = test_inheritance();
+        let mut output = input.clone();
+        output.last_checkin_block += 10;
+
+        // The second input carries this identity under another verifying key, so it isn't
+        // one of this app's charms and only the identity rule catches it
+        let other = App {
+            vk: B32([8; 32]),
+            ..app.clone()
+        };
+        let tx = test_tx(
+            vec![nft_charms(&app, &input), nft_charms(&other, &input)],
+            vec![nft_charms(&app, &output)],
+        );
+        assert!(!can_checkin(&app, &tx, &checkin_witness(&tx, CURRENT_BLOCK)));
+        assert_eq!(
+            evaluate(
+                &app,
+                &tx,
+                &block_height_input(CURRENT_BLOCK),
+                &operation_witness(Operation::Checkin, &checkin_witness(&tx, CURRENT_BLOCK)),
+            ),
+            Err(ContractError::Checkin(RuleFailed::AppIdentityInputs))
+        );
+    }
+
+    #[test]
+    fn test_checkin_bumps_last_checkin_block() {
+        let app = test_app();
+        let input = test_inheritance();
+        let checkin = |last_checkin_block| {
+            let mut output = input.clone();
+            output.last_checkin_block = last_checkin_block;
+            test_tx(vec![nft_charms(&app, &input)], vec![nft_charms(&app, &output)])
+        };
+
+        assert!(can_checkin(&app, &checkin(input.last_checkin_block + 1), &checkin_witness(&checkin(input.last_checkin_block + 1), CURRENT_BLOCK)));
+        assert!(!can_checkin(&app, &checkin(input.last_checkin_block), &checkin_witness(&checkin(input.last_checkin_block), CURRENT_BLOCK)));
+        assert!(!can_checkin(&app, &checkin(input.last_checkin_block - 1), &checkin_witness(&checkin(input.last_checkin_block - 1), CURRENT_BLOCK)));
+    }
+
+    #[test]
+    fn test_checkin_rejects_changed_beneficiary() {
+        let app = test_app();
+        let input = test_inheritance();
+        let mut output = input.clone();
+        output.last_checkin_block += 10;
+        let tx = test_tx(vec![nft_charms(&app, &input)], vec![nft_charms(&app, &output)]);
+        assert!(can_checkin(&app, &tx, &checkin_witness(&tx, CURRENT_BLOCK)));
+
+        output.beneficiaries[0].allocation = Allocation::Share(5000);
+        output.beneficiaries[1].allocation = Allocation::Share(5000);
+        let tx = test_tx(vec![nft_charms(&app, &input)], vec![nft_charms(&app, &output)]);
+        assert!(!can_checkin(&app, &tx, &checkin_witness(&tx, CURRENT_BLOCK)));
+
+        // The same change is fine as an update
+        assert!(can_update_beneficiaries(&app, &tx, &update_witness(&tx)));
+    }
+
+    #[test]
+    fn test_update_rejects_owner_change() {
+        let app = test_app();
+        let input = test_inheritance();
+        let mut output = input.clone();
+        output.beneficiaries.pop();
+        output.beneficiaries[0].allocation = Allocation::Share(10_000);
+        let tx = test_tx(vec![nft_charms(&app, &input)], vec![nft_charms(&app, &output)]);
+        assert!(can_update_beneficiaries(&app, &tx, &update_witness(&tx)));
+
+        // A valid key, but not the owner's
+        output.owner_pubkey = "02c6047f9441ed7d6d3045406e95c07cd85c778e4b8cef3ca7abac09b95c709ee5".to_string();
+        let tx = test_tx(vec![nft_charms(&app, &input)], vec![nft_charms(&app, &output)]);
+        assert!(!can_update_beneficiaries(&app, &tx, &update_witness(&tx)));
+        assert!(!can_checkin(&app, &tx, &checkin_witness(&tx, CURRENT_BLOCK)));
+    }
+
+    #[test]
+    fn test_update_rejects_rounding_mode_change() {
+        let app = test_app();
+        let input = test_inheritance();
+        let mut output = input.clone();
+        output.beneficiaries.pop();
+        output.beneficiaries[0].allocation = Allocation::Share(10_000);
+        output.rounding_mode = RoundingMode::LargestRemainder;
+        let tx = test_tx(vec![nft_charms(&app, &input)], vec![nft_charms(&app, &output)]);
+        assert!(!can_update_beneficiaries(&app, &tx, &update_witness(&tx)));
+
+        // Nor can a partial distribution change it for the beneficiaries still waiting
+        let (_, mut remaining, mut tx) = partial_distribution(&app);
+        remaining.rounding_mode = RoundingMode::LargestRemainder;
+        tx.outs[1] = nft_charms(&app, &remaining);
+        assert!(!can_trigger_distribution(&app, &tx, &no_derivations()));
+    }
+
+    #[test]
+    fn test_update_checks_content_invariants() {
+        let app = test_app();
+        let input = test_inheritance();
+        let changes: Vec<fn(&mut InheritanceContent)> = vec![
+            |_| {},
+            |c| c.beneficiaries.clear(),
+            |c| c.beneficiaries[1].allocation = Allocation::Share(3000),
+            |c| c.beneficiaries[1].payee = c.beneficiaries[0].payee.clone(),
+            |c| c.fallback_address = Some("not an address".to_string()),
+            |c| c.guardian_threshold = 1,
+            |c| {
+                c.metadata.insert(String::new(), "note".to_string());
+            },
+        ];
+
+        let mut rejected = 0;
+        for change in changes {
+            // Checked in since creation, so no longer valid as new content
+            let mut output = input.clone();
+            output.last_checkin_block += 10;
+            change(&mut output);
+            assert!(output.validate().is_err());
+
+            let tx = test_tx(vec![nft_charms(&app, &input)], vec![nft_charms(&app, &output)]);
+            let valid = output.validate_invariants().is_ok();
+            assert_eq!(valid, can_update_beneficiaries(&app, &tx, &update_witness(&tx)), "{:?}", output);
+            if !valid {
+                rejected += 1;
+            }
+        }
+        assert_eq!(rejected, 6);
+    }
+
+    fn create_tx(funding: &[UtxoId], n_inputs: usize) -> (App, Transaction) {
+        let app = App {
+            tag: NFT,
+            identity: composite_identity(funding),
+            vk: B32([7; 32]),
+        };
+        let tx = test_tx(
+            vec![Charms::new(); n_inputs],
+            vec![nft_charms(&app, &test_inheritance())],
+        );
+        (app, tx)
+    }
+
+    #[test]
+    fn test_decode_app_identity() {
+        let candidates: Vec<UtxoId> = (0..100)
+            .map(|i| {
+                let txid: [u8; 32] = Sha256::digest([i as u8]).into();
+                UtxoId(TxId(txid), i * 7 % 5)
+            })
+            .collect();
+        let (app, _) = create_tx(&candidates[42..43], 1);
+
+        assert_eq!(decode_app_identity(&app.identity, &candidates), candidates.get(42).cloned());
+        assert_eq!(decode_app_identity(&app.identity, &candidates[..42]), None);
+
+        // A multi-UTXO identity isn't any one of its inputs
+        let (app, _) = create_tx(&candidates[..2], 1);
+        assert_eq!(decode_app_identity(&app.identity, &candidates), None);
+    }
+
+    #[test]
+    fn test_create_single_funding_input() {
+        let funding = vec![test_utxo_id(0)];
+        let (app, tx) = create_tx(&funding, 1);
+
+        // The original single-string witness and a one-element list are equivalent
+        assert_eq!(composite_identity(&funding), hash(&test_utxo_id(0).to_string()));
+        let w = Data::from(&CreateWitness::Single(test_utxo_id(0).to_string()));
+        assert!(can_create_inheritance(&app, &tx, &w));
+        let w = Data::from(&CreateWitness::Multi(vec![test_utxo_id(0).to_string()]));
+        assert!(can_create_inheritance(&app, &tx, &w));
+    }
+
+    #[test]
+    fn test_create_records_attested_height() {
+        let (app, tx) = create_tx(&[test_utxo_id(0)], 1);
+        let w = Data::from(&CreateWitness::Single(test_utxo_id(0).to_string()));
+        let create = operation_witness(Operation::Create, &w);
+
+        // test_inheritance records creation (and its first check-in) at block 100
+        assert_eq!(check_create_inheritance(&mut Rules::proving(), &app, &tx, Some(100), &w), Ok(()));
+        assert!(app_contract(&app, &tx, &block_height_input(100), &create));
+
+        // A contract dated after the height it's created at would have its deadline pushed out
+        assert_eq!(check_create_inheritance(&mut Rules::proving(), &app, &tx, Some(99), &w), Err(RuleFailed::CreatedAtMismatch));
+        // ...and one backdated would start out overdue
+        assert_eq!(check_create_inheritance(&mut Rules::proving(), &app, &tx, Some(101), &w), Err(RuleFailed::CreatedAtMismatch));
+        assert_eq!(
+            evaluate(&app, &tx, &Data::empty(), &create),
+            Err(ContractError::Create(RuleFailed::HeightNotAttested))
+        );
+    }
+
+    #[test]
+    fn test_create_multiple_funding_inputs() {
+        for n in 2..=3 {
+            let funding: Vec<UtxoId> = (0..n).map(test_utxo_id).collect();
+            let (app, tx) = create_tx(&funding, n);
+
+            // Order of the witness list doesn't matter
+            let w = Data::from(&CreateWitness::Multi(
+                funding.iter().rev().map(|u| u.to_string()).collect(),
+            ));
+            assert!(can_create_inheritance(&app, &tx, &w));
+
+            // A strict subset can't reproduce the identity
+            assert_ne!(composite_identity(&funding[1..]), app.identity);
+        }
+    }
+
+    #[test]
+    fn test_create_rejects_input_with_existing_charm() {
+        let funding = vec![test_utxo_id(0)];
+        let (app, mut tx) = create_tx(&funding, 1);
+        let w = Data::from(&CreateWitness::Single(test_utxo_id(0).to_string()));
+        assert!(can_create_inheritance(&app, &tx, &w));
+
+        // The funding UTXO already holds an inheritance NFT of this app
+        tx.ins[0].1 = nft_charms(&app, &test_inheritance());
+        assert!(!can_create_inheritance(&app, &tx, &w));
+    }
+
+    #[test]
+    fn test_create_missing_funding_input() {
+        let funding: Vec<UtxoId> = (0..3).map(test_utxo_id).collect();
+        let (app, tx) = create_tx(&funding, 2);
+
+        let w = Data::from(&CreateWitness::Multi(funding.iter().map(|u| u.to_string()).collect()));
+        assert!(!can_create_inheritance(&app, &tx, &w));
+    }
+
+    fn test_history() -> (App, Vec<(Transaction, Data, Data)>) {
+        let app = test_app();
+        let created = test_inheritance();
+        let mut checked_in = created.clone();
+        checked_in.last_checkin_block += 10;
+
+        let create_w = operation_witness(Operation::Create, &Data::from(&test_utxo_id(0).to_string()));
+        let app = App {
+            identity: hash(&test_utxo_id(0).to_string()),
+            ..app
+        };
+
+        let history = vec![
+            (
+                test_tx(vec![Charms::new()], vec![nft_charms(&app, &created)]),
+                block_height_input(created.created_at_block),
+                create_w,
+            ),
+            {
+                let tx = test_tx(vec![nft_charms(&app, &created)], vec![nft_charms(&app, &checked_in)]);
+                let w = operation_witness(Operation::Checkin, &checkin_witness(&tx, CURRENT_BLOCK));
+                (tx, block_height_input(CURRENT_BLOCK), w)
+            },
+            (
+                distribution_tx(
+                    &app,
+                    &checked_in,
+                    1_000_000,
+                    &compute_distribution_plan(&checked_in, 1_000_000),
+                ),
+                block_height_input(CURRENT_BLOCK),
+                operation_witness(Operation::TriggerDistribution, &no_derivations()),
+            ),
+        ];
+        (app, history)
+    }
+
+    #[test]
+    fn test_chain_validate_full_history() {
+        let (app, history) = test_history();
+        let results = chain_validate(&history, &app);
+        assert_eq!(results, vec![Ok(()), Ok(()), Ok(())]);
+    }
+
+    #[test]
+    fn test_chain_validate_broken_chain() {
+        let (app, mut history) = test_history();
+
+        // Distribute spends the created state instead of the checked-in one
+        let created = test_inheritance();
+        history[2].0 = test_tx(vec![nft_charms(&app, &created)], vec![]);
+
+        let results = chain_validate(&history, &app);
+        assert_eq!(results[2], Err(InheritanceError::ChainBroken { tx_index: 2 }));
+    }
+
+    #[test]
+    fn test_validate_size_over_cap() {
+        // 100 beneficiaries at 1% each, all with taproot addresses
+        let mut inheritance = test_inheritance();
+        inheritance.beneficiaries = (0..100)
+            .map(|i| Beneficiary {
+                payee: Payee::Address(test_address(i)),
+                allocation: Allocation::Share(100),
+                vesting_delay_blocks: 0,
+            })
+            .collect();
+
+        assert!(validate_beneficiaries(&inheritance.beneficiaries, 0, &inheritance.rule_set));
+        assert!(matches!(
+            validate_size(&inheritance),
+            Err(ContractError::ContentTooLarge { max: MAX_CONTENT_BYTES, .. })
+        ));
+        assert!(!validate_inheritance(&inheritance));
+
+        // The same content passes with a larger cap
+        assert!(validate_size_with_cap(&inheritance, 64 * 1024).is_ok());
+    }
+
+    #[derive(Serialize)]
+    struct ContentWithExtraKey {
+        #[serde(flatten)]
+        inner: InheritanceContent,
+        unexpected: String,
+    }
+
+    fn charms_with_extra_key(app: &App, inheritance: &InheritanceContent) -> Charms {
+        let content = ContentWithExtraKey {
+            inner: inheritance.clone(),
+            unexpected: "surprise".to_string(),
+        };
+        Charms::from([(app.clone(), Data::from(&content))])
+    }
+
+    #[test]
+    fn test_unknown_fields_rejected_on_create() {
+        let funding = vec![test_utxo_id(0)];
+        let (app, mut tx) = create_tx(&funding, 1);
+        tx.outs = vec![charms_with_extra_key(&app, &test_inheritance())];
+
+        let w = Data::from(&test_utxo_id(0).to_string());
+        assert!(!can_create_inheritance(&app, &tx, &w));
+    }
+
+    #[test]
+    fn test_unknown_fields_rejected_on_spend() {
+        let app = test_app();
+        let input = test_inheritance();
+        let mut output = input.clone();
+        output.last_checkin_block += 10;
+
+        let tx = test_tx(vec![charms_with_extra_key(&app, &input)], vec![nft_charms(&app, &output)]);
+        assert!(!can_checkin(&app, &tx, &checkin_witness(&tx, CURRENT_BLOCK)));
+        assert!(!can_trigger_distribution(&app, &test_tx(vec![charms_with_extra_key(&app, &input)], vec![]), &at_block(CURRENT_BLOCK)));
+
+        let tx = test_tx(vec![nft_charms(&app, &input)], vec![charms_with_extra_key(&app, &output)]);
+        assert!(!can_checkin(&app, &tx, &checkin_witness(&tx, CURRENT_BLOCK)));
+        assert!(!can_update_beneficiaries(&app, &tx, &update_witness(&tx)));
+    }
+
+    #[test]
+    fn test_every_operation_decodes_strictly() {
+        let app = test_app();
+        let input = test_inheritance();
+        let x = block_height_input(CURRENT_BLOCK);
+        let mut output = input.clone();
+        output.last_checkin_block = CURRENT_BLOCK;
+
+        // Payloads the strict decoder rejects: an unknown key, and a version from the future
+        let rejected = |content: &InheritanceContent| {
+            let mut future = content.clone();
+            future.version = CONTENT_VERSION + 1;
+            [charms_with_extra_key(&app, content), nft_charms(&app, &future)]
+        };
+
+        let plan = compute_distribution_plan(&input, 1_000_000);
+        let distribution = distribution_tx(&app, &input, 1_000_000, &plan);
+        let trigger = operation_witness(Operation::TriggerDistribution, &no_derivations());
+        assert!(app_contract(&app, &distribution, &x, &trigger));
+        let spend = test_tx(vec![nft_charms(&app, &input)], vec![nft_charms(&app, &output)]);
+        let checkin = operation_witness(Operation::Checkin, &checkin_witness(&spend, CURRENT_BLOCK));
+        assert!(app_contract(&app, &spend, &x, &checkin));
+
+        for bad in rejected(&input) {
+            assert!(InheritanceContent::try_from(&bad[&app]).is_err());
+
+            let mut tx = distribution.clone();
+            tx.ins[0].1 = bad.clone();
+            assert!(!app_contract(&app, &tx, &x, &trigger));
+
+            let mut tx = spend.clone();
+            tx.ins[0].1 = bad;
+            assert!(!app_contract(&app, &tx, &x, &checkin));
+        }
+        for bad in rejected(&output) {
+            let mut tx = spend.clone();
+            tx.outs[0] = bad;
+            assert!(!app_contract(&app, &tx, &x, &checkin));
+        }
+
+        let funding = vec![test_utxo_id(0)];
+        let (app, mut tx) = create_tx(&funding, 1);
+        let w = operation_witness(Operation::Create, &Data::from(&test_utxo_id(0).to_string()));
+        assert!(app_contract(&app, &tx, &block_height_input(100), &w));
+        for bad in rejected(&test_inheritance()) {
+            tx.outs[0] = bad.into_values().map(|data| (app.clone(), data)).collect();
+            assert!(!app_contract(&app, &tx, &block_height_input(100), &w));
+        }
+    }
+
+    #[test]
+    fn test_unknown_fields_rejected_at_current_version() {
+        // A current-version payload with an extra key is neither read as the latest
+        // layout nor passed off as an older one
+        let data = Data::from(&ContentWithExtraKey {
+            inner: test_inheritance(),
+            unexpected: "surprise".to_string(),
+        });
+        assert_eq!(migrate_to_latest(&data), Err(MigrationError::Undecodable));
+        assert_eq!(content_version(&data), None);
+
+        // Old shapes still migrate through their own layouts, which are just as strict
+        let v1 = InheritanceContentV1 {
+            owner_pubkey: test_inheritance().owner_pubkey,
+            co_signers: vec![],
+            multisig_threshold: 0,
+            last_checkin_block: 100,
+            trigger_delay_blocks: 4320,
+            beneficiaries: vec![BeneficiaryV1 {
+                address: "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx".to_string(),
+                percentage: 100,
+            }],
+            status: InheritanceStatus::Active,
+            delay_decay: None,
+            version: 1,
+        };
+        assert!(migrate_to_latest(&Data::from(&v1)).is_ok());
+        assert!(migrate_to_latest(&legacy_data(&test_inheritance())).is_ok());
+
+        #[derive(Serialize)]
+        struct V1WithExtraKey {
+            #[serde(flatten)]
+            inner: InheritanceContentV1,
+            unexpected: String,
+        }
+        let data = Data::from(&V1WithExtraKey {
+            inner: v1,
+            unexpected: "surprise".to_string(),
+        });
+        assert_eq!(migrate_to_latest(&data), Err(MigrationError::Undecodable));
+    }
+
+    #[test]
+    fn test_unknown_beneficiary_fields_rejected() {
+        #[derive(Serialize)]
+        struct BeneficiaryWithExtraKey {
+            address: String,
+            allocation: Allocation,
+            nickname: String,
+        }
+
+        let data = Data::from(&BeneficiaryWithExtraKey {
+            address: "tb1p123".to_string(),
+            allocation: Allocation::Share(10000),
+            nickname: "kid".to_string(),
+        });
+        assert!(data.value::<Beneficiary>().is_err());
+    }
+
+    #[test]
+    fn test_is_expired_boundary() {
+        let inheritance = test_inheritance();
+        let deadline = deadline_block(&inheritance, 0);
+        assert_eq!(deadline, 100 + 4320);
+        assert!(!is_expired(&inheritance, deadline));
+        assert!(is_expired(&inheritance, deadline + 1));
+    }
+
+    #[test]
+    fn test_effective_delay_decay_schedule() {
+        let mut inheritance = test_inheritance();
+        inheritance.trigger_delay_blocks = 1000;
+        inheritance.delay_decay = Some(DelayDecay {
+            interval_blocks: 100,
+            step_blocks: 200,
+            floor_blocks: 300,
+        });
+        assert!(validate_inheritance(&inheritance));
+
+        let start = inheritance.last_checkin_block;
+        assert_eq!(effective_delay(&inheritance, start), 1000);
+        assert_eq!(effective_delay(&inheritance, start + 99), 1000);
+        assert_eq!(effective_delay(&inheritance, start + 100), 800);
+        assert_eq!(effective_delay(&inheritance, start + 250), 600);
+        assert_eq!(effective_delay(&inheritance, start + 350), 400);
+        assert_eq!(effective_delay(&inheritance, start + 400), 300);
+        assert_eq!(effective_delay(&inheritance, start + 10_000), 300);
+
+        // The shrinking delay pulls the deadline in
+        assert!(!is_expired(&inheritance, start + 350));
+        assert!(is_expired(&inheritance, start + 400));
+
+        // Without a policy the delay is fixed
+        inheritance.delay_decay = None;
+        assert_eq!(effective_delay(&inheritance, start + 10_000), 1000);
+    }
+
+    #[test]
+    fn test_delay_decay_floor_above_delay_rejected() {
+        let mut inheritance = test_inheritance();
+        inheritance.delay_decay = Some(DelayDecay {
+            interval_blocks: 100,
+            step_blocks: 200,
+            floor_blocks: inheritance.trigger_delay_blocks + 1,
+        });
+        assert!(!validate_inheritance(&inheritance));
+    }
+
+    #[test]
+    fn test_validate_owner_pubkey() {
+        // Generator point, x-only and compressed
+        let x_only = "79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798";
+        assert!(validate_owner_pubkey(x_only));
+        assert!(validate_owner_pubkey(&format!("02{}", x_only)));
+        assert!(validate_owner_pubkey(&format!("02{}", x_only.to_uppercase())));
+
+        // Odd-length hex
+        assert!(!validate_owner_pubkey(&x_only[1..]));
+        // Non-hex
+        assert!(!validate_owner_pubkey(&format!("02{}", x_only.replace('7', "g"))));
+        assert!(!validate_owner_pubkey("hello world"));
+        assert!(!validate_owner_pubkey(""));
+        // Point at infinity / zero encodings
+        assert!(!validate_owner_pubkey(&"00".repeat(33)));
+        assert!(!validate_owner_pubkey(&"00".repeat(32)));
+    }
+
+    #[test]
+    fn test_create_rejects_invalid_owner_pubkey() {
+        let mut inheritance = test_inheritance();
+        assert!(validate_inheritance(&inheritance));
+
+        inheritance.owner_pubkey = "hello world".to_string();
+        assert!(!validate_inheritance(&inheritance));
+    }
+
+    #[test]
+    fn test_create_rejects_invalid_co_signer() {
+        let mut inheritance = cosigned_inheritance();
+        assert!(validate_inheritance(&inheritance));
+
+        inheritance.co_signers[1] = "00".repeat(33);
+        assert!(!validate_inheritance(&inheritance));
+    }
+
+    #[test]
+    fn test_create_rejects_status_skipped_forward() {
+        let funding = vec![test_utxo_id(0)];
+        let (app, mut tx) = create_tx(&funding, 1);
+        let w = Data::from(&CreateWitness::Single(test_utxo_id(0).to_string()));
+        assert!(can_create_inheritance(&app, &tx, &w));
+
+        for status in [InheritanceStatus::Triggered, InheritanceStatus::Distributed] {
+            let mut inheritance = test_inheritance();
+            inheritance.status = status;
+            assert_eq!(inheritance.validate(), Err(ValidationError::NotActive));
+
+            tx.outs[0] = nft_charms(&app, &inheritance);
+            assert!(!can_create_inheritance(&app, &tx, &w));
+        }
+    }
+
+    #[test]
+    fn test_valid_transition() {
+        use InheritanceStatus::*;
+
+        // A contract is born Active and statuses never move backwards
+        assert!(valid_transition(None, &Active));
+        assert!(!valid_transition(None, &Triggered));
+        assert!(!valid_transition(None, &Distributed));
+        for (from, to) in [(Active, Active), (Active, Triggered), (Active, Distributed), (Triggered, Triggered), (Triggered, Distributed)] {
+            assert!(valid_transition(Some(&from), &to));
+        }
+        assert!(!valid_transition(Some(&Triggered), &Active));
+        for to in InheritanceStatus::ALL {
+            assert!(!valid_transition(Some(&Distributed), &to));
+        }
+    }
+
+    #[cfg(feature = "arbitrary")]
+    #[test]
+    fn test_arbitrary_owner_pubkey_prefix() {
+        use arbitrary::{Arbitrary, Unstructured};
+
+        for seed in 0..=255u8 {
+            let raw = [seed; 64];
+            let mut u = Unstructured::new(&raw);
+            let pubkey = OwnerPubkey::arbitrary(&mut u).unwrap();
+            assert!(pubkey.0[0] == 0x02 || pubkey.0[0] == 0x03);
+        }
+    }
+
+    #[test]
+    fn test_validate_beneficiaries_single_entry_over_cap() {
+        let beneficiaries = vec![Beneficiary {
+            payee: Payee::Address(test_address(0)),
+            allocation: Allocation::Share(FULL_SHARE + 1),
+            vesting_delay_blocks: 0,
+        }];
+        assert!(!validate_beneficiaries(&beneficiaries, 0, &RuleSet::default()));
+
+        let beneficiaries = vec![Beneficiary {
+            payee: Payee::Address(test_address(0)),
+            allocation: Allocation::Share(FULL_SHARE),
+            vesting_delay_blocks: 0,
+        }];
+        assert!(validate_beneficiaries(&beneficiaries, 0, &RuleSet::default()));
+    }
+
+    #[test]
+    fn test_content_size_at_limit_and_one_over() {
+        let mut inheritance = test_inheritance();
+        inheritance.rule_set = RuleSet::permissive();
+        inheritance.beneficiaries = (0..25)
+            .map(|i| Beneficiary {
+                payee: Payee::Address(test_address(i)),
+                allocation: Allocation::Share(400),
+                vesting_delay_blocks: 0,
+            })
+            .collect();
+        inheritance.metadata.insert("letter".to_string(), "x".repeat(1000));
+        assert!(content_size(&inheritance) < MAX_CONTENT_BYTES);
+
+        // Grow a note one char at a time until the content is exactly at the cap. It
+        // starts long enough that its length prefix doesn't grow along with it.
+        let grow = |inheritance: &mut InheritanceContent| {
+            inheritance
+                .metadata
+                .entry("note".to_string())
+                .or_insert_with(|| "x".repeat(256))
+                .push('x');
+        };
+        while content_size(&inheritance) < MAX_CONTENT_BYTES {
+            grow(&mut inheritance);
+        }
+        assert_eq!(content_size(&inheritance), MAX_CONTENT_BYTES);
+        assert!(validate_size(&inheritance).is_ok());
+        assert!(validate_inheritance(&inheritance));
+
+        // One more byte goes over
+        grow(&mut inheritance);
+        assert_eq!(content_size(&inheritance), MAX_CONTENT_BYTES + 1);
+        assert!(validate_size(&inheritance).is_err());
+        assert!(!validate_inheritance(&inheritance));
+    }
+
+    #[test]
+    fn test_validate_beneficiaries_address_too_long() {
+        let beneficiaries = vec![Beneficiary {
+            payee: Payee::Address("x".repeat(MAX_ADDRESS_LEN + 1)),
+            allocation: Allocation::Share(10000),
+            vesting_delay_blocks: 0,
+        }];
+        assert!(!validate_beneficiaries(&beneficiaries, 0, &RuleSet::default()));
+    }
+
+    fn test_owner_pubkey() -> OwnerPubkey {
+        let bytes = decode_hex(&test_inheritance().owner_pubkey).unwrap();
+        OwnerPubkey::try_from(bytes).unwrap()
+    }
+
+    #[test]
+    fn test_redact_owner_hides_pubkey() {
+        let inheritance = test_inheritance();
+        let redacted = redact_owner(&inheritance);
+
+        let serialized = Data::from(&redacted).bytes();
+        let pubkey_bytes = &test_owner_pubkey().0[1..];
+        assert!(!serialized.windows(pubkey_bytes.len()).any(|w| w == pubkey_bytes));
+        assert!(!serialized
+            .windows(inheritance.owner_pubkey.len())
+            .any(|w| w == inheritance.owner_pubkey.as_bytes()));
+
+        assert_eq!(redacted.beneficiaries.len(), inheritance.beneficiaries.len());
+        assert_eq!(redacted.last_checkin_block, inheritance.last_checkin_block);
+    }
+
+    #[test]
+    fn test_verify_owner_commitment() {
+        let mut inheritance = test_inheritance();
+        let redacted = redact_owner(&inheritance);
+        assert!(verify_owner_commitment(&redacted, &test_owner_pubkey()));
+
+        // The x-only form of the same key commits identically
+        inheritance.owner_pubkey = inheritance.owner_pubkey[2..].to_string();
+        assert!(verify_owner_commitment(&redact_owner(&inheritance), &test_owner_pubkey()));
+
+        let mut other = test_owner_pubkey();
+        other.0[32] ^= 1;
+        assert!(!verify_owner_commitment(&redacted, &other));
+    }
+
+    #[test]
+    fn test_summarize_active_before_deadline() {
+        let inheritance = test_inheritance();
+        let summary = summarize(&inheritance, 1000);
+
+        assert_eq!(summary.status, InheritanceStatus::Active);
+        assert_eq!(summary.effective_delay_blocks, 4320);
+        assert_eq!(summary.deadline_block, 4420);
+        assert_eq!(summary.blocks_until_deadline, 3420);
+        assert!(!summary.is_triggerable);
+        assert_eq!(summary.total_share_bps, 10_000);
+        assert_eq!(summary.beneficiary_count, 2);
+    }
+
+    #[test]
+    fn test_summarize_past_deadline() {
+        let mut inheritance = test_inheritance();
+        let summary = summarize(&inheritance, 4420);
+        assert_eq!(summary.blocks_until_deadline, 0);
+        assert!(!summary.is_triggerable);
+
+        let summary = summarize(&inheritance, 4421);
+        assert!(summary.is_triggerable);
+
+        inheritance.status = InheritanceStatus::Triggered;
+        assert!(summarize(&inheritance, 4421).is_triggerable);
+
+        inheritance.status = InheritanceStatus::Distributed;
+        assert!(!summarize(&inheritance, 4421).is_triggerable);
+    }
+
+    #[test]
+    fn test_summarize_with_decay() {
+        let mut inheritance = test_inheritance();
+        inheritance.delay_decay = Some(DelayDecay {
+            interval_blocks: 1000,
+            step_blocks: 1000,
+            floor_blocks: 1000,
+        });
+
+        let summary = summarize(&inheritance, 2100);
+        assert_eq!(summary.effective_delay_blocks, 2320);
+        assert_eq!(summary.deadline_block, 2420);
+        assert_eq!(summary.blocks_until_deadline, 320);
+    }
+
+    // The original layout, exactly as the first spells minted it
+    #[derive(Serialize)]
+    struct LegacyContent {
+        owner_pubkey: String,
+        last_checkin_block: u64,
+        trigger_delay_blocks: u64,
+        beneficiaries: Vec<BeneficiaryV1>,
+        status: InheritanceStatus,
+    }
+
+    fn legacy_data(inheritance: &InheritanceContent) -> Data {
+        Data::from(&LegacyContent {
+            owner_pubkey: inheritance.owner_pubkey.clone(),
+            last_checkin_block: inheritance.last_checkin_block,
+            trigger_delay_blocks: inheritance.trigger_delay_blocks,
+            beneficiaries: inheritance
+                .beneficiaries
+                .iter()
+                .map(|b| BeneficiaryV1 {
+                    address: b.payee.as_address().unwrap_or_default().to_string(),
+                    percentage: (b.allocation.share_bps().unwrap_or(0) / 100) as u8,
+                })
+                .collect(),
+            status: inheritance.status.clone(),
+        })
+    }
+
+    // test_inheritance as a legacy vault holds it: created before created_at_block existed
+    fn legacy_inheritance() -> InheritanceContent {
+        InheritanceContent {
+            created_at_block: 0,
+            ..test_inheritance()
+        }
+    }
+
+    fn owner_sign(msg: &[u8; 32]) -> Vec<u8> {
+        // Secret key 1, whose public key is the generator used by test_inheritance
+        let mut secret = [0u8; 32];
+        secret[31] = 1;
+        let signing_key = k256::schnorr::SigningKey::from_bytes(&secret).unwrap();
+        signing_key.sign_raw(msg, &[0u8; 32]).unwrap().to_bytes().to_vec()
+    }
+
+    #[test]
+    fn test_migrate_round_trip_latest() {
+        let inheritance = test_inheritance();
+        let migrated = migrate_to_latest(&Data::from(&inheritance)).unwrap();
+        assert_eq!(migrated, inheritance);
+    }
+
+    #[test]
+    fn test_migrate_legacy_payload() {
+        let inheritance = legacy_inheritance();
+        let data = legacy_data(&inheritance);
+        assert_eq!(content_version(&data), Some(0));
+
+        let migrated = migrate_to_latest(&data).unwrap();
+        assert_eq!(migrated.version, CONTENT_VERSION);
+        assert_eq!(migrated, inheritance);
+    }
+
+    #[test]
+    fn test_migrate_unknown_version_rejected() {
+        let mut inheritance = test_inheritance();
+        inheritance.version = CONTENT_VERSION + 1;
+        assert_eq!(
+            migrate_to_latest(&Data::from(&inheritance)),
+            Err(MigrationError::UnsupportedVersion(CONTENT_VERSION + 1))
+        );
+        assert_eq!(migrate_to_latest(&Data::from(&42u64)), Err(MigrationError::Undecodable));
+    }
+
+    #[test]
+    fn test_legacy_vault_keeps_checking_in() {
+        let app = test_app();
+        let input = test_inheritance();
+        let mut output = input.clone();
+        output.last_checkin_block += 10;
+
+        let tx = test_tx(
+            vec![Charms::from([(app.clone(), legacy_data(&input))])],
+            vec![Charms::from([(app.clone(), legacy_data(&output))])],
+        );
+        assert!(can_checkin(&app, &tx, &checkin_witness(&tx, CURRENT_BLOCK)));
+    }
+
+    fn migrate_witness(tx: &Transaction, content: &InheritanceContent) -> Data {
+        Data::from(&MigrateWitness {
+            signature: owner_sign(&signing_message(Operation::Migrate, tx, content)),
+        })
+    }
+
+    #[test]
+    fn test_can_migrate() {
+        let app = test_app();
+        let inheritance = legacy_inheritance();
+        let input = Charms::from([(app.clone(), legacy_data(&inheritance))]);
+
+        let tx = test_tx(vec![input.clone()], vec![nft_charms(&app, &inheritance)]);
+        let w = migrate_witness(&tx, &inheritance);
+        assert!(can_migrate(&app, &tx, &w));
+
+        // Semantics must be preserved
+        let mut changed = inheritance.clone();
+        changed.trigger_delay_blocks += 1;
+        let tx = test_tx(vec![input.clone()], vec![nft_charms(&app, &changed)]);
+        let w_changed = migrate_witness(&tx, &changed);
+        assert!(!can_migrate(&app, &tx, &w_changed));
+
+        // Already-latest content has nothing to migrate
+        let tx = test_tx(vec![nft_charms(&app, &inheritance)], vec![nft_charms(&app, &inheritance)]);
+        assert!(!can_migrate(&app, &tx, &w));
+
+        // Signature must be the owner's over the new content
+        let w_bad = Data::from(&MigrateWitness {
+            signature: owner_sign(&[0u8; 32]),
+        });
+        let tx = test_tx(vec![input], vec![nft_charms(&app, &inheritance)]);
+        assert!(!can_migrate(&app, &tx, &w_bad));
+    }
+
+    #[test]
+    fn test_strict_rules_reject_single_beneficiary() {
+        let mut inheritance = test_inheritance();
+        inheritance.beneficiaries = vec![Beneficiary {
+            payee: Payee::Address(test_address(0)),
+            allocation: Allocation::Share(10000),
+            vesting_delay_blocks: 0,
+        }];
+        assert!(validate_inheritance(&inheritance));
+
+        inheritance.rule_set = RuleSet::strict();
+        assert!(!validate_inheritance(&inheritance));
+
+        // Two beneficiaries satisfy the strict rules
+        inheritance.beneficiaries = test_inheritance().beneficiaries;
+        assert!(validate_inheritance(&inheritance));
+    }
+
+    #[test]
+    fn test_rule_set_minimum_delay() {
+        let mut inheritance = test_inheritance();
+        inheritance.rule_set = RuleSet::strict();
+        inheritance.trigger_delay_blocks = RuleSet::strict().minimum_delay_blocks - 1;
+        assert!(!validate_inheritance(&inheritance));
+
+        inheritance.rule_set = RuleSet::permissive();
+        assert!(validate_inheritance(&inheritance));
+    }
+
+    #[test]
+    fn test_rule_set_cannot_change_on_update() {
+        let app = test_app();
+        let input = test_inheritance();
+        let mut output = input.clone();
+        output.rule_set = RuleSet::permissive();
+        output.rule_set.max_beneficiaries = 50;
+
+        let tx = test_tx(vec![nft_charms(&app, &input)], vec![nft_charms(&app, &output)]);
+        assert!(!can_update_beneficiaries(&app, &tx, &update_witness(&tx)));
+    }
+
+    #[test]
+    fn test_default_rule_set_not_serialized() {
+        // Contracts on default rules serialize exactly as before rule sets existed
+        let inheritance = test_inheritance();
+        let bytes = Data::from(&inheritance).bytes();
+        assert!(!bytes.windows(8).any(|w| w == b"rule_set"));
+
+        // ...and payloads without a rule set decode to the defaults
+        let migrated = migrate_to_latest(&legacy_data(&inheritance)).unwrap();
+        assert_eq!(migrated.rule_set, RuleSet::default());
+    }
+
+    #[test]
+    fn test_signing_message_stable_across_round_trip() {
+        let app = test_app();
+        let inheritance = test_inheritance();
+        let tx = test_tx(vec![nft_charms(&app, &inheritance)], vec![nft_charms(&app, &inheritance)]);
+        let message = signing_message(Operation::Checkin, &tx, &inheritance);
+
+        // Content decoded back from its NFT payload signs identically
+        let decoded = migrate_to_latest(&Data::from(&inheritance)).unwrap();
+        assert_eq!(signing_message(Operation::Checkin, &tx, &decoded), message);
+
+        // So does a transaction that went through serialization
+        let decoded_tx: Transaction = Data::from(&tx).value().unwrap();
+        assert_eq!(signing_message(Operation::Checkin, &decoded_tx, &inheritance), message);
+
+        // Operation, inputs and content are all committed to
+        assert_ne!(signing_message(Operation::UpdateBeneficiaries, &tx, &inheritance), message);
+        let other_tx = test_tx(vec![Charms::new(), nft_charms(&app, &inheritance)], vec![]);
+        assert_ne!(signing_message(Operation::Checkin, &other_tx, &inheritance), message);
+        let mut changed = inheritance.clone();
+        changed.last_checkin_block += 1;
+        assert_ne!(signing_message(Operation::Checkin, &tx, &changed), message);
+    }
+
+    fn bps_beneficiaries(shares: &[u16]) -> Vec<Beneficiary> {
+        shares
+            .iter()
+            .enumerate()
+            .map(|(i, &share_bps)| Beneficiary {
+                payee: Payee::Address(test_address(i)),
+                allocation: Allocation::Share(share_bps),
+                vesting_delay_blocks: 0,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_share_bps_sum_must_be_exact() {
+        let rules = RuleSet::default();
+        assert!(validate_beneficiaries(&bps_beneficiaries(&[1250, 8750]), 0, &rules));
+        assert!(!validate_beneficiaries(&bps_beneficiaries(&[1250, 8749]), 0, &rules));
+        assert!(!validate_beneficiaries(&bps_beneficiaries(&[1250, 8751]), 0, &rules));
+    }
+
+    #[test]
+    fn test_legacy_and_bps_payloads_mix() {
+        let app = test_app();
+        let input = legacy_inheritance();
+        let mut output = input.clone();
+        output.last_checkin_block += 10;
+
+        // Legacy whole-percent input decodes with shares scaled to bps
+        let legacy = legacy_data(&input);
+        let migrated = migrate_to_latest(&legacy).unwrap();
+        assert_eq!(migrated.beneficiaries[0].allocation, Allocation::Share(6000));
+        assert_eq!(migrated.beneficiaries[1].allocation, Allocation::Share(4000));
+
+        // A check-in can spend a legacy NFT into a bps one
+        let tx = test_tx(
+            vec![Charms::from([(app.clone(), legacy)])],
+            vec![nft_charms(&app, &output)],
+        );
+        assert!(can_checkin(&app, &tx, &checkin_witness(&tx, CURRENT_BLOCK)));
+    }
+
+    #[test]
+    fn test_one_bps_beneficiary_and_dust() {
+        let beneficiaries = bps_beneficiaries(&[1, 9999]);
+        assert!(validate_beneficiaries(&beneficiaries, 0, &RuleSet::default()));
+
+        // 1 bps of 1 BTC is payable, 1 bps of 0.05 BTC is dust
+        assert_eq!(share_amount(100_000_000, 1), 10_000);
+        assert!(!is_dust_share(100_000_000, 1));
+        assert_eq!(share_amount(5_000_000, 1), 500);
+        assert!(is_dust_share(5_000_000, 1));
+    }
+
+    #[test]
+    fn test_address_to_script() {
+        // BIP-173 P2WPKH vector
+        let script = address_to_script("tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx").unwrap();
+        assert_eq!(script[..2], [0x00, 0x14]);
+        assert_eq!(script[2..], decode_hex("751e76e8199196d454941c45d1b3a323f1433bd6").unwrap()[..]);
+
+        // BIP-350 taproot vector
+        let script = address_to_script("tb1pqqqqp399et2xygdj5xreqhjjvcmzhxw4aywxecjdzew6hylgvsesf3hn0c").unwrap();
+        assert_eq!(script[..2], [0x51, 0x20]);
+
+        // Legacy P2PKH
+        let script = address_to_script("1BvBMSEYstWetqTFn5Au4m4GFg7xJaNVN2").unwrap();
+        assert_eq!(script[..3], [0x76, 0xa9, 0x14]);
+
+        assert!(address_to_script("tb1p123").is_none());
+    }
+
+    #[test]
+    fn test_allocate_amounts_balances() {
+        let amounts = allocate_amounts(100, &[3334, 3333, 3333], RoundingMode::FloorFirst);
+        assert_eq!(amounts, vec![34, 33, 33]);
+        assert_eq!(amounts.iter().sum::<u64>(), 100);
+    }
+
+    #[test]
+    fn test_rounding_modes_on_three_way_split() {
+        // The largest fraction is the last entry's (33.34 sats)
+        let shares = [3333, 3333, 3334];
+        assert_eq!(allocate_amounts(100, &shares, RoundingMode::FloorFirst), vec![34, 33, 33]);
+        assert_eq!(allocate_amounts(100, &shares, RoundingMode::LargestRemainder), vec![33, 33, 34]);
+
+        // Two sats left over: one each for the two largest fractions, earlier entry winning ties
+        let shares = [3000, 3500, 3500];
+        assert_eq!(allocate_amounts(5, &shares, RoundingMode::FloorFirst), vec![3, 1, 1]);
+        assert_eq!(allocate_amounts(5, &shares, RoundingMode::LargestRemainder), vec![1, 2, 2]);
+
+        // Shares below 100% still balance
+        assert_eq!(allocate_amounts(100, &[2500, 2500], RoundingMode::LargestRemainder), vec![74, 26]);
+    }
+
+    #[test]
+    fn test_allocate_amounts_over_full_share() {
+        // 120% of the total can't be paid out: no amounts rather than an underflow
+        for rounding in [RoundingMode::FloorFirst, RoundingMode::LargestRemainder] {
+            assert!(allocate_amounts(100, &[6000, 6000], rounding).is_empty());
+            assert!(allocate_amounts(u64::MAX, &[FULL_SHARE; 3], rounding).is_empty());
+            assert!(allocate_amounts_ppm(100, &[600_000, 600_000], rounding).is_empty());
+            assert_eq!(waterfall(100, &[Allocation::Share(6000), Allocation::Share(6000)], rounding), vec![0, 0]);
+        }
+        assert_eq!(allocate_amounts(100, &[FULL_SHARE], RoundingMode::FloorFirst), vec![100]);
+    }
+
+    #[test]
+    fn test_distribution_honors_rounding_mode() {
+        let app = test_app();
+        let mut inheritance = test_inheritance();
+        inheritance.beneficiaries[0].allocation = Allocation::Share(3333);
+        inheritance.beneficiaries[1].allocation = Allocation::Share(6667);
+        inheritance.rounding_mode = RoundingMode::LargestRemainder;
+        assert!(validate_inheritance(&inheritance));
+
+        let plan = compute_distribution_plan(&inheritance, 1_000_001);
+        assert_eq!((plan[0].1, plan[1].1), (333_300, 666_701));
+        let tx = distribution_tx(&app, &inheritance, 1_000_001, &plan);
+        assert!(can_trigger_distribution(&app, &tx, &at_block(CURRENT_BLOCK)));
+
+        // Paying the floor-first split instead is rejected
+        let mut floor_first = inheritance.clone();
+        floor_first.rounding_mode = RoundingMode::FloorFirst;
+        let plan = compute_distribution_plan(&floor_first, 1_000_001);
+        assert_eq!((plan[0].1, plan[1].1), (333_301, 666_700));
+        let tx = distribution_tx(&app, &inheritance, 1_000_001, &plan);
+        assert!(!can_trigger_distribution(&app, &tx, &at_block(CURRENT_BLOCK)));
+    }
+
+    #[test]
+    fn test_distribution_exact_match() {
+        let app = test_app();
+        let inheritance = test_inheritance();
+        let plan = compute_distribution_plan(&inheritance, 1_000_000);
+        assert_eq!(plan[0].1, 600_000);
+        assert_eq!(plan[1].1, 400_000);
+
+        let tx = distribution_tx(&app, &inheritance, 1_000_000, &plan);
+        assert!(verify_distribution_completeness(&app, &inheritance, &tx, &DistributionWitness::default()));
+        assert!(can_trigger_distribution(&app, &tx, &at_block(CURRENT_BLOCK)));
+    }
+
+    #[test]
+    fn test_distribution_rejects_garbage_witness() {
+        let app = test_app();
+        let inheritance = test_inheritance();
+        let plan = compute_distribution_plan(&inheritance, 1_000_000);
+        let tx = distribution_tx(&app, &inheritance, 1_000_000, &plan);
+
+        // The payouts are complete, but a witness that doesn't decode isn't one without derivations
+        let (garbage, missing) = (Data::from(&"garbage"), Data::empty());
+        assert!(!can_trigger_distribution(&app, &tx, &garbage));
+        assert!(!can_trigger_distribution(&app, &tx, &missing));
+        let x = block_height_input(CURRENT_BLOCK);
+        assert_eq!(
+            evaluate(&app, &tx, &x, &operation_witness(Operation::TriggerDistribution, &garbage)),
+            Err(ContractError::TriggerDistribution(RuleFailed::Witness))
+        );
+    }
+
+    #[test]
+    fn test_inheritance_tax_estimate() {
+        let inheritance = test_inheritance();
+        let estimate = compute_inheritance_tax_estimate(&inheritance, 1_000_000, 1000);
+        let heirs: Vec<String> = inheritance.beneficiaries.iter().map(|b| b.payee.label()).collect();
+
+        assert_eq!(
+            estimate.gross_per_beneficiary,
+            vec![(heirs[0].clone(), 600_000), (heirs[1].clone(), 400_000)]
+        );
+        assert_eq!(estimate.tax_per_beneficiary, vec![(heirs[0].clone(), 60_000), (heirs[1].clone(), 40_000)]);
+        assert_eq!(estimate.total_tax_sats, 100_000);
+        let total_net: u64 = estimate.net_per_beneficiary.iter().map(|(_, net)| net).sum();
+        assert_eq!(total_net, 900_000);
+
+        // No tax leaves everything with the heirs
+        let untaxed = compute_inheritance_tax_estimate(&inheritance, 1_000_000, 0);
+        assert_eq!(untaxed.total_tax_sats, 0);
+        assert_eq!(untaxed.net_per_beneficiary, untaxed.gross_per_beneficiary);
+    }
+
+    #[test]
+    fn test_legal_document_lists_the_terms() {
+        let mut inheritance = test_inheritance();
+        let document = format_contract_for_legal_document(&inheritance);
+
+        assert!(document.starts_with("Grantor\n"));
+        let grantor = redact_owner(&inheritance).owner_pubkey_commitment;
+        assert!(document.contains(&format!("  Owner key commitment: {grantor}\n")));
+        assert!(!document.contains(&inheritance.owner_pubkey));
+        assert!(document.contains("  Delay: 4320 blocks (approximately 30 days) without a check-in\n"));
+        assert!(document.contains("  1 | tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx | 60%\n"));
+        assert!(document.contains("  2 | tb1pqqqqp399et2xygdj5xreqhjjvcmzhxw4aywxecjdzew6hylgvsesf3hn0c | 40%\n"));
+        assert!(document.ends_with(&format!("  Content hash: {}\n", inheritance.content_hash())));
+        assert_eq!(format_contract_for_legal_document(&inheritance), document);
+
+        inheritance.beneficiaries[0].allocation = Allocation::PreciseShare(SharePpm(333_334));
+        inheritance.beneficiaries[1].allocation = Allocation::Fixed(50_000);
+        let document = format_contract_for_legal_document(&inheritance);
+        assert!(document.contains(" | 33.3334%\n"));
+        assert!(document.contains(" | 50000 sats\n"));
+    }
+
+    #[test]
+    fn test_estimated_time_remaining() {
+        // Deadline is block 4420; 1728 ten-minute blocks are 12 days
+        let inheritance = test_inheritance();
+        let twelve_days = std::time::Duration::from_secs(12 * 86_400);
+        assert_eq!(estimated_time_remaining(&inheritance, 4420 - 1728, 600), Some(twelve_days));
+        assert_eq!(estimated_time_remaining(&inheritance, 4420, 600), Some(std::time::Duration::ZERO));
+        assert_eq!(estimated_time_remaining(&inheritance, 4421, 600), None);
+
+        // The interval is the caller's to choose
+        assert_eq!(estimated_time_remaining(&inheritance, 4420 - 1728, 300), Some(twelve_days / 2));
+    }
+
+    #[test]
+    fn test_distribution_rejects_padding_outputs() {
+        let app = test_app();
+        let inheritance = test_inheritance();
+        let plan = compute_distribution_plan(&inheritance, 1_000_000);
+
+        // One output per beneficiary plus change is fine
+        let mut padded = plan.clone();
+        padded.push((Payee::from("tb1qrp33g0q5c5txsp9arysrx4k6zdkfs4nce4xj0gdcccefvpysxf3q0sl5k7"), 546));
+        let tx = distribution_tx(&app, &inheritance, 1_000_000, &padded);
+        assert!(can_trigger_distribution(&app, &tx, &at_block(CURRENT_BLOCK)));
+
+        // A dust output more is padding
+        padded.push((plan[0].0.clone(), 546));
+        let tx = distribution_tx(&app, &inheritance, 1_000_000, &padded);
+        assert!(!verify_distribution_completeness(&app, &inheritance, &tx, &DistributionWitness::default()));
+        assert!(!can_trigger_distribution(&app, &tx, &at_block(CURRENT_BLOCK)));
+    }
+
+    #[test]
+    fn test_distribution_one_sat_short_fails() {
+        let app = test_app();
+        let inheritance = test_inheritance();
+        let mut plan = compute_distribution_plan(&inheritance, 1_000_000);
+        plan[1].1 -= 1;
+
+        let tx = distribution_tx(&app, &inheritance, 1_000_000, &plan);
+        assert!(!verify_distribution_completeness(&app, &inheritance, &tx, &DistributionWitness::default()));
+        assert!(!can_trigger_distribution(&app, &tx, &at_block(CURRENT_BLOCK)));
+    }
+
+    #[test]
+    fn test_distribution_one_sat_over_passes() {
+        let app = test_app();
+        let inheritance = test_inheritance();
+        let mut plan = compute_distribution_plan(&inheritance, 1_000_000);
+        plan[1].1 += 1;
+
+        let tx = distribution_tx(&app, &inheritance, 1_000_000, &plan);
+        assert!(verify_distribution_completeness(&app, &inheritance, &tx, &DistributionWitness::default()));
+    }
+
+    #[test]
+    fn test_distribution_missing_output_fails() {
+        let app = test_app();
+        let inheritance = test_inheritance();
+        let plan = compute_distribution_plan(&inheritance, 1_000_000);
+
+        let tx = distribution_tx(&app, &inheritance, 1_000_000, &plan[..1]);
+        assert!(!verify_distribution_completeness(&app, &inheritance, &tx, &DistributionWitness::default()));
+        assert!(!can_trigger_distribution(&app, &tx, &at_block(CURRENT_BLOCK)));
+    }
+
+    #[test]
+    fn test_distribution_ignores_foreign_app_decoy_input() {
+        let app = test_app();
+        let inheritance = test_inheritance();
+        let plan = compute_distribution_plan(&inheritance, 1_000);
+
+        // A charm of another app carrying identical content, worth far less, spent
+        // ahead of the vault: the payouts still have to cover the vault's value
+        let decoy = App {
+            identity: B32([9; 32]),
+            ..app.clone()
+        };
+        let mut tx = distribution_tx(&app, &inheritance, 1_000_000, &plan);
+        tx.ins.insert(0, (test_utxo_id(9), nft_charms(&decoy, &inheritance)));
+        tx.coin_ins.as_mut().unwrap().insert(0, NativeOutput {
+            amount: 1_000,
+            dest: vec![0x51, 0x20, 0xbb],
+        });
+        assert!(!verify_distribution_completeness(&app, &inheritance, &tx, &DistributionWitness::default()));
+
+        // Paying out the vault's full value passes
+        let plan = compute_distribution_plan(&inheritance, 1_000_000);
+        let mut tx = distribution_tx(&app, &inheritance, 1_000_000, &plan);
+        tx.ins.insert(0, (test_utxo_id(9), nft_charms(&decoy, &inheritance)));
+        tx.coin_ins.as_mut().unwrap().insert(0, NativeOutput {
+            amount: 1_000,
+            dest: vec![0x51, 0x20, 0xbb],
+        });
+        assert!(verify_distribution_completeness(&app, &inheritance, &tx, &DistributionWitness::default()));
+    }
+
+    #[test]
+    fn test_full_lifecycle() {
+        let (app, history) = test_history();
+        let create = history[0].clone();
+
+        let created = test_inheritance();
+        let mut first_checkin = created.clone();
+        first_checkin.last_checkin_block = 200;
+        let mut second_checkin = first_checkin.clone();
+        second_checkin.last_checkin_block = 300;
+        let mut updated = second_checkin.clone();
+        updated.last_checkin_block = 400;
+        updated.beneficiaries[0].allocation = Allocation::Share(4000);
+        updated.beneficiaries[1].allocation = Allocation::Share(6000);
+
+        let spend = |operation: Operation, input: &InheritanceContent, output: &InheritanceContent| {
+            let tx = test_tx(vec![nft_charms(&app, input)], vec![nft_charms(&app, output)]);
+            let w = match operation {
+                Operation::UpdateBeneficiaries => update_witness(&tx),
+                _ => checkin_witness(&tx, CURRENT_BLOCK),
+            };
+            (tx, block_height_input(CURRENT_BLOCK), operation_witness(operation, &w))
+        };
+        let distribute = distribution_tx(&app, &updated, 1_000_000, &compute_distribution_plan(&updated, 1_000_000));
+
+        let lifecycle = vec![
+            create,
+            spend(Operation::Checkin, &created, &first_checkin),
+            spend(Operation::Checkin, &first_checkin, &second_checkin),
+            spend(Operation::UpdateBeneficiaries, &second_checkin, &updated),
+            (
+                distribute,
+                block_height_input(CURRENT_BLOCK),
+                operation_witness(Operation::TriggerDistribution, &no_derivations()),
+            ),
+        ];
+        assert!(chain_validate(&lifecycle, &app).iter().all(|r| r.is_ok()));
+
+        // Track the vault's state from each transaction's output, the way a wallet would
+        let id = ContractId::from(&app);
+        let mut storage = InMemoryStorage::default();
+        for (tx, _, _) in &lifecycle[..4] {
+            let output = charm_values(&app, tx.outs.iter()).next().unwrap();
+            storage.save(id, migrate_to_latest(output).unwrap()).unwrap();
+        }
+        assert_eq!(storage.load(&id), Ok(Some(updated.clone())));
+        assert_eq!(storage.list_active(), vec![(id, updated.clone())]);
+
+        // Identity carries through every step
+        for (tx, _, _) in &lifecycle[1..] {
+            assert_eq!(inputs_with_app_identity(&app, tx).len(), 1);
+        }
+
+        // Each check-in pushes the deadline out; the vault only becomes triggerable once it passes
+        let deadline = deadline_block(&updated, 0);
+        assert!(deadline > deadline_block(&created, 0));
+        assert!(!is_expired(&updated, deadline));
+        assert!(is_expired(&updated, deadline + 1));
+        assert_eq!(summarize(&updated, deadline + 1).status, InheritanceStatus::Active);
+        assert!(summarize(&updated, deadline + 1).is_triggerable);
+
+        // A check-in can't rewind time
+        let mut rewound = updated.clone();
+        rewound.last_checkin_block = first_checkin.last_checkin_block;
+        let rewind = spend(Operation::Checkin, &updated, &rewound).0;
+        assert!(!can_checkin(&app, &rewind, &checkin_witness(&rewind, CURRENT_BLOCK)));
+
+        // Distributing a stale state breaks the chain
+        let mut stale = lifecycle.clone();
+        stale[4].0 = distribution_tx(
+            &app,
+            &second_checkin,
+            1_000_000,
+            &compute_distribution_plan(&second_checkin, 1_000_000),
+        );
+        assert_eq!(chain_validate(&stale, &app)[4], Err(InheritanceError::ChainBroken { tx_index: 4 }));
+
+        // Nothing survives distribution, so a later check-in has no state to spend
+        let mut after_distribution = lifecycle.clone();
+        after_distribution.push(spend(Operation::Checkin, &updated, &updated));
+        assert_eq!(
+            chain_validate(&after_distribution, &app)[5],
+            Err(InheritanceError::ChainBroken { tx_index: 5 })
+        );
+
+        // Once distributed, the vault drops out of the active list
+        let mut distributed = updated.clone();
+        distributed.status = InheritanceStatus::Distributed;
+        storage.save(id, distributed).unwrap();
+        assert!(storage.list_active().is_empty());
+    }
+
+    // One fixed gift up front, the rest split between the other two beneficiaries
+    fn fixed_and_share_inheritance() -> InheritanceContent {
+        let mut inheritance = test_inheritance();
+        inheritance.total_locked_sats = 1_000_000;
+        inheritance.beneficiaries = vec![
+            Beneficiary {
+                payee: Payee::from("tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx"),
+                allocation: Allocation::Share(5000),
+                vesting_delay_blocks: 0,
+            },
+            Beneficiary {
+                payee: Payee::from("tb1pqqqqp399et2xygdj5xreqhjjvcmzhxw4aywxecjdzew6hylgvsesf3hn0c"),
+                allocation: Allocation::Share(5000),
+                vesting_delay_blocks: 0,
+            },
+            Beneficiary {
+                payee: Payee::from("mipcBbFg9gMiCh81Kj8tqqdgoZub1ZJRfn"),
+                allocation: Allocation::Fixed(100_000),
+                vesting_delay_blocks: 0,
+            },
+        ];
+        inheritance
+    }
+
+    #[test]
+    fn test_fixed_allocation_plan() {
+        let app = test_app();
+        let inheritance = fixed_and_share_inheritance();
+        assert!(validate_inheritance(&inheritance));
+
+        // The fixed gift is paid first, shares split the remaining 900_000
+        let plan = compute_distribution_plan(&inheritance, 1_000_000);
+        let amounts: Vec<u64> = plan.iter().map(|(_, amount)| *amount).collect();
+        assert_eq!(amounts, vec![450_000, 450_000, 100_000]);
+
+        let tx = distribution_tx(&app, &inheritance, 1_000_000, &plan);
+        assert!(can_trigger_distribution(&app, &tx, &at_block(CURRENT_BLOCK)));
+    }
+
+    #[test]
+    fn test_fixed_allocations_exceeding_total_rejected() {
+        let mut inheritance = fixed_and_share_inheritance();
+        inheritance.total_locked_sats = 99_999;
+        assert!(!validate_inheritance(&inheritance));
+
+        // Overflowing fixed amounts are rejected rather than wrapping
+        let mut beneficiaries = fixed_and_share_inheritance().beneficiaries;
+        beneficiaries[0].allocation = Allocation::Fixed(u64::MAX);
+        assert!(!validate_beneficiaries(&beneficiaries, u64::MAX, &RuleSet::default()));
+    }
+
+    #[test]
+    fn test_fixed_allocations_paid_in_order_when_vault_short() {
+        // The vault turned out to hold less than the fixed gifts: earlier entries are paid first
+        let mut inheritance = fixed_and_share_inheritance();
+        inheritance.beneficiaries[0].allocation = Allocation::Fixed(300_000);
+        inheritance.beneficiaries[1].allocation = Allocation::Fixed(100_000);
+        inheritance.total_locked_sats = 500_000;
+        assert!(validate_inheritance(&inheritance));
+
+        let plan = compute_distribution_plan(&inheritance, 250_000);
+        let amounts: Vec<u64> = plan.iter().map(|(_, amount)| *amount).collect();
+        assert_eq!(amounts, vec![250_000, 0, 0]);
+
+        let plan = compute_distribution_plan(&inheritance, 350_000);
+        let amounts: Vec<u64> = plan.iter().map(|(_, amount)| *amount).collect();
+        assert_eq!(amounts, vec![300_000, 50_000, 0]);
+    }
+
+    #[test]
+    fn test_all_fixed_allocations_must_cover_total() {
+        let mut beneficiaries = fixed_and_share_inheritance().beneficiaries;
+        beneficiaries[0].allocation = Allocation::Fixed(500_000);
+        beneficiaries[1].allocation = Allocation::Fixed(400_000);
+
+        assert!(validate_beneficiaries(&beneficiaries, 1_000_000, &RuleSet::default()));
+        assert!(!validate_beneficiaries(&beneficiaries, 1_000_001, &RuleSet::default()));
+
+        // A fixed amount too small to pay out is rejected
+        beneficiaries[2].allocation = Allocation::Fixed(DUST_THRESHOLD_SATS - 1);
+        assert!(!validate_beneficiaries(&beneficiaries, 900_545, &RuleSet::default()));
+    }
+
+    #[test]
+    fn test_v2_payload_migrates_to_allocations() {
+        let inheritance = test_inheritance();
+        let v2 = InheritanceContentV2 {
+            owner_pubkey: inheritance.owner_pubkey.clone(),
+            co_signers: vec![],
+            multisig_threshold: 0,
+            last_checkin_block: inheritance.last_checkin_block,
+            trigger_delay_blocks: inheritance.trigger_delay_blocks,
+            beneficiaries: vec![BeneficiaryV2 {
+                address: "tb1p123".to_string(),
+                share_bps: 10_000,
+            }],
+            status: InheritanceStatus::Active,
+            delay_decay: None,
+            version: 2,
+            rule_set: RuleSet::default(),
+        };
+
+        let data = Data::from(&v2);
+        assert_eq!(content_version(&data), Some(2));
+        let migrated = migrate_to_latest(&data).unwrap();
+        assert_eq!(migrated.version, CONTENT_VERSION);
+        assert_eq!(migrated.beneficiaries[0].allocation, Allocation::Share(10_000));
+        assert_eq!(migrated.total_locked_sats, 0);
+    }
+
+    #[test]
+    fn test_snapshot_round_trip() {
+        let app = test_app();
+        let inheritance = test_inheritance();
+        let snapshot = create_snapshot(&app, &inheritance, 500);
+
+        assert_eq!(snapshot.contract_id, ContractId(app.identity));
+        assert_eq!(snapshot.snapshot_block, 500);
+        assert!(snapshot.snapshot_timestamp_unix > 0);
+
+        // JSON, as stored by cloud backups
+        let json = serde_json::to_string(&snapshot).unwrap();
+        let decoded: ContractSnapshot = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded, snapshot);
+        assert_eq!(restore_snapshot(&decoded), inheritance);
+
+        // Binary, using the same encoding as charm data
+        let decoded: ContractSnapshot = Data::from(&snapshot).value().unwrap();
+        assert_eq!(decoded, snapshot);
+        assert_eq!(restore_snapshot(&decoded), inheritance);
+    }
+
+    #[test]
+    fn test_inheritance_qr_round_trip() {
+        let mut inheritance = guardian_inheritance(2);
+        inheritance.delay_decay = Some(DelayDecay { interval_blocks: 144, step_blocks: 10, floor_blocks: 1000 });
+        inheritance.fallback_address = Some("tb1qrp33g0q5c5txsp9arysrx4k6zdkfs4nce4xj0gdcccefvpysxf3q0sl5k7".to_string());
+        inheritance.metadata.insert("label".to_string(), "Family vault".to_string());
+        inheritance.oracle_pubkey = Some(GUARDIAN_KEYS[0].to_string());
+        inheritance.will_document_hash = Some(B32([9; 32]));
+        inheritance.grace_period = Some(GracePeriod { extra_blocks: 144, requires_medical_attestation: true });
+        inheritance.court_pubkey = Some(test_signer_key(3));
+        inheritance.checkin_window_blocks = 1000;
+
+        let qr = encode_inheritance_qr(&inheritance);
+        let uri = std::str::from_utf8(&qr).unwrap();
+        let data = uri.strip_prefix("charm-inheritance://v1?data=").unwrap();
+        assert!(!data.contains(['+', '/', '=']));
+        assert_eq!(decode_inheritance_qr(&qr), Ok(inheritance));
+
+        // Anything that isn't one of our URIs
+        for malformed in ["", "charm-inheritance://v1?data=", "charm-inheritance://v2?data=oWE", "https://example.com"] {
+            assert_eq!(decode_inheritance_qr(malformed.as_bytes()), Err(QrDecodeError::InvalidUri), "{malformed}");
+        }
+        assert_eq!(decode_inheritance_qr(&[0xff, 0xfe]), Err(QrDecodeError::InvalidUri));
+
+        // ...and URIs that don't carry a contract
+        assert_eq!(decode_inheritance_qr(b"charm-inheritance://v1?data=a+b/"), Err(QrDecodeError::InvalidBase64));
+        assert_eq!(decode_inheritance_qr(b"charm-inheritance://v1?data=AAAA"), Err(QrDecodeError::InvalidContent));
+    }
+
+    #[test]
+    fn test_distribution_outputs_matched_by_address() {
+        let app = test_app();
+        let inheritance = test_inheritance();
+        let mut plan = compute_distribution_plan(&inheritance, 1_000_000);
+
+        // Outputs in reverse order, with a change output in between
+        plan.reverse();
+        plan.insert(1, (Payee::from("mipcBbFg9gMiCh81Kj8tqqdgoZub1ZJRfn"), 1_000));
+        let tx = distribution_tx(&app, &inheritance, 1_000_000, &plan);
+        assert!(verify_distribution_completeness(&app, &inheritance, &tx, &DistributionWitness::default()));
+
+        // One share split across two outputs to the same address, in place of the change
+        plan.remove(1);
+        let (address, amount) = plan.remove(0);
+        plan.push((address.clone(), amount - 100_000));
+        plan.push((address.clone(), 100_000));
+        let tx = distribution_tx(&app, &inheritance, 1_000_000, &plan);
+        assert!(verify_distribution_completeness(&app, &inheritance, &tx, &DistributionWitness::default()));
+
+        // ...but the split outputs still have to add up
+        plan.pop();
+        plan.push((address, 99_999));
+        let tx = distribution_tx(&app, &inheritance, 1_000_000, &plan);
+        assert!(!verify_distribution_completeness(&app, &inheritance, &tx, &DistributionWitness::default()));
+    }
+
+    #[test]
+    fn test_waterfall_mixed_order() {
+        // Fixed entries are paid first wherever they sit in the list
+        let allocations = [
+            Allocation::Share(7500),
+            Allocation::Fixed(10_000),
+            Allocation::Share(2500),
+            Allocation::Fixed(20_000),
+        ];
+        assert_eq!(waterfall(130_000, &allocations, RoundingMode::FloorFirst), vec![75_000, 10_000, 25_000, 20_000]);
+
+        // Short vault: fixed entries drain in list order and the shares get nothing
+        assert_eq!(waterfall(15_000, &allocations, RoundingMode::FloorFirst), vec![0, 10_000, 0, 5_000]);
+
+        // With no shares, any surplus goes to the first entry
+        assert_eq!(waterfall(40_000, &allocations[1..2], RoundingMode::FloorFirst), vec![40_000]);
+    }
+
+    #[test]
+    fn test_mixed_allocations_require_a_share_for_the_remainder() {
+        let mut beneficiaries = fixed_and_share_inheritance().beneficiaries;
+        beneficiaries[0].allocation = Allocation::Fixed(200_000);
+        beneficiaries[1].allocation = Allocation::Fixed(300_000);
+
+        // Fixed amounts leave 400_000 over and no share would take it
+        assert!(!validate_beneficiaries(&beneficiaries, 1_000_000, &RuleSet::default()));
+
+        // One share entry takes the whole remainder
+        beneficiaries[2].allocation = Allocation::Share(FULL_SHARE);
+        assert!(validate_beneficiaries(&beneficiaries, 1_000_000, &RuleSet::default()));
+    }
+
+    #[test]
+    fn test_waterfall_sums_to_total() {
+        // Deterministic pseudo-random allocation lists (xorshift), checked over many vault sizes
+        let mut state: u64 = 0x9e37_79b9_7f4a_7c15;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+
+        for _ in 0..500 {
+            let n = (next() % 6 + 1) as usize;
+            let mut allocations: Vec<Allocation> = (0..n)
+                .map(|_| {
+                    if next() % 2 == 0 {
+                        Allocation::Fixed(next() % 2_000_000)
+                    } else {
+                        Allocation::Share(0)
+                    }
+                })
+                .collect();
+
+            // Give the share entries random weights summing to FULL_SHARE
+            let share_count = allocations.iter().filter(|a| a.share_bps().is_some()).count() as u16;
+            let mut left = FULL_SHARE;
+            let mut seen = 0;
+            for allocation in allocations.iter_mut() {
+                if let Allocation::Share(share_bps) = allocation {
+                    seen += 1;
+                    *share_bps = if seen == share_count { left } else { (next() % (left as u64 + 1)) as u16 };
+                    left -= *share_bps;
+                }
+            }
+
+            let total_sats = next() % 10_000_000;
+            for rounding in [RoundingMode::FloorFirst, RoundingMode::LargestRemainder] {
+                let amounts = waterfall(total_sats, &allocations, rounding);
+                assert_eq!(amounts.len(), allocations.len());
+                assert_eq!(amounts.iter().sum::<u64>(), total_sats, "{:?} over {}", allocations, total_sats);
+            }
+        }
+    }
+
+    #[test]
+    fn test_trigger_delay_against_network() {
+        use validation_rules::{DelayValidationError, MAINNET_MAX, MAINNET_MIN, TESTNET_MAX, TESTNET_MIN};
+
+        assert_eq!(validate_trigger_delay_against_network(4320, Network::Mainnet), Ok(()));
+        assert_eq!(validate_trigger_delay_against_network(TESTNET_MIN, Network::Testnet), Ok(()));
+
+        assert_eq!(
+            validate_trigger_delay_against_network(MAINNET_MIN - 1, Network::Mainnet),
+            Err(DelayValidationError::TooShort { minimum: MAINNET_MIN, provided: MAINNET_MIN - 1 })
+        );
+        assert_eq!(
+            validate_trigger_delay_against_network(MAINNET_MAX + 1, Network::Mainnet),
+            Err(DelayValidationError::TooLong { maximum: MAINNET_MAX, provided: MAINNET_MAX + 1 })
+        );
+        assert_eq!(
+            validate_trigger_delay_against_network(TESTNET_MIN - 1, Network::Testnet),
+            Err(DelayValidationError::TooShort { minimum: TESTNET_MIN, provided: TESTNET_MIN - 1 })
+        );
+        assert_eq!(
+            validate_trigger_delay_against_network(TESTNET_MAX + 1, Network::Signet),
+            Err(DelayValidationError::TooLong { maximum: TESTNET_MAX, provided: TESTNET_MAX + 1 })
+        );
+    }
+
+    #[test]
+    fn test_validate_inheritance_uses_network_bounds() {
+        // 100 blocks is fine on testnet but too short for mainnet
+        let mut inheritance = test_inheritance();
+        inheritance.trigger_delay_blocks = 100;
+        assert!(validate_inheritance(&inheritance));
+
+        inheritance.network = Network::Mainnet;
+        assert!(!validate_inheritance(&inheritance));
+    }
+
+    // BIP-32 test vector 2: the master xpub and its first non-hardened child
+    const VECTOR_XPUB: &str = "xpub661MyMwAqRbcFW31YEwpkMuc5THy2PSt5bDMsktWQcFF8syAmRUapSCGu8ED9W6oDMSgv6Zz8idoc4a6mr8BDzTJY47LJhkJ8UB7WEGuduB";
+    const VECTOR_XPUB_0: &str = "xpub69H7F5d8KSRgmmdJg2KhpAK8SR3DjMwAdkxj3ZuxV27CprR9LgpeyGmXUbC6wb7ERfvrnKZjXoUmmDznezpbZb7ap6r1D3tgFxHmwMkQTPH";
+
+    #[test]
+    fn test_bip32_derive_child_vector() {
+        let master = ExtendedPubKey::from_base58(VECTOR_XPUB).unwrap();
+        let expected = ExtendedPubKey::from_base58(VECTOR_XPUB_0).unwrap();
+        assert_eq!(master.derive_child(0), Some(expected));
+
+        assert!(master.derive_child(HARDENED_INDEX).is_none());
+        assert!(ExtendedPubKey::from_base58("tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx").is_none());
+    }
+
+    // test_inheritance with the second beneficiary paid through an xpub
+    fn xpub_inheritance() -> InheritanceContent {
+        let mut inheritance = test_inheritance();
+        inheritance.beneficiaries[1].payee = Payee::Xpub(XpubPayee {
+            xpub: VECTOR_XPUB.to_string(),
+            first_index: 0,
+            last_index: 9,
+        });
+        inheritance
+    }
+
+    fn xpub_distribution_tx(app: &App, inheritance: &InheritanceContent, child_index: u32) -> Transaction {
+        let child = ExtendedPubKey::from_base58(VECTOR_XPUB).unwrap().derive_child(child_index).unwrap();
+        let plan = compute_distribution_plan(inheritance, 1_000_000);
+
+        let mut tx = distribution_tx(app, inheritance, 1_000_000, &plan[..1]);
+        tx.outs.push(Charms::new());
+        tx.coin_outs.as_mut().unwrap().push(NativeOutput {
+            amount: plan[1].1,
+            dest: p2wpkh_script(&child.public_key),
+        });
+        tx
+    }
+
+    fn xpub_witness(child_index: u32) -> Data {
+        let child = ExtendedPubKey::from_base58(VECTOR_XPUB).unwrap().derive_child(child_index).unwrap();
+        Data::from(&DistributionWitness {
+            derived: vec![DerivedPayment {
+                beneficiary_index: 1,
+                child_index,
+                script_pubkey: p2wpkh_script(&child.public_key),
+            }],
+            current_block: Some(CURRENT_BLOCK),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_xpub_beneficiary_distribution() {
+        let app = test_app();
+        let inheritance = xpub_inheritance();
+        assert!(validate_inheritance(&inheritance));
+
+        let tx = xpub_distribution_tx(&app, &inheritance, 3);
+        assert!(can_trigger_distribution(&app, &tx, &xpub_witness(3)));
+
+        // Without the witness the contract can't tell where the heir is paid
+        assert!(!can_trigger_distribution(&app, &tx, &at_block(CURRENT_BLOCK)));
+    }
+
+    #[test]
+    fn test_xpub_beneficiary_wrong_derived_key() {
+        let app = test_app();
+        let inheritance = xpub_inheritance();
+
+        // The witness claims index 3 but the script is for index 4
+        let mut witness: DistributionWitness = xpub_witness(3).value().unwrap();
+        let child = ExtendedPubKey::from_base58(VECTOR_XPUB).unwrap().derive_child(4).unwrap();
+        witness.derived[0].script_pubkey = p2wpkh_script(&child.public_key);
+
+        let tx = xpub_distribution_tx(&app, &inheritance, 4);
+        assert!(!can_trigger_distribution(&app, &tx, &Data::from(&witness)));
+    }
+
+    #[test]
+    fn test_xpub_beneficiary_index_out_of_range() {
+        let app = test_app();
+        let inheritance = xpub_inheritance();
+
+        let tx = xpub_distribution_tx(&app, &inheritance, 10);
+        assert!(!can_trigger_distribution(&app, &tx, &xpub_witness(10)));
+
+        // A hardened range can never be derived from the xpub
+        let mut inheritance = xpub_inheritance();
+        if let Payee::Xpub(xpub) = &mut inheritance.beneficiaries[1].payee {
+            xpub.last_index = HARDENED_INDEX;
+        }
+        assert!(!validate_inheritance(&inheritance));
+    }
+
+    #[test]
+    fn test_checkin_reminder_urgency() {
+        // Delay 4320 from block 100: the deadline is block 4420
+        let inheritance = test_inheritance();
+        let at_percent_left = |percent: u64| 4420 - 4320 * percent / 100;
+
+        assert_eq!(generate_checkin_reminder(&inheritance, at_percent_left(80)), None);
+
+        let cases = [
+            (75, ReminderUrgency::Routine),
+            (50, ReminderUrgency::Routine),
+            (40, ReminderUrgency::Soon),
+            (20, ReminderUrgency::Urgent),
+            (5, ReminderUrgency::Critical),
+        ];
+        for (percent, urgency) in cases {
+            let current_block = at_percent_left(percent);
+            let reminder = generate_checkin_reminder(&inheritance, current_block).unwrap();
+            assert_eq!(reminder.urgency, urgency, "{percent}% left");
+            assert_eq!(reminder.blocks_until_deadline, 4420 - current_block);
+            assert!(reminder.suggested_checkin_block >= current_block);
+            assert!(reminder.suggested_checkin_block < 4420);
+        }
+
+        // Past the deadline is critical too
+        let reminder = generate_checkin_reminder(&inheritance, 5000).unwrap();
+        assert_eq!(reminder.urgency, ReminderUrgency::Critical);
+        assert_eq!(reminder.blocks_until_deadline, 0);
+    }
+
+    #[test]
+    fn test_simulate_inheritance_timeline() {
+        // Delay 1000 from block 100: the deadline is block 1100
+        let mut inheritance = test_inheritance();
+        inheritance.trigger_delay_blocks = 1000;
+
+        let timeline = simulate_inheritance_timeline(&inheritance, 0, 1500, 250);
+        let first_block = |status| timeline.iter().find(|(_, report)| report.status == status).map(|(block, _)| *block);
+        assert_eq!(first_block(LivenessStatus::Safe), Some(0));
+        assert_eq!(first_block(LivenessStatus::Warning), Some(600));
+        assert_eq!(first_block(LivenessStatus::Critical), Some(1001));
+        assert_eq!(first_block(LivenessStatus::Expired), Some(1101));
+
+        // The transitions fall between the samples, which are all still there
+        let blocks: Vec<u64> = timeline.iter().map(|(block, _)| *block).collect();
+        assert_eq!(blocks, vec![0, 250, 500, 600, 750, 1000, 1001, 1101, 1250, 1500]);
+        for (block, report) in &timeline {
+            assert_eq!(*report, check_contract_liveness(&inheritance, *block));
+        }
+        assert_eq!(check_contract_liveness(&inheritance, 599).status, LivenessStatus::Safe);
+        assert_eq!(check_contract_liveness(&inheritance, 1000).status, LivenessStatus::Warning);
+        assert_eq!(check_contract_liveness(&inheritance, 1100).status, LivenessStatus::Critical);
+
+        // A range that runs backwards is empty, and a step of 0 samples every block
+        assert!(simulate_inheritance_timeline(&inheritance, 1500, 0, 250).is_empty());
+        assert_eq!(simulate_inheritance_timeline(&inheritance, 10, 12, 0).len(), 3);
+    }
+
+    // Pays the first test beneficiary and leaves the NFT holding the second
+    fn partial_distribution(app: &App) -> (InheritanceContent, InheritanceContent, Transaction) {
+        partial_distribution_of(app, test_inheritance(), Some(CURRENT_BLOCK))
+    }
+
+    fn partial_distribution_of(
+        app: &App,
+        inheritance: InheritanceContent,
+        trigger_height: Option<u64>,
+    ) -> (InheritanceContent, InheritanceContent, Transaction) {
+        let plan = compute_distribution_plan(&inheritance, 1_000_000);
+
+        let mut remaining = inheritance.clone();
+        remaining.status = InheritanceStatus::Triggered;
+        remaining.beneficiaries = vec![Beneficiary {
+            payee: plan[1].0.clone(),
+            allocation: Allocation::Fixed(plan[1].1),
+            vesting_delay_blocks: inheritance.beneficiaries[1].vesting_delay_blocks,
+        }];
+        remaining.total_locked_sats = plan[1].1;
+        remaining.trigger_height = trigger_height;
+
+        let mut tx = distribution_tx(app, &inheritance, 1_000_000, &plan[..1]);
+        tx.outs.push(nft_charms(app, &remaining));
+        tx.coin_outs.as_mut().unwrap().push(NativeOutput {
+            amount: plan[1].1,
+            dest: vec![0x51, 0x20, 0xbb],
+        });
+        (inheritance, remaining, tx)
+    }
+
+    #[test]
+    fn test_partial_then_final_distribution() {
+        let app = test_app();
+        let (_, remaining, tx) = partial_distribution(&app);
+        assert!(can_trigger_distribution(&app, &tx, &at_block(CURRENT_BLOCK)));
+
+        // The final transaction pays the rest and burns the NFT
+        let plan = compute_distribution_plan(&remaining, 400_000);
+        assert_eq!(plan[0].1, 400_000);
+        let tx = distribution_tx(&app, &remaining, 400_000, &plan);
+        assert!(can_trigger_distribution(&app, &tx, &at_block(CURRENT_BLOCK)));
+    }
+
+    #[test]
+    fn test_partial_distribution_rejects_bad_remainder() {
+        let app = test_app();
+
+        // Carried beneficiary shortchanged
+        let (inheritance, mut remaining, _) = partial_distribution(&app);
+        remaining.beneficiaries[0].allocation = Allocation::Fixed(300_000);
+        remaining.total_locked_sats = 300_000;
+        let plan = compute_distribution_plan(&inheritance, 1_000_000);
+        let mut tx = distribution_tx(&app, &inheritance, 1_000_000, &plan[..1]);
+        tx.outs.push(nft_charms(&app, &remaining));
+        tx.coin_outs.as_mut().unwrap().push(NativeOutput {
+            amount: 300_000,
+            dest: vec![0x51, 0x20, 0xbb],
+        });
+        assert!(!can_trigger_distribution(&app, &tx, &at_block(CURRENT_BLOCK)));
+
+        // Surviving NFT must be Triggered
+        let (_, mut remaining, mut tx) = partial_distribution(&app);
+        remaining.status = InheritanceStatus::Active;
+        tx.outs[1] = nft_charms(&app, &remaining);
+        assert!(!can_trigger_distribution(&app, &tx, &at_block(CURRENT_BLOCK)));
+
+        // The NFT's output must actually hold the remainder
+        let (_, _, mut tx) = partial_distribution(&app);
+        tx.coin_outs.as_mut().unwrap()[1].amount -= 1;
+        assert!(!can_trigger_distribution(&app, &tx, &at_block(CURRENT_BLOCK)));
+
+        // The paid beneficiary must really be paid
+        let (_, _, mut tx) = partial_distribution(&app);
+        tx.coin_outs.as_mut().unwrap()[0].amount -= 1;
+        assert!(!can_trigger_distribution(&app, &tx, &at_block(CURRENT_BLOCK)));
+    }
+
+    #[test]
+    fn test_script_beneficiary_distribution() {
+        let app = test_app();
+
+        // A 2-of-3 multisig heir, paid to a P2WSH script
+        let mut p2wsh = vec![0x00, 0x20];
+        p2wsh.extend([0x42; 32]);
+        let mut inheritance = test_inheritance();
+        inheritance.beneficiaries[1].payee = Payee::Script(ScriptPayee { script_pubkey: p2wsh });
+        assert!(validate_inheritance(&inheritance));
+
+        let mut plan = compute_distribution_plan(&inheritance, 1_000_000);
+        let tx = distribution_tx(&app, &inheritance, 1_000_000, &plan);
+        assert!(can_trigger_distribution(&app, &tx, &at_block(CURRENT_BLOCK)));
+
+        plan[1].1 -= 1;
+        let tx = distribution_tx(&app, &inheritance, 1_000_000, &plan);
+        assert!(!can_trigger_distribution(&app, &tx, &at_block(CURRENT_BLOCK)));
+    }
+
+    #[test]
+    fn test_script_beneficiary_bounds() {
+        let mut inheritance = test_inheritance();
+        inheritance.beneficiaries[1].payee = Payee::Script(ScriptPayee { script_pubkey: vec![] });
+        assert!(!validate_inheritance(&inheritance));
+
+        let script = vec![0x51; MAX_SCRIPT_LEN + 1];
+        assert!(!validate_payee(&Payee::Script(ScriptPayee { script_pubkey: script })));
+        let script = vec![0x51; MAX_SCRIPT_LEN];
+        assert!(validate_payee(&Payee::Script(ScriptPayee { script_pubkey: script })));
+    }
+
+    #[test]
+    fn test_find_inheritance_utxos() {
+        let app = test_app();
+        let other_vault = App {
+            identity: B32([9; 32]),
+            ..app.clone()
+        };
+        let other_app = App {
+            vk: B32([8; 32]),
+            ..app.clone()
+        };
+
+        let mut distributed = test_inheritance();
+        distributed.status = InheritanceStatus::Distributed;
+        let mut triggered = test_inheritance();
+        triggered.status = InheritanceStatus::Triggered;
+
+        let txs = vec![
+            (
+                TxId([1; 32]),
+                test_tx(
+                    vec![],
+                    vec![
+                        nft_charms(&app, &test_inheritance()),
+                        Charms::new(),
+                        nft_charms(&other_app, &test_inheritance()),
+                    ],
+                ),
+            ),
+            (
+                TxId([2; 32]),
+                test_tx(
+                    vec![],
+                    vec![
+                        Charms::from([(app.clone(), Data::from(&"not an inheritance"))]),
+                        nft_charms(&other_vault, &triggered),
+                        nft_charms(&app, &distributed),
+                    ],
+                ),
+            ),
+        ];
+
+        let found = find_inheritance_utxos(&txs, &app);
+        assert_eq!(
+            found,
+            vec![
+                (UtxoId(TxId([1; 32]), 0), test_inheritance()),
+                (UtxoId(TxId([2; 32]), 1), triggered),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_is_distributable_by_status() {
+        // Deadline is block 4420
+        let mut inheritance = test_inheritance();
+        let heights = [0, 4419, 4420, 4421, u64::MAX];
+
+        let expected = [
+            (InheritanceStatus::Active, [false, false, false, true, true]),
+            (InheritanceStatus::Triggered, [true; 5]),
+            (InheritanceStatus::Distributed, [false; 5]),
+        ];
+        for (status, distributable) in expected {
+            inheritance.status = status.clone();
+            for (height, expected) in heights.iter().zip(distributable) {
+                assert_eq!(is_distributable(&inheritance, *height), expected, "{status:?} at {height}");
+                assert_eq!(summarize(&inheritance, *height).is_triggerable, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_grace_period_delays_distribution() {
+        let app = test_app();
+        // Deadline is block 4420, and the grace period runs to 5420
+        let mut inheritance = test_inheritance();
+        inheritance.grace_period = Some(GracePeriod {
+            extra_blocks: 1000,
+            requires_medical_attestation: true,
+        });
+        assert_eq!(inheritance.validate(), Ok(()));
+        assert!(is_expired(&inheritance, 4421));
+
+        let plan = compute_distribution_plan(&inheritance, 1_000_000);
+        let tx = distribution_tx(&app, &inheritance, 1_000_000, &plan);
+        assert!(!can_trigger_distribution(&app, &tx, &at_block(4421)));
+        assert!(!can_trigger_distribution(&app, &tx, &at_block(5420)));
+        assert!(can_trigger_distribution(&app, &tx, &at_block(5421)));
+        assert!(!summarize(&inheritance, 5420).is_triggerable);
+
+        // At most a quarter of the delay
+        inheritance.grace_period = Some(GracePeriod {
+            extra_blocks: 4320 / 4 + 1,
+            requires_medical_attestation: false,
+        });
+        assert_eq!(inheritance.validate(), Err(ValidationError::InvalidGracePeriod));
+    }
+
+    // Records one more miss on `input`, as proven at `current_block`
+    fn record_miss(app: &App, input: &InheritanceContent, current_block: u64) -> (bool, InheritanceContent) {
+        let mut output = input.clone();
+        output.missed_count += 1;
+        let tx = test_tx(vec![nft_charms(app, input)], vec![nft_charms(app, &output)]);
+        let w = operation_witness(Operation::RecordMiss, &Data::empty());
+        (app_contract(app, &tx, &block_height_input(current_block), &w), output)
+    }
+
+    #[test]
+    fn test_record_misses_before_trigger() {
+        let app = test_app();
+        // Deadlines lapse after blocks 4420, 8740 and 13060
+        let mut inheritance = test_inheritance();
+        inheritance.allowed_missed_checkins = 3;
+        assert_eq!(inheritance.validate(), Ok(()));
+        assert!(!is_distributable(&inheritance, 4421));
+
+        assert!(!record_miss(&app, &inheritance, 4420).0);
+        let (recorded, once) = record_miss(&app, &inheritance, 4421);
+        assert!(recorded);
+
+        // The same lapsed period can't be counted twice
+        assert!(!record_miss(&app, &once, 4421).0);
+        assert!(!record_miss(&app, &once, 8740).0);
+        let (recorded, twice) = record_miss(&app, &once, 8741);
+        assert!(recorded);
+
+        // The height must be attested
+        let mut thrice = twice.clone();
+        thrice.missed_count += 1;
+        let tx = test_tx(vec![nft_charms(&app, &twice)], vec![nft_charms(&app, &thrice)]);
+        assert!(!can_record_miss(&app, &tx, None));
+        assert!(can_record_miss(&app, &tx, Some(13_061)));
+
+        // Only once every allowed miss is recorded can the vault be distributed
+        assert!(!is_distributable(&twice, 13_061));
+        assert!(is_distributable(&thrice, 13_061));
+        let plan = compute_distribution_plan(&thrice, 1_000_000);
+        let distribution = distribution_tx(&app, &thrice, 1_000_000, &plan);
+        assert!(can_trigger_distribution(&app, &distribution, &at_block(13_061)));
+
+        // No more misses than that
+        assert!(!record_miss(&app, &thrice, 20_000).0);
+
+        // Nor any for a contract that doesn't count them
+        assert!(!record_miss(&app, &test_inheritance(), 20_000).0);
+    }
+
+    #[test]
+    fn test_checkin_resets_missed_count() {
+        let app = test_app();
+        let mut input = test_inheritance();
+        input.allowed_missed_checkins = 3;
+        input.missed_count = 2;
+        let mut output = input.clone();
+        output.last_checkin_block = CURRENT_BLOCK;
+
+        // The count must go back to zero
+        let tx = test_tx(vec![nft_charms(&app, &input)], vec![nft_charms(&app, &output)]);
+        assert!(!can_checkin(&app, &tx, &checkin_witness(&tx, CURRENT_BLOCK)));
+        output.missed_count = 0;
+        let tx = test_tx(vec![nft_charms(&app, &input)], vec![nft_charms(&app, &output)]);
+        assert!(can_checkin(&app, &tx, &checkin_witness(&tx, CURRENT_BLOCK)));
+
+        // An update proves the owner alive too
+        assert!(can_update_beneficiaries(&app, &tx, &update_witness(&tx)));
+
+        // After the reset, the next miss is for the first deadline after the check-in
+        assert!(!record_miss(&app, &output, CURRENT_BLOCK + 4320).0);
+        assert!(record_miss(&app, &output, CURRENT_BLOCK + 4321).0);
+
+        // New content can't start with misses
+        assert_eq!(input.validate(), Err(ValidationError::MissesRecorded));
+    }
+
+    #[test]
+    fn test_checkin_window() {
+        let app = test_app();
+        // Deadline 4420, so the window opens at block 3920
+        let mut input = test_inheritance();
+        input.checkin_window_blocks = 500;
+        assert_eq!(input.validate(), Ok(()));
+        assert_eq!(checkin_window_opens(&input, 3000), 3920);
+
+        let checkin_at = |input: &InheritanceContent, block: u64| {
+            let mut output = input.clone();
+            output.last_checkin_block = block;
+            let tx = test_tx(vec![nft_charms(&app, input)], vec![nft_charms(&app, &output)]);
+            let w = operation_witness(Operation::Checkin, &checkin_witness(&tx, block));
+            evaluate(&app, &tx, &block_height_input(block), &w)
+        };
+
+        // Too early, then inside the window, up to the deadline itself
+        assert_eq!(checkin_at(&input, 1000), Err(ContractError::Checkin(RuleFailed::CheckinTooEarly)));
+        assert_eq!(checkin_at(&input, 3919), Err(ContractError::Checkin(RuleFailed::CheckinTooEarly)));
+        assert_eq!(checkin_at(&input, 3920), Ok(Operation::Checkin));
+        assert_eq!(checkin_at(&input, 4420), Ok(Operation::Checkin));
+
+        // An update that moves the check-in waits for the window too; one that doesn't, needn't
+        let update_at = |input: &InheritanceContent, last_checkin_block: u64, block: u64| {
+            let mut output = input.clone();
+            output.last_checkin_block = last_checkin_block;
+            output.beneficiaries.reverse();
+            let tx = test_tx(vec![nft_charms(&app, input)], vec![nft_charms(&app, &output)]);
+            let w = operation_witness(Operation::UpdateBeneficiaries, &update_witness(&tx));
+            evaluate(&app, &tx, &block_height_input(block), &w)
+        };
+        assert_eq!(
+            update_at(&input, 1000, 1000),
+            Err(ContractError::UpdateBeneficiaries(RuleFailed::CheckinTooEarly))
+        );
+        assert_eq!(
+            update_at(&input, 3919, 3919),
+            Err(ContractError::UpdateBeneficiaries(RuleFailed::CheckinTooEarly))
+        );
+        assert_eq!(update_at(&input, 3920, 3920), Ok(Operation::UpdateBeneficiaries));
+        assert_eq!(update_at(&input, 100, 1000), Ok(Operation::UpdateBeneficiaries));
+
+        // Without a window, any check-in after the last one counts
+        input.checkin_window_blocks = 0;
+        assert_eq!(checkin_at(&input, 1000), Ok(Operation::Checkin));
+        assert_eq!(update_at(&input, 1000, 1000), Ok(Operation::UpdateBeneficiaries));
+
+        // The window can't be longer than the delay
+        input.checkin_window_blocks = input.trigger_delay_blocks + 1;
+        assert_eq!(input.validate(), Err(ValidationError::InvalidCheckinWindow));
+    }
+
+    // A contract under the court of guardian 0
+    fn court_inheritance() -> InheritanceContent {
+        let mut inheritance = test_inheritance();
+        inheritance.court_pubkey = Some(test_signer_key(2));
+        inheritance
+    }
+
+    // `input` frozen at CURRENT_BLOCK, with the court's (guardian `signer`'s) signature
+    fn court_freeze(app: &App, input: &InheritanceContent, signer: usize) -> (Transaction, Data) {
+        let frozen = apply_court_freeze(input, hash("court order 42"), CURRENT_BLOCK);
+        let tx = test_tx(vec![nft_charms(app, input)], vec![Charms::from([(app.clone(), Data::from(&frozen))])]);
+        let message = court_order_message(Operation::CourtFreeze, &tx, &frozen);
+        let w = Data::from(&CourtFreezeWitness { court_freeze_sig: guardian_sign(signer, &message).1 });
+        (tx, w)
+    }
+
+    #[test]
+    fn test_court_freeze_and_unfreeze() {
+        let app = test_app();
+        let input = court_inheritance();
+        assert_eq!(input.validate(), Ok(()));
+        let x = block_height_input(CURRENT_BLOCK);
+
+        // Only the court can freeze, and only at an attested height
+        let (tx, w) = court_freeze(&app, &input, 1);
+        assert!(!app_contract(&app, &tx, &x, &operation_witness(Operation::CourtFreeze, &w)));
+        let (tx, w) = court_freeze(&app, &input, 0);
+        assert!(!app_contract(&app, &tx, &Data::empty(), &operation_witness(Operation::CourtFreeze, &w)));
+        assert!(app_contract(&app, &tx, &x, &operation_witness(Operation::CourtFreeze, &w)));
+
+        // A contract without a court can't be frozen
+        let (tx, w) = court_freeze(&app, &test_inheritance(), 0);
+        assert!(!app_contract(&app, &tx, &x, &operation_witness(Operation::CourtFreeze, &w)));
+
+        // Lifting the freeze restores the contract as it was, on the court's word
+        let frozen = apply_court_freeze(&input, hash("court order 42"), CURRENT_BLOCK);
+        let frozen_charms = Charms::from([(app.clone(), Data::from(&frozen))]);
+        let unfreeze = |output: &InheritanceContent, signer: usize| {
+            let tx = test_tx(vec![frozen_charms.clone()], vec![nft_charms(&app, output)]);
+            let message = court_order_message(Operation::CourtUnfreeze, &tx, &frozen);
+            let w = Data::from(&CourtUnfreezeWitness { court_unfreeze_sig: guardian_sign(signer, &message).1 });
+            app_contract(&app, &tx, &x, &operation_witness(Operation::CourtUnfreeze, &w))
+        };
+        assert!(unfreeze(&input, 0));
+        assert!(!unfreeze(&input, 1));
+        let mut altered = input.clone();
+        altered.last_checkin_block = CURRENT_BLOCK;
+        assert!(!unfreeze(&altered, 0));
+
+        // The owner can't drop the court to escape a freeze
+        let mut no_court = input.clone();
+        no_court.court_pubkey = None;
+        let tx = test_tx(vec![nft_charms(&app, &input)], vec![nft_charms(&app, &no_court)]);
+        assert!(!can_update_beneficiaries(&app, &tx, &update_witness(&tx)));
+
+        // The court's key must be usable
+        let mut bad_court = input.clone();
+        bad_court.court_pubkey = Some(OwnerPubkey([0xff; 33]));
+        assert_eq!(bad_court.validate(), Err(ValidationError::InvalidCourtPubkey));
+    }
+
+    #[test]
+    fn test_frozen_contract_blocks_every_operation() {
+        let app = test_app();
+        let input = court_inheritance();
+        let frozen = apply_court_freeze(&input, hash("court order 42"), CURRENT_BLOCK);
+        let frozen_charms = Charms::from([(app.clone(), Data::from(&frozen))]);
+        let x = block_height_input(CURRENT_BLOCK);
+
+        // Owner check-in
+        let mut checked_in = input.clone();
+        checked_in.last_checkin_block = CURRENT_BLOCK;
+        let tx = test_tx(vec![frozen_charms.clone()], vec![nft_charms(&app, &checked_in)]);
+        let w = checkin_witness(&tx, CURRENT_BLOCK);
+        assert!(!app_contract(&app, &tx, &x, &operation_witness(Operation::Checkin, &w)));
+
+        // Beneficiary update
+        let w = update_witness(&tx);
+        assert!(!app_contract(&app, &tx, &x, &operation_witness(Operation::UpdateBeneficiaries, &w)));
+
+        // Distribution, even long past the deadline
+        let plan = compute_distribution_plan(&input, 1_000_000);
+        let mut tx = distribution_tx(&app, &input, 1_000_000, &plan);
+        tx.ins[0].1 = frozen_charms.clone();
+        let late = block_height_input(CURRENT_BLOCK + 100_000);
+        let w = at_block(CURRENT_BLOCK + 100_000);
+        assert!(!app_contract(&app, &tx, &late, &operation_witness(Operation::TriggerDistribution, &w)));
+
+        // Missed check-ins, migration and a second freeze
+        let mut missed = input.clone();
+        missed.missed_count = 1;
+        let tx = test_tx(vec![frozen_charms.clone()], vec![nft_charms(&app, &missed)]);
+        assert!(!app_contract(&app, &tx, &late, &operation_witness(Operation::RecordMiss, &Data::empty())));
+        assert!(!app_contract(&app, &tx, &x, &operation_witness(Operation::Migrate, &Data::empty())));
+        let refrozen = apply_court_freeze(&input, hash("court order 43"), CURRENT_BLOCK);
+        let tx = test_tx(vec![frozen_charms], vec![Charms::from([(app.clone(), Data::from(&refrozen))])]);
+        let message = court_order_message(Operation::CourtFreeze, &tx, &refrozen);
+        let w = Data::from(&CourtFreezeWitness { court_freeze_sig: guardian_sign(0, &message).1 });
+        assert!(!app_contract(&app, &tx, &x, &operation_witness(Operation::CourtFreeze, &w)));
+    }
+
+    const PRIVATE_ADDRESS: &str = "tb1pqqqqp399et2xygdj5xreqhjjvcmzhxw4aywxecjdzew6hylgvsesf3hn0c";
+    const FALLBACK_ADDRESS: &str = "mipcBbFg9gMiCh81Kj8tqqdgoZub1ZJRfn";
+
+    // test_inheritance with the second beneficiary's address hidden behind a commitment
+    fn committed_inheritance() -> InheritanceContent {
+        let mut inheritance = test_inheritance();
+        inheritance.beneficiaries[1].payee = Payee::Committed(CommittedPayee {
+            commitment: commit_payee(PRIVATE_ADDRESS, &B32([5; 32])),
+            fallback_address: FALLBACK_ADDRESS.to_string(),
+        });
+        inheritance
+    }
+
+    fn reveal_witness(salt: B32) -> Data {
+        Data::from(&DistributionWitness {
+            revealed: vec![RevealedPayee {
+                beneficiary_index: 1,
+                address: PRIVATE_ADDRESS.to_string(),
+                salt,
+            }],
+            current_block: Some(CURRENT_BLOCK),
+            ..Default::default()
+        })
+    }
+
+    fn committed_distribution_tx(app: &App, inheritance: &InheritanceContent, second: &str) -> Transaction {
+        let mut plan = compute_distribution_plan(inheritance, 1_000_000);
+        plan[1].0 = Payee::from(second);
+        distribution_tx(app, inheritance, 1_000_000, &plan)
+    }
+
+    #[test]
+    fn test_committed_beneficiary_revealed() {
+        let app = test_app();
+        let inheritance = committed_inheritance();
+        assert!(validate_inheritance(&inheritance));
+
+        let tx = committed_distribution_tx(&app, &inheritance, PRIVATE_ADDRESS);
+        assert!(can_trigger_distribution(&app, &tx, &reveal_witness(B32([5; 32]))));
+    }
+
+    #[test]
+    fn test_committed_beneficiary_wrong_salt() {
+        let app = test_app();
+        let inheritance = committed_inheritance();
+
+        let tx = committed_distribution_tx(&app, &inheritance, PRIVATE_ADDRESS);
+        assert!(!can_trigger_distribution(&app, &tx, &reveal_witness(B32([6; 32]))));
+    }
+
+    #[test]
+    fn test_committed_beneficiary_unrevealed_uses_fallback() {
+        let app = test_app();
+        let inheritance = committed_inheritance();
+
+        // No reveal: the share must go to the fallback address...
+        let tx = committed_distribution_tx(&app, &inheritance, FALLBACK_ADDRESS);
+        assert!(can_trigger_distribution(&app, &tx, &at_block(CURRENT_BLOCK)));
+
+        // ...and not the private address, which nobody proved
+        let tx = committed_distribution_tx(&app, &inheritance, PRIVATE_ADDRESS);
+        assert!(!can_trigger_distribution(&app, &tx, &at_block(CURRENT_BLOCK)));
+    }
+
+    fn at_block(current_block: u64) -> Data {
+        Data::from(&DistributionWitness {
+            current_block: Some(current_block),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_vesting_beneficiary_paid_after_delay() {
+        let app = test_app();
+        let mut inheritance = test_inheritance();
+        inheritance.beneficiaries[1].vesting_delay_blocks = 105_000;
+        assert!(validate_inheritance(&inheritance));
+
+        // Triggering pays the first beneficiary and records the height
+        let (_, remaining, tx) = partial_distribution_of(&app, inheritance, Some(5000));
+        assert!(can_trigger_distribution(&app, &tx, &at_block(5000)));
+        assert!(!can_trigger_distribution(&app, &tx, &at_block(5001)));
+        assert!(!can_trigger_distribution(&app, &tx, &no_derivations()));
+
+        // The vesting beneficiary can't claim early, or without saying when...
+        let plan = compute_distribution_plan(&remaining, 400_000);
+        let tx = distribution_tx(&app, &remaining, 400_000, &plan);
+        assert!(!can_trigger_distribution(&app, &tx, &at_block(109_999)));
+        assert!(!can_trigger_distribution(&app, &tx, &no_derivations()));
+
+        // ...but can once the delay has elapsed
+        assert!(can_trigger_distribution(&app, &tx, &at_block(110_000)));
+    }
+
+    #[test]
+    fn test_vesting_blocks_direct_final_distribution() {
+        let app = test_app();
+        let mut inheritance = test_inheritance();
+        inheritance.beneficiaries[1].vesting_delay_blocks = 1000;
+
+        // Untriggered, the vault has no trigger height to vest from
+        let plan = compute_distribution_plan(&inheritance, 1_000_000);
+        let tx = distribution_tx(&app, &inheritance, 1_000_000, &plan);
+        assert!(!can_trigger_distribution(&app, &tx, &at_block(1_000_000)));
+
+        // Non-vesting contracts are unaffected
+        let inheritance = test_inheritance();
+        let plan = compute_distribution_plan(&inheritance, 1_000_000);
+        let tx = distribution_tx(&app, &inheritance, 1_000_000, &plan);
+        assert!(can_trigger_distribution(&app, &tx, &at_block(CURRENT_BLOCK)));
+    }
+
+    #[test]
+    fn test_vesting_delay_carried_and_trigger_height_fixed() {
+        let app = test_app();
+        let mut inheritance = test_inheritance();
+        inheritance.beneficiaries[1].vesting_delay_blocks = 1000;
+
+        // Dropping the carried beneficiary's vesting delay is rejected
+        let (_, mut remaining, mut tx) = partial_distribution_of(&app, inheritance, Some(5000));
+        remaining.beneficiaries[0].vesting_delay_blocks = 0;
+        tx.outs[1] = nft_charms(&app, &remaining);
+        assert!(!can_trigger_distribution(&app, &tx, &at_block(5000)));
+
+        // A new contract can't claim to be triggered already
+        let mut inheritance = test_inheritance();
+        inheritance.trigger_height = Some(1);
+        assert!(!validate_inheritance(&inheritance));
+    }
+
+    #[test]
+    fn test_owner_beneficiary_policy() {
+        // test_inheritance's first beneficiary is the P2WPKH address of the owner's key
+        let mut inheritance = test_inheritance();
+        assert_eq!(owner_beneficiaries(&inheritance), vec![0]);
+        assert!(validate_inheritance(&inheritance));
+
+        inheritance.rule_set.reject_owner_beneficiary = true;
+        assert!(!validate_inheritance(&inheritance));
+
+        // The same key as a legacy address is caught too
+        inheritance.beneficiaries[0].payee = Payee::from("mrCDrCybB6J1vRfbwM5hemdJz73FwDBC8r");
+        assert!(!validate_inheritance(&inheritance));
+
+        inheritance.beneficiaries[0].payee = Payee::from("mipcBbFg9gMiCh81Kj8tqqdgoZub1ZJRfn");
+        assert!(owner_beneficiaries(&inheritance).is_empty());
+        assert!(validate_inheritance(&inheritance));
+    }
+
+    #[test]
+    fn test_owner_beneficiary_taproot() {
+        // BIP-86 test vector: internal key and its key-path address
+        let mut inheritance = test_inheritance();
+        inheritance.owner_pubkey = "cc8a4bc64d897bddc5fbc2f670f7a8ba0b386779106cf1223c6fc5d7cd6fc115".to_string();
+        inheritance.beneficiaries[1].payee = Payee::from("bc1p5cyxnuxmeuwuvkwfem96lqzszd02n6xdcjrs20cac6yqjjwudpxqkedrcr");
+        assert_eq!(owner_beneficiaries(&inheritance), vec![1]);
+    }
+
+    #[test]
+    fn test_owner_beneficiary_policy_on_update() {
+        let app = test_app();
+        let mut input = test_inheritance();
+        input.beneficiaries[0].payee = Payee::from("mipcBbFg9gMiCh81Kj8tqqdgoZub1ZJRfn");
+        input.rule_set.reject_owner_beneficiary = true;
+
+        // Updating the owner's own address back in is rejected under the policy...
+        let mut output = input.clone();
+        output.beneficiaries = test_inheritance().beneficiaries;
+        let tx = test_tx(vec![nft_charms(&app, &input)], vec![nft_charms(&app, &output)]);
+        assert!(!can_update_beneficiaries(&app, &tx, &update_witness(&tx)));
+
+        // ...and allowed without it
+        input.rule_set.reject_owner_beneficiary = false;
+        output.rule_set.reject_owner_beneficiary = false;
+        let tx = test_tx(vec![nft_charms(&app, &input)], vec![nft_charms(&app, &output)]);
+        assert!(can_update_beneficiaries(&app, &tx, &update_witness(&tx)));
+    }
+
+    #[test]
+    fn test_fallback_address_validation() {
+        let mut inheritance = test_inheritance();
+        assert_eq!(validate_fallback_address(&inheritance), Ok(()));
+
+        inheritance.fallback_address = Some("mipcBbFg9gMiCh81Kj8tqqdgoZub1ZJRfn".to_string());
+        assert_eq!(validate_fallback_address(&inheritance), Ok(()));
+        assert!(validate_inheritance(&inheritance));
+
+        inheritance.fallback_address = Some("tb1qnotanaddress".to_string());
+        assert_eq!(validate_fallback_address(&inheritance), Err(FallbackAddressError::Malformed));
+        assert!(!validate_inheritance(&inheritance));
+
+        inheritance.fallback_address = Some("1BgGZ9tcN4rm9KBzDn7KprQz87SZ26SAMH".to_string());
+        assert_eq!(validate_fallback_address(&inheritance), Err(FallbackAddressError::WrongNetwork));
+        assert!(!validate_inheritance(&inheritance));
+
+        inheritance.fallback_address = Some("tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx".to_string());
+        assert_eq!(validate_fallback_address(&inheritance), Err(FallbackAddressError::IsBeneficiary));
+        assert!(!validate_inheritance(&inheritance));
+    }
+
+    #[test]
+    fn test_fallback_address_network() {
+        assert!(address_on_network("tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx", Network::Testnet));
+        assert!(address_on_network("tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx", Network::Signet));
+        assert!(!address_on_network("tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx", Network::Mainnet));
+        assert!(!address_on_network("tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx", Network::Regtest));
+        assert!(address_on_network("1BgGZ9tcN4rm9KBzDn7KprQz87SZ26SAMH", Network::Mainnet));
+        assert!(address_on_network("mipcBbFg9gMiCh81Kj8tqqdgoZub1ZJRfn", Network::Regtest));
+    }
+
+    #[test]
+    fn test_fallback_address_changes_only_on_update() {
+        let app = test_app();
+        let input = test_inheritance();
+        let mut output = input.clone();
+        output.last_checkin_block += 10;
+        output.fallback_address = Some("mipcBbFg9gMiCh81Kj8tqqdgoZub1ZJRfn".to_string());
+
+        let tx = test_tx(vec![nft_charms(&app, &input)], vec![nft_charms(&app, &output)]);
+        assert!(can_update_beneficiaries(&app, &tx, &update_witness(&tx)));
+        assert!(!can_checkin(&app, &tx, &checkin_witness(&tx, CURRENT_BLOCK)));
+
+        output.fallback_address = Some("1BgGZ9tcN4rm9KBzDn7KprQz87SZ26SAMH".to_string());
+        let tx = test_tx(vec![nft_charms(&app, &input)], vec![nft_charms(&app, &output)]);
+        assert!(!can_update_beneficiaries(&app, &tx, &update_witness(&tx)));
+
+        // A partial distribution can't redirect the fallback either
+        let (_, mut remaining, mut tx) = partial_distribution(&app);
+        remaining.fallback_address = Some("mipcBbFg9gMiCh81Kj8tqqdgoZub1ZJRfn".to_string());
+        tx.outs[1] = nft_charms(&app, &remaining);
+        assert!(!can_trigger_distribution(&app, &tx, &at_block(CURRENT_BLOCK)));
+    }
+
+    #[test]
+    fn test_fallback_address_update_needs_owner_signature() {
+        let app = test_app();
+        let input = test_inheritance();
+        let mut output = input.clone();
+        output.fallback_address = Some("mipcBbFg9gMiCh81Kj8tqqdgoZub1ZJRfn".to_string());
+        let tx = test_tx(vec![nft_charms(&app, &input)], vec![nft_charms(&app, &output)]);
+        let signed_by = |signature: Vec<u8>| Data::from(&UpdateBeneficiariesWitness { signature });
+
+        // No witness
+        assert!(!can_update_beneficiaries(&app, &tx, &Data::empty()));
+
+        // The owner's signature over a check-in of the same transaction
+        let checkin = owner_sign(&signing_message(Operation::Checkin, &tx, &output));
+        assert!(!can_update_beneficiaries(&app, &tx, &signed_by(checkin)));
+
+        assert!(can_update_beneficiaries(&app, &tx, &update_witness(&tx)));
+    }
+
+    fn lightning_payee(offer: &str) -> Payee {
+        Payee::Lightning(LightningPayee {
+            offer: offer.to_string(),
+            settlement_script: vec![0x51, 0x20, 0xcc],
+        })
+    }
+
+    #[test]
+    fn test_lightning_beneficiary_validation() {
+        let mut inheritance = test_inheritance();
+        inheritance.beneficiaries[1].payee = lightning_payee("lno1zcss9mk8y3wkklfvevcrszlmu23kfrxh49px20665dqwmn4p72pksese");
+        assert!(validate_inheritance(&inheritance));
+
+        // Round-trips through the payload like any other payee
+        let decoded = migrate_to_latest(&Data::from(&inheritance)).unwrap();
+        assert_eq!(decoded.beneficiaries, inheritance.beneficiaries);
+
+        inheritance.beneficiaries[1].payee = lightning_payee("lno1zcss9mk8y3wkklfvevcrszlmu23kfrxh49px20665dqwmn4p72pks");
+        assert!(!validate_inheritance(&inheritance));
+
+        inheritance.beneficiaries[1].payee = Payee::Lightning(LightningPayee {
+            offer: "lno1zcss9mk8y3wkklfvevcrszlmu23kfrxh49px20665dqwmn4p72pksese".to_string(),
+            settlement_script: vec![],
+        });
+        assert!(!validate_inheritance(&inheritance));
+    }
+
+    #[test]
+    fn test_lightning_beneficiary_paid_to_settlement_script() {
+        let app = test_app();
+        let mut inheritance = test_inheritance();
+        inheritance.beneficiaries[1].payee = lightning_payee("lno1zcss9mk8y3wkklfvevcrszlmu23kfrxh49px20665dqwmn4p72pksese");
+
+        let mut plan = compute_distribution_plan(&inheritance, 1_000_000);
+        let tx = distribution_tx(&app, &inheritance, 1_000_000, &plan);
+        assert_eq!(tx.coin_outs.as_ref().unwrap()[1].dest, vec![0x51, 0x20, 0xcc]);
+        assert!(can_trigger_distribution(&app, &tx, &at_block(CURRENT_BLOCK)));
+
+        plan[1].1 -= 1;
+        let tx = distribution_tx(&app, &inheritance, 1_000_000, &plan);
+        assert!(!can_trigger_distribution(&app, &tx, &at_block(CURRENT_BLOCK)));
+    }
+
+    #[test]
+    fn test_created_at_block_recorded_at_creation() {
+        let mut inheritance = test_inheritance();
+        assert!(validate_inheritance(&inheritance));
+
+        inheritance.created_at_block = 99;
+        assert!(!validate_inheritance(&inheritance));
+        inheritance.created_at_block = 101;
+        assert!(!validate_inheritance(&inheritance));
+    }
+
+    #[test]
+    fn test_created_at_block_immutable() {
+        let app = test_app();
+        let input = test_inheritance();
+        let mut output = input.clone();
+        output.last_checkin_block += 10;
+        output.created_at_block += 10;
+
+        let tx = test_tx(vec![nft_charms(&app, &input)], vec![nft_charms(&app, &output)]);
+        assert!(!can_checkin(&app, &tx, &checkin_witness(&tx, CURRENT_BLOCK)));
+        assert!(!can_update_beneficiaries(&app, &tx, &update_witness(&tx)));
+
+        output.created_at_block = input.created_at_block;
+        let tx = test_tx(vec![nft_charms(&app, &input)], vec![nft_charms(&app, &output)]);
+        assert!(can_checkin(&app, &tx, &checkin_witness(&tx, CURRENT_BLOCK)));
+        assert!(can_update_beneficiaries(&app, &tx, &update_witness(&tx)));
+
+        let (_, mut remaining, mut tx) = partial_distribution(&app);
+        remaining.created_at_block = 0;
+        tx.outs[1] = nft_charms(&app, &remaining);
+        assert!(!can_trigger_distribution(&app, &tx, &at_block(CURRENT_BLOCK)));
+    }
+
+    // x-only public keys of secret keys 2, 3 and 4
+    const GUARDIAN_KEYS: [&str; 3] = [
+        "c6047f9441ed7d6d3045406e95c07cd85c778e4b8cef3ca7abac09b95c709ee5",
+        "f9308a019258c31049344f85f89d5229b531c845836f99b08601f113bce036f9",
+        "e493dbf1c10d80f3581e4904930b1404cc6c13900ee0758474fa94abe8c4cd13",
+    ];
+
+    fn guardian_inheritance(threshold: u8) -> InheritanceContent {
+        let mut inheritance = test_inheritance();
+        inheritance.guardians = GUARDIAN_KEYS.iter().map(|key| key.to_string()).collect();
+        inheritance.guardian_threshold = threshold;
+        inheritance
+    }
+
+    // Signature by guardian i (secret key i + 2)
+    fn guardian_sign(i: usize, msg: &[u8; 32]) -> (String, Vec<u8>) {
+        let mut secret = [0u8; 32];
+        secret[31] = i as u8 + 2;
+        let signing_key = k256::schnorr::SigningKey::from_bytes(&secret).unwrap();
+        let sig = signing_key.sign_raw(msg, &[0u8; 32]).unwrap().to_bytes().to_vec();
+        (GUARDIAN_KEYS[i].to_string(), sig)
+    }
+
+    #[test]
+    fn test_oracle_checkin() {
+        let app = test_app();
+        let mut input = test_inheritance();
+        input.oracle_pubkey = Some(GUARDIAN_KEYS[0].to_string());
+        assert_eq!(input.validate(), Ok(()));
+        let mut output = input.clone();
+        output.last_checkin_block = CURRENT_BLOCK;
+        let tx = test_tx(vec![nft_charms(&app, &input)], vec![nft_charms(&app, &output)]);
+
+        let message = signing_message(Operation::OracleCheckin, &tx, &output);
+        let attest = |i| {
+            Data::from(&OracleCheckinWitness {
+                attestation: guardian_sign(i, &message).1,
+                current_block: CURRENT_BLOCK,
+            })
+        };
+        let x = block_height_input(CURRENT_BLOCK);
+        assert!(can_oracle_checkin(&app, &tx, Some(CURRENT_BLOCK), &attest(0)));
+        assert!(app_contract(&app, &tx, &x, &operation_witness(Operation::OracleCheckin, &attest(0))));
+
+        // The height must be attested, and be the one the witness gives
+        assert!(!app_contract(&app, &tx, &Data::empty(), &operation_witness(Operation::OracleCheckin, &attest(0))));
+        assert!(!can_oracle_checkin(&app, &tx, Some(CURRENT_BLOCK - 1), &attest(0)));
+
+        // Signed by a key other than the oracle's
+        assert!(!can_oracle_checkin(&app, &tx, Some(CURRENT_BLOCK), &attest(1)));
+
+        // Without an oracle, no attestation is accepted
+        let mut no_oracle = input.clone();
+        no_oracle.oracle_pubkey = None;
+        let mut no_oracle_output = no_oracle.clone();
+        no_oracle_output.last_checkin_block = CURRENT_BLOCK;
+        let tx = test_tx(vec![nft_charms(&app, &no_oracle)], vec![nft_charms(&app, &no_oracle_output)]);
+        assert!(!can_oracle_checkin(&app, &tx, Some(CURRENT_BLOCK), &attest(0)));
+
+        // The oracle's key must be usable
+        input.oracle_pubkey = Some("02abcd".to_string());
+        assert_eq!(input.validate(), Err(ValidationError::InvalidOraclePubkey));
+    }
+
+    // An extension of `input` by the emergency contact at `current_block`, signed by guardian `signer`
+    fn emergency_extension(app: &App, input: &InheritanceContent, signer: usize, current_block: u64) -> (Transaction, Data) {
+        let mut output = input.clone();
+        output.last_checkin_block = (input.last_checkin_block + EMERGENCY_EXTENSION_BLOCKS).min(current_block);
+        output.emergency_extension_used = true;
+        let tx = test_tx(vec![nft_charms(app, input)], vec![nft_charms(app, &output)]);
+        let message = signing_message(Operation::EmergencyExtend, &tx, &output);
+        let w = Data::from(&EmergencyExtendWitness { signature: guardian_sign(signer, &message).1 });
+        (tx, w)
+    }
+
+    // The block the owner of test_inheritance last had to check in by
+    const EXTENSION_DEADLINE: u64 = 100 + 4320;
+
+    // Guardians may distribute after 1440 blocks of silence instead of 4320
+    fn emergency_guardian_inheritance() -> InheritanceContent {
+        let mut inheritance = guardian_inheritance(2);
+        inheritance.guardian_emergency_delay = 1440;
+        inheritance
+    }
+
+    #[test]
+    fn test_guardian_emergency_trigger_before_deadline() {
+        let app = test_app();
+        let inheritance = emergency_guardian_inheritance();
+        assert_eq!(inheritance.validate(), Ok(()));
+
+        let plan = compute_distribution_plan(&inheritance, 1_000_000);
+        let tx = distribution_tx(&app, &inheritance, 1_000_000, &plan);
+        let msg = signing_message(Operation::GuardianEmergencyTrigger, &tx, &inheritance);
+        let emergency = |current_block, signers: &[usize]| {
+            let w = Data::from(&DistributionWitness {
+                current_block: Some(current_block),
+                guardian_signatures: signers.iter().map(|&i| guardian_sign(i, &msg)).collect(),
+                ..Default::default()
+            });
+            app_contract(
+                &app,
+                &tx,
+                &block_height_input(current_block),
+                &operation_witness(Operation::GuardianEmergencyTrigger, &w),
+            )
+        };
+
+        // Well before the normal deadline (4420), two of three guardians suffice
+        assert!(!is_distributable(&inheritance, 1541));
+        assert!(emergency(1541, &[0, 1]));
+        assert!(emergency(1541, &[1, 2]));
+
+        // One guardian is short of the threshold, even signing twice
+        assert!(!emergency(1541, &[0]));
+        assert!(!emergency(1541, &[0, 0]));
+
+        // The emergency delay still has to pass
+        assert!(!emergency(1540, &[0, 1]));
+
+        // Signatures for a normal trigger don't count
+        let normal = signing_message(Operation::TriggerDistribution, &tx, &inheritance);
+        let w = Data::from(&DistributionWitness {
+            current_block: Some(1541),
+            guardian_signatures: vec![guardian_sign(0, &normal), guardian_sign(1, &normal)],
+            ..Default::default()
+        });
+        assert!(!can_guardian_emergency_trigger(&app, &tx, Some(1541), &w));
+
+        // Without an emergency delay, guardians wait like anyone else
+        let mut no_emergency = inheritance.clone();
+        no_emergency.guardian_emergency_delay = 0;
+        let tx = distribution_tx(&app, &no_emergency, 1_000_000, &plan);
+        let msg = signing_message(Operation::GuardianEmergencyTrigger, &tx, &no_emergency);
+        let w = Data::from(&DistributionWitness {
+            current_block: Some(1541),
+            guardian_signatures: vec![guardian_sign(0, &msg), guardian_sign(1, &msg)],
+            ..Default::default()
+        });
+        assert!(!can_guardian_emergency_trigger(&app, &tx, Some(1541), &w));
+    }
+
+    #[test]
+    fn test_guardian_emergency_delay_validation() {
+        let mut inheritance = emergency_guardian_inheritance();
+
+        // Must cut the trigger delay short
+        inheritance.guardian_emergency_delay = inheritance.trigger_delay_blocks;
+        assert_eq!(inheritance.validate(), Err(ValidationError::InvalidGuardians));
+
+        // And needs guardians to use it
+        let mut no_guardians = test_inheritance();
+        no_guardians.guardian_emergency_delay = 1440;
+        assert_eq!(no_guardians.validate(), Err(ValidationError::InvalidGuardians));
+    }
+
+    #[test]
+    fn test_emergency_extension_only_once() {
+        let app = test_app();
+        let mut input = test_inheritance();
+        input.emergency_contact_pubkey = Some(GUARDIAN_KEYS[0].to_string());
+        assert_eq!(input.validate(), Ok(()));
+        assert_eq!(deadline_block(&input, EXTENSION_DEADLINE), EXTENSION_DEADLINE);
+
+        // First extension accepted, at the height the public input attests
+        let (tx, w) = emergency_extension(&app, &input, 0, EXTENSION_DEADLINE);
+        assert!(can_emergency_extend(&app, &tx, Some(EXTENSION_DEADLINE), &w));
+        let w = operation_witness(Operation::EmergencyExtend, &w);
+        assert!(app_contract(&app, &tx, &block_height_input(EXTENSION_DEADLINE), &w));
+        assert!(!app_contract(&app, &tx, &Data::empty(), &w));
+
+        // Second rejected: the extension is spent
+        let extended = InheritanceContent::try_from(tx.outs[0].values().next().unwrap()).unwrap();
+        assert_eq!(extended.last_checkin_block, input.last_checkin_block + EMERGENCY_EXTENSION_BLOCKS);
+        let (tx, w) = emergency_extension(&app, &extended, 0, extended.last_checkin_block + 1);
+        assert!(!can_emergency_extend(&app, &tx, Some(extended.last_checkin_block + 1), &w));
+
+        // ...and the owner can't reset it
+        let mut reset = extended.clone();
+        reset.emergency_extension_used = false;
+        let tx = test_tx(vec![nft_charms(&app, &extended)], vec![nft_charms(&app, &reset)]);
+        assert!(!can_update_beneficiaries(&app, &tx, &update_witness(&tx)));
+
+        // ...though the rest of the contract can still be updated
+        let mut updated = extended.clone();
+        updated.metadata.insert("note".to_string(), "extended while in hospital".to_string());
+        let tx = test_tx(vec![nft_charms(&app, &extended)], vec![nft_charms(&app, &updated)]);
+        assert!(can_update_beneficiaries(&app, &tx, &update_witness(&tx)));
+
+        // Nor can a contract start out with it spent
+        let mut spent = input.clone();
+        spent.emergency_extension_used = true;
+        assert_eq!(spent.validate(), Err(ValidationError::InvalidEmergencyContact));
+    }
+
+    #[test]
+    fn test_emergency_extension_needs_contact() {
+        let app = test_app();
+        let mut input = test_inheritance();
+        input.emergency_contact_pubkey = Some(GUARDIAN_KEYS[0].to_string());
+        let at = Some(EXTENSION_DEADLINE);
+
+        // Signed by a key other than the contact's
+        let (tx, w) = emergency_extension(&app, &input, 1, EXTENSION_DEADLINE);
+        assert!(!can_emergency_extend(&app, &tx, at, &w));
+
+        // The owner's signature doesn't count either
+        let output = InheritanceContent::try_from(tx.outs[0].values().next().unwrap()).unwrap();
+        let owner = Data::from(&EmergencyExtendWitness {
+            signature: owner_sign(&signing_message(Operation::EmergencyExtend, &tx, &output)),
+        });
+        assert!(!can_emergency_extend(&app, &tx, at, &owner));
+
+        // Only by the fixed amount
+        let mut further = output.clone();
+        further.last_checkin_block += 1;
+        let tx = test_tx(vec![nft_charms(&app, &input)], vec![nft_charms(&app, &further)]);
+        let message = signing_message(Operation::EmergencyExtend, &tx, &further);
+        let w = Data::from(&EmergencyExtendWitness { signature: guardian_sign(0, &message).1 });
+        assert!(!can_emergency_extend(&app, &tx, Some(EXTENSION_DEADLINE + EMERGENCY_EXTENSION_BLOCKS), &w));
+
+        // Not once the contract is Triggered
+        let mut triggered = input.clone();
+        triggered.status = InheritanceStatus::Triggered;
+        let (tx, w) = emergency_extension(&app, &triggered, 0, EXTENSION_DEADLINE);
+        assert!(!can_emergency_extend(&app, &tx, at, &w));
+
+        // Without a contact, no one can extend
+        input.emergency_contact_pubkey = None;
+        let (tx, w) = emergency_extension(&app, &input, 0, EXTENSION_DEADLINE);
+        assert!(!can_emergency_extend(&app, &tx, at, &w));
+    }
+
+    #[test]
+    fn test_emergency_extension_after_deadline() {
+        let app = test_app();
+        let mut input = test_inheritance();
+        input.emergency_contact_pubkey = Some(GUARDIAN_KEYS[0].to_string());
+
+        // Once the deadline has passed the vault belongs to the heirs, and the
+        // contact can't take it back from them
+        for current_block in [EXTENSION_DEADLINE + 1, CURRENT_BLOCK] {
+            assert!(is_distributable(&input, current_block));
+            let (tx, w) = emergency_extension(&app, &input, 0, current_block);
+            assert_eq!(
+                check_emergency_extend(&mut Rules::proving(), &app, &tx, Some(current_block), &w),
+                Err(RuleFailed::DeadlinePassed)
+            );
+        }
+
+        // Before the deadline the extension runs up to the current block, no further
+        let early = input.last_checkin_block + 1_000;
+        let (tx, w) = emergency_extension(&app, &input, 0, early);
+        assert!(can_emergency_extend(&app, &tx, Some(early), &w));
+        let (tx, w) = emergency_extension(&app, &input, 0, EXTENSION_DEADLINE);
+        assert!(!can_emergency_extend(&app, &tx, Some(early), &w));
+    }
+
+    #[test]
+    fn test_guardian_validation() {
+        assert!(validate_inheritance(&guardian_inheritance(2)));
+        assert!(validate_inheritance(&guardian_inheritance(3)));
+        assert!(!validate_inheritance(&guardian_inheritance(4)));
+        assert!(!validate_inheritance(&guardian_inheritance(0)));
+
+        // No guardians, no threshold
+        let mut inheritance = test_inheritance();
+        assert!(validate_inheritance(&inheritance));
+        inheritance.guardian_threshold = 1;
+        assert!(!validate_inheritance(&inheritance));
+
+        // Malformed key
+        let mut inheritance = guardian_inheritance(2);
+        inheritance.guardians[1] = "not a key".to_string();
+        assert!(!validate_inheritance(&inheritance));
+
+        // The same key twice, even in different encodings
+        let mut inheritance = guardian_inheritance(2);
+        inheritance.guardians[2] = format!("02{}", GUARDIAN_KEYS[0]);
+        assert!(!validate_inheritance(&inheritance));
+    }
+
+    #[test]
+    fn test_verify_guardian_quorum() {
+        let inheritance = guardian_inheritance(2);
+        let msg = [9u8; 32];
+
+        assert!(verify_guardian_quorum(&inheritance, &msg, &[guardian_sign(0, &msg), guardian_sign(2, &msg)]));
+        assert!(!verify_guardian_quorum(&inheritance, &msg, &[guardian_sign(0, &msg)]));
+
+        // A repeat signer counts once
+        assert!(!verify_guardian_quorum(&inheritance, &msg, &[guardian_sign(1, &msg), guardian_sign(1, &msg)]));
+
+        // A signature over a different message doesn't count
+        let other = guardian_sign(2, &[8u8; 32]);
+        assert!(!verify_guardian_quorum(&inheritance, &msg, &[guardian_sign(0, &msg), other]));
+
+        // Without guardians there's no quorum
+        assert!(!verify_guardian_quorum(&test_inheritance(), &msg, &[guardian_sign(0, &msg)]));
+    }
+
+    #[test]
+    fn test_guardians_change_only_on_update() {
+        let app = test_app();
+        let input = guardian_inheritance(2);
+        let mut output = input.clone();
+        output.last_checkin_block += 10;
+        output.guardian_threshold = 3;
+
+        let tx = test_tx(vec![nft_charms(&app, &input)], vec![nft_charms(&app, &output)]);
+        assert!(can_update_beneficiaries(&app, &tx, &update_witness(&tx)));
+        assert!(!can_checkin(&app, &tx, &checkin_witness(&tx, CURRENT_BLOCK)));
+
+        output.guardians.pop();
+        let tx = test_tx(vec![nft_charms(&app, &input)], vec![nft_charms(&app, &output)]);
+        assert!(!can_update_beneficiaries(&app, &tx, &update_witness(&tx)));
+    }
+
+    fn estate(shares_bps: &[u16]) -> PercentageAllocation {
+        PercentageAllocation {
+            contracts: shares_bps
+                .iter()
+                .enumerate()
+                .map(|(i, &share_bps)| (ContractId(B32([i as u8; 32])), share_bps))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_validate_total_allocation() {
+        assert!(validate_total_allocation(&estate(&[6000, 4000])));
+        assert!(validate_total_allocation(&estate(&[10_000])));
+        assert!(validate_total_allocation(&estate(&[2500])));
+        assert!(validate_total_allocation(&estate(&[])));
+
+        assert!(!validate_total_allocation(&estate(&[6000, 4001])));
+        assert!(!validate_total_allocation(&estate(&[10_001])));
+        assert!(!validate_total_allocation(&estate(&[u16::MAX, u16::MAX])));
+    }
+
+    #[test]
+    fn test_allocate_across_contracts() {
+        let alloc = estate(&[6000, 3333]);
+        let amounts = allocate_across_contracts(1_000_000, &alloc);
+        assert_eq!(
+            amounts,
+            vec![
+                (ContractId(B32([0; 32])), 600_000),
+                (ContractId(B32([1; 32])), 333_300),
+            ]
+        );
+
+        let amounts = allocate_across_contracts(999, &estate(&[5000, 5000]));
+        assert_eq!(amounts.iter().map(|(_, sats)| sats).sum::<u64>(), 998);
+    }
+
+    fn metadata(entries: usize, value_len: usize) -> BTreeMap<String, String> {
+        (0..entries).map(|i| (format!("note-{i}"), "x".repeat(value_len))).collect()
+    }
+
+    #[test]
+    fn test_metadata_bounds() {
+        let mut inheritance = test_inheritance();
+        inheritance.metadata.insert("seed-backup".to_string(), "Safe deposit box at X".to_string());
+        assert!(validate_inheritance(&inheritance));
+
+        inheritance.metadata = metadata(MAX_METADATA_ENTRIES, RuleSet::default().max_note_length);
+        assert!(validate_inheritance(&inheritance));
+
+        inheritance.metadata = metadata(MAX_METADATA_ENTRIES + 1, 1);
+        assert!(!validate_inheritance(&inheritance));
+
+        inheritance.metadata = metadata(1, RuleSet::default().max_note_length + 1);
+        assert!(!validate_inheritance(&inheritance));
+
+        inheritance.metadata = BTreeMap::from([("k".repeat(MAX_METADATA_KEY_LEN + 1), String::new())]);
+        assert!(!validate_inheritance(&inheritance));
+
+        inheritance.metadata = BTreeMap::from([(String::new(), "empty key".to_string())]);
+        assert!(!validate_inheritance(&inheritance));
+    }
+
+    #[test]
+    fn test_metadata_bloat_hits_size_cap() {
+        // Looser rules allow longer notes, but the content as a whole is still capped
+        let mut inheritance = test_inheritance();
+        inheritance.rule_set = RuleSet::permissive();
+        inheritance.metadata = metadata(MAX_METADATA_ENTRIES, RuleSet::permissive().max_note_length);
+        assert!(validate_metadata(&inheritance.metadata, &inheritance.rule_set));
+        assert!(content_size(&inheritance) > MAX_CONTENT_BYTES);
+        assert!(!validate_inheritance(&inheritance));
+    }
+
+    #[test]
+    fn test_metadata_changes_only_on_update() {
+        let app = test_app();
+        let input = test_inheritance();
+        let mut output = input.clone();
+        output.last_checkin_block += 10;
+        output.metadata.insert("letter".to_string(), "In the top drawer".to_string());
+
+        let tx = test_tx(vec![nft_charms(&app, &input)], vec![nft_charms(&app, &output)]);
+        assert!(can_update_beneficiaries(&app, &tx, &update_witness(&tx)));
+        assert!(!can_checkin(&app, &tx, &checkin_witness(&tx, CURRENT_BLOCK)));
+
+        output.metadata = metadata(MAX_METADATA_ENTRIES + 1, 1);
+        let tx = test_tx(vec![nft_charms(&app, &input)], vec![nft_charms(&app, &output)]);
+        assert!(!can_update_beneficiaries(&app, &tx, &update_witness(&tx)));
+    }
+
+    #[test]
+    fn test_will_document_hash_set_at_creation() {
+        let mut inheritance = test_inheritance();
+        inheritance.will_document_hash = Some(B32([0xab; 32]));
+        assert_eq!(inheritance.validate(), Ok(()));
+
+        inheritance.will_locked = true;
+        assert_eq!(inheritance.validate(), Ok(()));
+
+        // The all-zero hash commits to nothing
+        inheritance.will_document_hash = Some(B32([0; 32]));
+        assert_eq!(inheritance.validate(), Err(ValidationError::InvalidWillDocumentHash));
+    }
+
+    #[test]
+    fn test_will_document_hash_updates_before_lock() {
+        let app = test_app();
+        let mut input = test_inheritance();
+        input.will_document_hash = Some(B32([0xab; 32]));
+        let mut output = input.clone();
+        output.last_checkin_block += 10;
+        output.will_document_hash = Some(B32([0xcd; 32]));
+
+        let tx = test_tx(vec![nft_charms(&app, &input)], vec![nft_charms(&app, &output)]);
+        assert!(can_update_beneficiaries(&app, &tx, &update_witness(&tx)));
+
+        // A check-in must leave it untouched
+        assert!(!can_checkin(&app, &tx, &checkin_witness(&tx, CURRENT_BLOCK)));
+
+        // Locking is an update too, and the hash can change in the same one
+        output.will_locked = true;
+        let tx = test_tx(vec![nft_charms(&app, &input)], vec![nft_charms(&app, &output)]);
+        assert!(can_update_beneficiaries(&app, &tx, &update_witness(&tx)));
+
+        output.will_document_hash = Some(B32([0; 32]));
+        let tx = test_tx(vec![nft_charms(&app, &input)], vec![nft_charms(&app, &output)]);
+        assert!(!can_update_beneficiaries(&app, &tx, &update_witness(&tx)));
+    }
+
+    #[test]
+    fn test_will_document_hash_immutable_after_lock() {
+        let app = test_app();
+        let mut input = test_inheritance();
+        input.will_document_hash = Some(B32([0xab; 32]));
+        input.will_locked = true;
+
+        let update = |change: fn(&mut InheritanceContent)| {
+            let mut output = input.clone();
+            output.last_checkin_block += 10;
+            change(&mut output);
+            let tx = test_tx(vec![nft_charms(&app, &input)], vec![nft_charms(&app, &output)]);
+            can_update_beneficiaries(&app, &tx, &update_witness(&tx))
+        };
+
+        // Other settings can still change
+        assert!(update(|c| {
+            c.metadata.insert("letter".to_string(), "In the top drawer".to_string());
+        }));
+
+        assert!(!update(|c| c.will_document_hash = Some(B32([0xcd; 32]))));
+        assert!(!update(|c| c.will_document_hash = None));
+        assert!(!update(|c| c.will_locked = false));
+    }
+
+    // Leaves `tombstone` as the only NFT output of a final distribution
+    fn with_tombstone(app: &App, mut tx: Transaction, tombstone: &DistributionTombstone) -> Transaction {
+        tx.outs.push(Charms::from([(app.clone(), Data::from(tombstone))]));
+        tx
+    }
+
+    #[test]
+    fn test_tombstone_commits_to_payout_plan() {
+        let app = test_app();
+        let mut inheritance = test_inheritance();
+        inheritance.leave_tombstone = true;
+
+        let plan = compute_distribution_plan(&inheritance, 1_000_000);
+        let tombstone = DistributionTombstone {
+            status: InheritanceStatus::Distributed,
+            distributed_sats: 1_000_000,
+            plan_commitment: plan_commitment(&plan),
+        };
+        let tx = distribution_tx(&app, &inheritance, 1_000_000, &plan);
+        assert!(can_trigger_distribution(&app, &with_tombstone(&app, tx.clone(), &tombstone), &at_block(CURRENT_BLOCK)));
+
+        // The contract asked for a tombstone, so burning the NFT is rejected
+        assert!(!can_trigger_distribution(&app, &tx, &at_block(CURRENT_BLOCK)));
+
+        // A commitment to any other plan is rejected, even with the same total
+        let mut other_plan = plan.clone();
+        other_plan[0].1 -= 1;
+        other_plan[1].1 += 1;
+        let other = [
+            DistributionTombstone {
+                plan_commitment: plan_commitment(&other_plan),
+                ..tombstone.clone()
+            },
+            DistributionTombstone {
+                plan_commitment: plan_commitment(&[plan[1].clone(), plan[0].clone()]),
+                ..tombstone.clone()
+            },
+            DistributionTombstone {
+                distributed_sats: 999_999,
+                ..tombstone.clone()
+            },
+            DistributionTombstone {
+                status: InheritanceStatus::Triggered,
+                ..tombstone.clone()
+            },
+        ];
+        for bad in other {
+            assert!(!can_trigger_distribution(&app, &with_tombstone(&app, tx.clone(), &bad), &at_block(CURRENT_BLOCK)));
+        }
+
+        // Without the flag, the NFT must be burned
+        inheritance.leave_tombstone = false;
+        let tx = distribution_tx(&app, &inheritance, 1_000_000, &plan);
+        assert!(!can_trigger_distribution(&app, &with_tombstone(&app, tx, &tombstone), &at_block(CURRENT_BLOCK)));
+    }
+
+    #[test]
+    fn test_status_display_and_from_str() {
+        for status in InheritanceStatus::ALL {
+            assert_eq!(status.to_string().parse::<InheritanceStatus>(), Ok(status));
+        }
+        assert_eq!(InheritanceStatus::Triggered.to_string(), "triggered");
+        assert_eq!("DISTRIBUTED".parse(), Ok(InheritanceStatus::Distributed));
+        assert_eq!("expired".parse::<InheritanceStatus>(), Err(ParseStatusError("expired".to_string())));
+        assert_eq!("".parse::<InheritanceStatus>(), Err(ParseStatusError(String::new())));
+    }
+
+    #[test]
+    fn test_status_on_chain_encoding_is_stable() {
+        // Minted NFTs carry these strings; changing them would orphan every vault
+        let encoded = ["Active", "Triggered", "Distributed"];
+        for (status, name) in InheritanceStatus::ALL.into_iter().zip(encoded) {
+            let data = Data::from(&status);
+            assert_eq!(data.value::<String>().unwrap(), name);
+            assert_eq!(data.value::<InheritanceStatus>().unwrap(), status);
+        }
+    }
+
+    #[test]
+    fn test_status_is_terminal() {
+        let terminal: Vec<_> = InheritanceStatus::ALL.into_iter().filter(|s| s.is_terminal()).collect();
+        assert_eq!(terminal, vec![InheritanceStatus::Distributed]);
+    }
+
+    #[test]
+    fn test_over_budget_transaction_rejected_before_validation() {
+        let app = test_app();
+        let inheritance = test_inheritance();
+        let plan = compute_distribution_plan(&inheritance, 1_000_000);
+        let tx = distribution_tx(&app, &inheritance, 1_000_000, &plan);
+
+        // A witness padded with derived keys nobody asked for doesn't change the outcome...
+        let padded = DistributionWitness {
+            derived: vec![
+                DerivedPayment {
+                    beneficiary_index: 0,
+                    child_index: 0,
+                    script_pubkey: vec![0x00, 0x14],
+                };
+                100_000
+            ],
+            current_block: Some(CURRENT_BLOCK),
+            ..Default::default()
+        };
+        let w = Data::from(&padded);
+        assert!(can_trigger_distribution(&app, &tx, &w));
+
+        // ...but it's more work than the contract will take on, so it's refused up front
+        assert!(validation_cost(&app, &tx, &w) > MAX_VALIDATION_COST);
+        let x = block_height_input(CURRENT_BLOCK);
+        assert!(!app_contract(&app, &tx, &x, &operation_witness(Operation::TriggerDistribution, &w)));
+
+        assert!(validation_cost(&app, &tx, &no_derivations()) <= MAX_VALIDATION_COST);
+        let unpadded = operation_witness(Operation::TriggerDistribution, &no_derivations());
+        assert!(app_contract(&app, &tx, &x, &unpadded));
+    }
+
+    #[test]
+    fn test_oversized_witness_rejected_before_decoding() {
+        let app = test_app();
+        let inheritance = test_inheritance();
+        let plan = compute_distribution_plan(&inheritance, 1_000_000);
+        let tx = distribution_tx(&app, &inheritance, 1_000_000, &plan);
+
+        // Cheap enough to check, and still a valid distribution, but too many bytes
+        let long = DistributionWitness {
+            derived: vec![
+                DerivedPayment {
+                    beneficiary_index: 0,
+                    child_index: 0,
+                    script_pubkey: vec![0x00, 0x14],
+                };
+                2_000
+            ],
+            current_block: Some(CURRENT_BLOCK),
+            ..Default::default()
+        };
+        let w = Data::from(&long);
+        let x = block_height_input(CURRENT_BLOCK);
+        assert!(w.bytes().len() > MAX_WITNESS_BYTES);
+        assert!(validation_cost(&app, &tx, &w) <= MAX_VALIDATION_COST);
+        assert!(can_trigger_distribution(&app, &tx, &w));
+        let oversized = operation_witness(Operation::TriggerDistribution, &w);
+        assert!(!app_contract(&app, &tx, &x, &oversized));
+        assert!(matches!(
+            evaluate(&app, &tx, &x, &oversized),
+            Err(ContractError::WitnessTooLarge { max: MAX_WITNESS_BYTES, .. })
+        ));
+
+        // Bytes that don't even decode as a witness are measured, not parsed
+        let garbage = Data::from(&vec![0u8; MAX_WITNESS_BYTES]);
+        assert!(matches!(evaluate(&app, &tx, &x, &garbage), Err(ContractError::WitnessTooLarge { .. })));
+
+        // Contents are measured the same way, before any of them is migrated
+        let mut oversized = tx.clone();
+        oversized.ins[0].1.insert(app.clone(), Data::from(&vec![0u8; MAX_CONTENT_BYTES + 1]));
+        let trigger = operation_witness(Operation::TriggerDistribution, &no_derivations());
+        assert!(!app_contract(&app, &oversized, &x, &trigger));
+        assert!(matches!(
+            evaluate(&app, &oversized, &x, &trigger),
+            Err(ContractError::ContentTooLarge { max: MAX_CONTENT_BYTES, .. })
+        ));
+    }
+
+    #[test]
+    fn test_witness_must_match_the_claimed_operation() {
+        let app = test_app();
+        let inheritance = test_inheritance();
+        let plan = compute_distribution_plan(&inheritance, 1_000_000);
+        let tx = distribution_tx(&app, &inheritance, 1_000_000, &plan);
+        let distribution = Data::from(&DistributionWitness::default());
+        let x = block_height_input(CURRENT_BLOCK);
+
+        let trigger = operation_witness(Operation::TriggerDistribution, &distribution);
+        assert!(app_contract(&app, &tx, &x, &trigger));
+
+        // Claiming a check-in with a distribution's witness validates as a check-in, and fails
+        assert!(!app_contract(&app, &tx, &x, &operation_witness(Operation::Checkin, &distribution)));
+
+        // A bare witness names no operation at all
+        assert!(!app_contract(&app, &tx, &x, &distribution));
+
+        // A check-in's transaction can't be passed off as any other operation either
+        let mut checked_in = inheritance.clone();
+        checked_in.last_checkin_block = CURRENT_BLOCK;
+        let tx = test_tx(vec![nft_charms(&app, &inheritance)], vec![nft_charms(&app, &checked_in)]);
+        let w = checkin_witness(&tx, CURRENT_BLOCK);
+        assert!(app_contract(&app, &tx, &x, &operation_witness(Operation::Checkin, &w)));
+        for operation in [Operation::Create, Operation::TriggerDistribution, Operation::Migrate] {
+            assert!(!app_contract(&app, &tx, &x, &operation_witness(operation, &w)));
+        }
+    }
+
+    #[test]
+    fn test_beneficiary_ordering_is_canonical() {
+        let beneficiary = |address: &str, allocation| Beneficiary {
+            payee: Payee::from(address),
+            allocation,
+            vesting_delay_blocks: 0,
+        };
+        let list = vec![
+            beneficiary("tb1qb", Allocation::Share(2000)),
+            beneficiary("tb1qa", Allocation::Fixed(5000)),
+            beneficiary("tb1qa", Allocation::Share(3000)),
+            beneficiary("tb1qb", Allocation::Share(2000)), // Duplicate (rejected by validation, see validate_beneficiaries)
+            beneficiary("tb1qa", Allocation::Share(1000)),
+        ];
+
+        // Address first, then allocation
+        let mut sorted = list.clone();
+        sorted.sort();
+        let keys: Vec<_> = sorted.iter().map(|b| (b.payee.clone(), b.allocation)).collect();
+        assert_eq!(
+            keys,
+            vec![
+                (Payee::from("tb1qa"), Allocation::Share(1000)),
+                (Payee::from("tb1qa"), Allocation::Share(3000)),
+                (Payee::from("tb1qa"), Allocation::Fixed(5000)),
+                (Payee::from("tb1qb"), Allocation::Share(2000)),
+                (Payee::from("tb1qb"), Allocation::Share(2000)),
+            ]
+        );
+
+        // The order is total: every rotation and reversal sorts to the same list
+        for shift in 0..list.len() {
+            let mut rotated = list.clone();
+            rotated.rotate_left(shift);
+            assert!(same_beneficiaries_unordered(&rotated, &list));
+            rotated.reverse();
+            rotated.sort();
+            assert_eq!(rotated, sorted);
+        }
+
+        // Equal beneficiaries hash equally, so duplicates collapse in a set
+        let set: std::collections::HashSet<&Beneficiary> = list.iter().collect();
+        assert_eq!(set.len(), 4);
+
+        // Order still matters to the contract's own comparison
+        assert_ne!(list, sorted);
+        assert!(!same_beneficiaries_unordered(&list, &sorted[1..]));
+    }
+
+    // Beneficiary paid to guardian key i's taproot output, with the given share
+    fn taproot_beneficiary(i: usize, share_bps: u16) -> Beneficiary {
+        let mut script_pubkey = vec![0x51, 0x20];
+        script_pubkey.extend(decode_hex(GUARDIAN_KEYS[i]).unwrap());
+        Beneficiary {
+            payee: Payee::Script(ScriptPayee { script_pubkey }),
+            allocation: Allocation::Share(share_bps),
+            vesting_delay_blocks: 0,
+        }
+    }
+
+    #[test]
+    fn test_verify_beneficiary_signatures() {
+        let msg = [9u8; 32];
+        let beneficiaries = vec![
+            taproot_beneficiary(0, 5000),
+            taproot_beneficiary(1, 3000),
+            taproot_beneficiary(2, 2000),
+        ];
+        let sig = |i| guardian_sign(i, &msg).1;
+
+        // All sign
+        let all = vec![sig(0), sig(1), sig(2)];
+        assert_eq!(verify_beneficiary_signatures(&beneficiaries, &all, &msg), 10_000);
+
+        // Exactly a 50% threshold
+        let threshold = vec![sig(0), vec![], vec![]];
+        assert_eq!(verify_beneficiary_signatures(&beneficiaries, &threshold, &msg), 5000);
+
+        // None sign
+        assert_eq!(verify_beneficiary_signatures(&beneficiaries, &[], &msg), 0);
+        assert_eq!(verify_beneficiary_signatures(&beneficiaries, &[vec![], vec![], vec![]], &msg), 0);
+
+        // A bad signature in the middle is skipped; the others still count
+        let mut tampered = sig(1);
+        tampered[0] ^= 1;
+        let middle_invalid = vec![sig(0), tampered, sig(2)];
+        assert_eq!(verify_beneficiary_signatures(&beneficiaries, &middle_invalid, &msg), 7000);
+
+        // Signatures must line up with their beneficiary, and be over the same message
+        let swapped = vec![sig(1), sig(0), sig(2)];
+        assert_eq!(verify_beneficiary_signatures(&beneficiaries, &swapped, &msg), 2000);
+        assert_eq!(verify_beneficiary_signatures(&beneficiaries, &all, &[8u8; 32]), 0);
+        assert_eq!(verify_beneficiary_signatures(&beneficiaries, &all, &msg[..31]), 0);
+
+        // A P2WPKH beneficiary has no key to check, and a taproot one only its own
+        let others = test_inheritance().beneficiaries;
+        assert_eq!(verify_beneficiary_signatures(&others, &all[..2], &msg), 0);
+    }
+
+    #[test]
+    fn test_public_validation_matches_contract() {
+        let changes: Vec<fn(&mut InheritanceContent)> = vec![
+            |_| {},
+            |c| c.status = InheritanceStatus::Triggered,
+            |c| c.trigger_height = Some(150),
+            |c| c.created_at_block = 99,
+            |c| c.owner_pubkey = "not hex".to_string(),
+            |c| c.beneficiaries[1].payee = c.beneficiaries[0].payee.clone(),
+            |c| c.rule_set.min_beneficiaries = 0,
+            |c| c.beneficiaries.clear(),
+            |c| c.rule_set = RuleSet::strict(),
+            |c| c.rule_set.max_beneficiaries = 1,
+            |c| c.beneficiaries[1].allocation = Allocation::Share(10_001),
+            |c| c.beneficiaries[1].allocation = Allocation::Fixed(DUST_THRESHOLD_SATS - 1),
+            |c| c.beneficiaries[1].payee = Payee::from(""),
+            |c| c.beneficiaries[1].allocation = Allocation::Fixed(1_000),
+            |c| c.beneficiaries[1].allocation = Allocation::Share(3000),
+            |c| c.rule_set.reject_owner_beneficiary = true,
+            |c| c.fallback_address = Some("not an address".to_string()),
+            |c| c.guardian_threshold = 1,
+            |c| {
+                c.metadata.insert(String::new(), "note".to_string());
+            },
+            |c| c.trigger_delay_blocks = 0,
+            |c| c.trigger_delay_blocks = 1_000_000,
+            |c| c.delay_decay = Some(DelayDecay { interval_blocks: 0, step_blocks: 1, floor_blocks: 1 }),
+            |c| {
+                c.metadata.insert("note".to_string(), "x".repeat(200));
+                c.metadata.insert("more".to_string(), "x".repeat(200));
+                c.beneficiaries = (0..60)
+                    .map(|i| Beneficiary {
+                        payee: Payee::Address(format!("tb1p{:0>58}", i)),
+                        allocation: Allocation::Share(0),
+                        vesting_delay_blocks: 0,
+                    })
+                    .collect();
+                c.beneficiaries[0].allocation = Allocation::Share(FULL_SHARE);
+            },
+            |c| c.co_signers = vec!["not hex".to_string()],
+            |c| c.multisig_threshold = 2,
+        ];
+
+        let funding = vec![test_utxo_id(0)];
+        let app = App {
+            tag: NFT,
+            identity: composite_identity(&funding),
+            vk: B32([7; 32]),
+        };
+        let w = Data::from(&CreateWitness::Single(test_utxo_id(0).to_string()));
+
+        let mut rejected = 0;
+        for change in changes {
+            let mut content = test_inheritance();
+            change(&mut content);
+            let tx = test_tx(vec![Charms::new()], vec![nft_charms(&app, &content)]);
+
+            let valid = content.validate().is_ok();
+            assert_eq!(valid, validate_inheritance(&content), "{content:?}");
+            assert_eq!(valid, can_create_inheritance(&app, &tx, &w), "{content:?}");
+            if !valid {
+                rejected += 1;
+            }
+        }
+        assert_eq!(rejected, 23);
+    }
+
+    #[test]
+    fn test_validation_errors_name_the_rule() {
+        let mut content = test_inheritance();
+        assert_eq!(content.validate(), Ok(()));
+
+        content.beneficiaries[1].payee = Payee::from("");
+        assert_eq!(
+            content.validate(),
+            Err(ValidationError::Beneficiary { index: 1, error: BeneficiaryError::InvalidPayee })
+        );
+        assert_eq!(content.beneficiaries[1].validate(), Err(BeneficiaryError::InvalidPayee));
+        assert_eq!(content.beneficiaries[0].validate(), Ok(()));
+
+        let mut content = test_inheritance();
+        content.rule_set = RuleSet::strict();
+        content.beneficiaries.pop();
+        assert_eq!(
+            content.validate(),
+            Err(ValidationError::TooFewBeneficiaries { min: 2, provided: 1 })
+        );
+
+        let mut content = test_inheritance();
+        content.trigger_delay_blocks = 1;
+        assert_eq!(
+            content.validate(),
+            Err(ValidationError::Delay(DelayValidationError::TooShort { minimum: 6, provided: 1 }))
+        );
+
+        let mut content = test_inheritance();
+        content.network = Network::Mainnet;
+        assert_eq!(
+            content.validate(),
+            Err(ValidationError::Beneficiary { index: 0, error: BeneficiaryError::UnsupportedAddress })
+        );
+
+        let mut content = test_inheritance();
+        content.beneficiaries[0].allocation = Allocation::Share(5000);
+        assert_eq!(content.validate(), Err(ValidationError::SharesNotWhole));
+
+        let mut content = test_inheritance();
+        content.beneficiaries[1].payee = content.beneficiaries[0].payee.clone();
+        assert_eq!(content.validate(), Err(ValidationError::DuplicateAddress { index: 1 }));
+
+        let mut content = cosigned_inheritance();
+        content.multisig_threshold = 4;
+        assert_eq!(content.validate(), Err(ValidationError::UnreachableMultisigThreshold));
+        content.co_signers.push("not hex".to_string());
+        assert_eq!(content.validate(), Err(ValidationError::InvalidCoSigner));
+    }
+
+    // Owner key and heirs shared by the builder tests
+    const OWNER: &str = "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798";
+    const HEIR_A: &str = "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx";
+    const HEIR_B: &str = "tb1pqqqqp399et2xygdj5xreqhjjvcmzhxw4aywxecjdzew6hylgvsesf3hn0c";
+
+    #[test]
+    fn test_builder_matches_struct_literal() {
+        let built = InheritanceContent::builder()
+            .owner_pubkey(OWNER)
+            .delay_blocks(4320)
+            .created_at_block(100)
+            .beneficiary(HEIR_A, 6000)
+            .beneficiary(HEIR_B, 4000)
+            .build();
+        assert_eq!(built, Ok(test_inheritance()));
+
+        let built = built.unwrap();
+        assert_eq!(built.status, InheritanceStatus::Active);
+        assert_eq!(built.trigger_height, None);
+        assert_eq!(built.version, CONTENT_VERSION);
+    }
+
+    #[test]
+    fn test_new_validates_up_front() {
+        let heirs = test_inheritance().beneficiaries;
+        assert_eq!(InheritanceContent::new(OWNER, 4320, heirs.clone(), 100), Ok(test_inheritance()));
+
+        assert_eq!(
+            InheritanceContent::new("not a key", 4320, heirs.clone(), 100),
+            Err(ValidationError::InvalidOwnerPubkey)
+        );
+        assert_eq!(
+            InheritanceContent::new(OWNER, 4320, Vec::new(), 100),
+            Err(ValidationError::TooFewBeneficiaries { min: 1, provided: 0 })
+        );
+        assert_eq!(
+            InheritanceContent::new(OWNER, 0, heirs.clone(), 100),
+            Err(ValidationError::DelayBelowRuleSet { minimum: 1, provided: 0 })
+        );
+
+        let mut short = heirs.clone();
+        short[1].allocation = Allocation::Share(3000);
+        assert_eq!(InheritanceContent::new(OWNER, 4320, short, 100), Err(ValidationError::SharesNotWhole));
+
+        let mut unpayable = heirs;
+        unpayable[1].payee = Payee::from("");
+        assert_eq!(
+            InheritanceContent::new(OWNER, 4320, unpayable, 100),
+            Err(ValidationError::Beneficiary { index: 1, error: BeneficiaryError::InvalidPayee })
+        );
+    }
+
+    // Account xpub (m/44'/0'/0') of the "abandon ... about" BIP-39 test mnemonic
+    const ACCOUNT_XPUB: &str = "xpub6BosfCnifzxcFwrSzQiqu2DBVTshkCXacvNsWGYJVVhhawA7d4R5WSWGFNbi8Aw6ZRc1brxMyWMzG3DSSSSoekkudhUd9yLb6qx39T9nMdj";
+
+    #[test]
+    fn test_create_inheritance_from_xpub() {
+        let heirs = test_inheritance().beneficiaries;
+
+        // The key of the wallet's first receive address, m/44'/0'/0'/0/0
+        let expected = "03aaeb52dd7494c361049de67cc680e83ebcbbbdbeb13637d92cd845f70308af5e";
+        let content = create_inheritance_from_xpub(ACCOUNT_XPUB, "m/44'/0'/0'/0/0", heirs.clone(), 4320).unwrap();
+        assert_eq!(content.owner_pubkey, expected);
+        assert_eq!(content.trigger_delay_blocks, 4320);
+        assert_eq!(content.beneficiaries, heirs);
+        assert_eq!(content.status, InheritanceStatus::Active);
+
+        // The same key, by a path relative to the xpub
+        let relative = create_inheritance_from_xpub(ACCOUNT_XPUB, "0/0", heirs.clone(), 4320).unwrap();
+        assert_eq!(relative.owner_pubkey, expected);
+        let next = create_inheritance_from_xpub(ACCOUNT_XPUB, "m/44h/0h/0h/0/1", heirs.clone(), 4320).unwrap();
+        assert_ne!(next.owner_pubkey, expected);
+    }
+
+    #[test]
+    fn test_create_inheritance_from_xpub_errors() {
+        let heirs = test_inheritance().beneficiaries;
+        let from = |xpub, path| create_inheritance_from_xpub(xpub, path, heirs.clone(), 4320);
+
+        assert_eq!(from("xpub-not-really", "0/0"), Err(XpubError::InvalidXpub));
+        assert_eq!(from(&ACCOUNT_XPUB[..ACCOUNT_XPUB.len() - 1], "0/0"), Err(XpubError::InvalidXpub));
+
+        // Hardened steps past the xpub can't be derived from it
+        assert_eq!(from(ACCOUNT_XPUB, "0'/0"), Err(XpubError::DerivationFailed));
+        assert_eq!(from(ACCOUNT_XPUB, "m/44'/0'/0'/0'/0"), Err(XpubError::DerivationFailed));
+
+        // A full path must pass through the xpub, and be well-formed
+        assert_eq!(from(ACCOUNT_XPUB, "m/44'/0'/1'/0/0"), Err(XpubError::DerivationFailed));
+        assert_eq!(from(ACCOUNT_XPUB, "m/44'/0'"), Err(XpubError::DerivationFailed));
+        assert_eq!(from(ACCOUNT_XPUB, "0/x"), Err(XpubError::DerivationFailed));
+        assert_eq!(from(ACCOUNT_XPUB, "0//0"), Err(XpubError::DerivationFailed));
+
+        // A good key doesn't save bad content
+        assert_eq!(
+            create_inheritance_from_xpub(ACCOUNT_XPUB, "0/0", Vec::new(), 4320),
+            Err(XpubError::Content(ValidationError::TooFewBeneficiaries { min: 1, provided: 0 }))
+        );
+    }
+
+    #[test]
+    fn test_builder_error_paths() {
+        use validation_rules::TESTNET_MAX;
+
+        let builder = || {
+            InheritanceContent::builder()
+                .owner_pubkey(OWNER)
+                .delay_blocks(4320)
+                .created_at_block(100)
+        };
+
+        // Missing or malformed owner key
+        let missing_owner = InheritanceContent::builder().delay_blocks(4320).beneficiary(HEIR_A, 10_000);
+        assert_eq!(missing_owner.build(), Err(ValidationError::InvalidOwnerPubkey));
+        assert_eq!(
+            builder().owner_pubkey("02abcd").beneficiary(HEIR_A, 10_000).build(),
+            Err(ValidationError::InvalidOwnerPubkey)
+        );
+
+        // Beneficiary list as a whole
+        assert_eq!(builder().build(), Err(ValidationError::TooFewBeneficiaries { min: 1, provided: 0 }));
+        let capped = RuleSet {
+            max_beneficiaries: 1,
+            ..RuleSet::default()
+        };
+        assert_eq!(
+            builder().rule_set(capped).beneficiary(HEIR_A, 6000).beneficiary(HEIR_B, 4000).build(),
+            Err(ValidationError::TooManyBeneficiaries { max: 1, provided: 2 })
+        );
+        assert_eq!(
+            builder().beneficiary(HEIR_A, 6000).beneficiary(HEIR_B, 3000).build(),
+            Err(ValidationError::SharesNotWhole)
+        );
+        assert_eq!(
+            builder().total_locked_sats(50_000).fixed_beneficiary(HEIR_A, 60_000).beneficiary(HEIR_B, 10_000).build(),
+            Err(ValidationError::FixedExceedsVault)
+        );
+
+        // Individual entries
+        assert_eq!(
+            builder().beneficiary("", 10_000).build(),
+            Err(ValidationError::Beneficiary { index: 0, error: BeneficiaryError::InvalidPayee })
+        );
+        assert_eq!(
+            builder().beneficiary(HEIR_A, 10_001).build(),
+            Err(ValidationError::Beneficiary { index: 0, error: BeneficiaryError::ShareAboveWhole })
+        );
+        assert_eq!(
+            builder().total_locked_sats(50_000).beneficiary(HEIR_A, 10_000).fixed_beneficiary(HEIR_B, 1).build(),
+            Err(ValidationError::Beneficiary { index: 1, error: BeneficiaryError::FixedBelowDust })
+        );
+
+        // Rule set
+        let unsatisfiable = RuleSet {
+            min_beneficiaries: 3,
+            max_beneficiaries: 2,
+            ..RuleSet::default()
+        };
+        assert_eq!(
+            builder().rule_set(unsatisfiable).beneficiary(HEIR_A, 10_000).build(),
+            Err(ValidationError::InconsistentRuleSet)
+        );
+        let no_self_payment = RuleSet {
+            reject_owner_beneficiary: true,
+            ..RuleSet::default()
+        };
+        // HEIR_A is the owner key's own P2WPKH address
+        assert_eq!(
+            builder().rule_set(no_self_payment).beneficiary(HEIR_A, 10_000).build(),
+            Err(ValidationError::OwnerIsBeneficiary)
+        );
+
+        // Delay
+        assert_eq!(
+            builder().delay_blocks(0).beneficiary(HEIR_A, 10_000).build(),
+            Err(ValidationError::DelayBelowRuleSet { minimum: 1, provided: 0 })
+        );
+        assert_eq!(
+            builder().delay_blocks(3).beneficiary(HEIR_A, 10_000).build(),
+            Err(ValidationError::Delay(DelayValidationError::TooShort { minimum: 6, provided: 3 }))
+        );
+        assert_eq!(
+            builder().delay_blocks(TESTNET_MAX + 1).beneficiary(HEIR_A, 10_000).build(),
+            Err(ValidationError::Delay(DelayValidationError::TooLong {
+                maximum: TESTNET_MAX,
+                provided: TESTNET_MAX + 1
+            }))
+        );
+    }
+
+    #[test]
+    fn test_precise_shares_split_in_thirds() {
+        let mut inheritance = test_inheritance();
+        let third = |address, ppm| Beneficiary {
+            payee: Payee::from(address),
+            allocation: Allocation::PreciseShare(SharePpm(ppm)),
+            vesting_delay_blocks: 0,
+        };
+        inheritance.beneficiaries = vec![
+            inheritance.beneficiaries[0].clone(),
+            third("tb1pqqqqp399et2xygdj5xreqhjjvcmzhxw4aywxecjdzew6hylgvsesf3hn0c", 333_333),
+            third("mipcBbFg9gMiCh81Kj8tqqdgoZub1ZJRfn", 333_333),
+        ];
+        inheritance.beneficiaries[0].allocation = Allocation::PreciseShare(SharePpm(333_334));
+        assert!(validate_inheritance(&inheritance));
+
+        let plan = compute_distribution_plan(&inheritance, 3_000_000);
+        let amounts: Vec<u64> = plan.iter().map(|(_, amount)| *amount).collect();
+        assert_eq!(amounts, vec![1_000_002, 999_999, 999_999]);
+
+        // The shares must add up to exactly 1_000_000 ppm
+        inheritance.beneficiaries[0].allocation = Allocation::PreciseShare(SharePpm(333_333));
+        assert!(!validate_inheritance(&inheritance));
+
+        // Basis points and ppm mix: 50% + 1/6 + 1/3
+        inheritance.beneficiaries[0].allocation = Allocation::Share(5000);
+        inheritance.beneficiaries[1].allocation = Allocation::PreciseShare(SharePpm(166_667));
+        assert!(validate_inheritance(&inheritance));
+        assert_eq!(summarize(&inheritance, 100).total_share_bps, 10_000);
+
+        inheritance.beneficiaries[1].allocation = Allocation::PreciseShare(SharePpm(FULL_SHARE_PPM + 1));
+        assert!(!validate_inheritance(&inheritance));
+    }
+
+    #[test]
+    fn test_share_ppm_percent_conversions() {
+        assert_eq!(SharePpm::from_percent(100.0), Some(SharePpm(FULL_SHARE_PPM)));
+        assert_eq!(SharePpm::from_percent(100.0 / 3.0), Some(SharePpm(333_333)));
+        assert_eq!(SharePpm::from_percent(33.3334), Some(SharePpm(333_334)));
+        assert_eq!(SharePpm::from_percent(100.5), None);
+        assert_eq!(SharePpm::from_percent(-1.0), None);
+        assert_eq!(SharePpm::from_percent(f64::NAN), None);
+
+        assert_eq!(SharePpm(333_334).to_percent(), 33.3334);
+        assert_eq!(SharePpm::from_bps(2500), SharePpm(250_000));
+        assert_eq!(SharePpm(333_334).to_string(), "33.3334%");
+        assert_eq!(SharePpm(250_000).to_string(), "25%");
+        assert_eq!(SharePpm(10).to_string(), "0.001%");
+
+        // Allocating by ppm agrees with allocating by the equivalent basis points
+        let bps = [3333, 6667];
+        let ppm: Vec<u32> = bps.iter().map(|&b| SharePpm::from_bps(b).0).collect();
+        for rounding in [RoundingMode::FloorFirst, RoundingMode::LargestRemainder] {
+            assert_eq!(allocate_amounts(1_000_001, &bps, rounding), allocate_amounts_ppm(1_000_001, &ppm, rounding));
+        }
+    }
+
+    #[test]
+    fn test_checkin_frequency_policy() {
+        let mut inheritance = test_inheritance();
+        inheritance.policy_max_gap = 400;
+
+        // Gaps of 300, 500 and 100 blocks, given out of order
+        let history = [1400, 100, 900, 400];
+        let result = validate_checkin_frequency_policy(&inheritance, &history);
+        assert_eq!(
+            result,
+            CheckinPolicyResult {
+                compliant: false,
+                longest_gap_blocks: 500,
+                average_gap_blocks: 1300.0 / 3.0,
+                recommended_trigger_delay: 750,
+            }
+        );
+
+        inheritance.policy_max_gap = 500;
+        assert!(validate_checkin_frequency_policy(&inheritance, &history).compliant);
+
+        // Without a policy, or without enough history, everything complies
+        inheritance.policy_max_gap = 0;
+        assert!(validate_checkin_frequency_policy(&inheritance, &history).compliant);
+        inheritance.policy_max_gap = 400;
+        let result = validate_checkin_frequency_policy(&inheritance, &[100]);
+        assert!(result.compliant);
+        assert_eq!(result.average_gap_blocks, 0.0);
+
+        // The recommendation stays within the network's bounds
+        assert_eq!(result.recommended_trigger_delay, validation_rules::TESTNET_MIN);
+    }
+
+    #[test]
+    fn test_checkin_rejects_future_block() {
+        let app = test_app();
+        let input = test_inheritance();
+        let mut output = input.clone();
+        output.last_checkin_block = 1_000;
+        let tx = test_tx(vec![nft_charms(&app, &input)], vec![nft_charms(&app, &output)]);
+
+        // Checking in at the current height, or one already past
+        assert!(can_checkin(&app, &tx, &checkin_witness(&tx, 1_000)));
+        assert!(can_checkin(&app, &tx, &checkin_witness(&tx, 1_001)));
+
+        // Dating the check-in ahead of the chain is rejected, as is not saying where the chain is
+        assert!(!can_checkin(&app, &tx, &checkin_witness(&tx, 999)));
+        assert!(!can_checkin(&app, &tx, &Data::empty()));
+    }
+
+    #[test]
+    fn test_checkin_at_attested_height() {
+        let app = test_app();
+        let input = test_inheritance();
+        let mut output = input.clone();
+        output.last_checkin_block = CURRENT_BLOCK;
+        let tx = test_tx(vec![nft_charms(&app, &input)], vec![nft_charms(&app, &output)]);
+        let w = checkin_witness(&tx, CURRENT_BLOCK);
+        let checkin = operation_witness(Operation::Checkin, &w);
+        assert!(app_contract(&app, &tx, &block_height_input(CURRENT_BLOCK), &checkin));
+
+        // A public input that is neither empty nor a height is refused
+        assert!(!app_contract(&app, &tx, &Data::from(&"850000"), &checkin));
+
+        // The height comes from the public input: a check-in without one, or whose
+        // witness claims another, has no height to be checked against
+        assert!(!can_checkin_at(&app, &tx, None, &w));
+        assert!(!can_checkin_at(&app, &tx, Some(CURRENT_BLOCK + 1), &w));
+
+        let mut forged = output.clone();
+        forged.last_checkin_block = CURRENT_BLOCK + 100;
+        let tx = test_tx(vec![nft_charms(&app, &input)], vec![nft_charms(&app, &forged)]);
+        assert!(!can_checkin_at(&app, &tx, Some(CURRENT_BLOCK), &checkin_witness(&tx, CURRENT_BLOCK + 100)));
+    }
+
+    #[test]
+    fn test_update_cant_date_checkin_in_future() {
+        let app = test_app();
+        let input = test_inheritance();
+        let update = |output: &InheritanceContent, block_height: Option<u64>| {
+            let tx = test_tx(vec![nft_charms(&app, &input)], vec![nft_charms(&app, output)]);
+            check_update_beneficiaries(&mut Rules::proving(), &app, &tx, block_height, &update_witness(&tx))
+        };
+
+        // Moving the check-in up to the attested height is fine, one block past it isn't
+        let mut output = input.clone();
+        output.beneficiaries.reverse();
+        output.last_checkin_block = CURRENT_BLOCK;
+        assert_eq!(update(&output, Some(CURRENT_BLOCK)), Ok(()));
+        output.last_checkin_block = CURRENT_BLOCK + 1;
+        assert_eq!(update(&output, Some(CURRENT_BLOCK)), Err(RuleFailed::CheckinInFuture));
+        output.last_checkin_block = u64::MAX;
+        assert_eq!(update(&output, Some(CURRENT_BLOCK)), Err(RuleFailed::CheckinInFuture));
+
+        // Moving it at all takes an attested height; leaving it alone doesn't
+        output.last_checkin_block = CURRENT_BLOCK;
+        assert_eq!(update(&output, None), Err(RuleFailed::HeightNotAttested));
+        output.last_checkin_block = input.last_checkin_block;
+        assert_eq!(update(&output, None), Ok(()));
+    }
+
+    // Three taproot heirs (50/30/20%) who can trigger early with 60% of the estate
+    fn majority_inheritance() -> InheritanceContent {
+        let mut inheritance = test_inheritance();
+        inheritance.beneficiaries = vec![
+            taproot_beneficiary(0, 5000),
+            taproot_beneficiary(1, 3000),
+            taproot_beneficiary(2, 2000),
+        ];
+        inheritance.recovery_mode = Some(ContractRecoveryMode::BeneficiaryMajority { threshold_basis_points: 6000 });
+        inheritance
+    }
+
+    #[test]
+    fn test_beneficiary_majority_triggers_before_deadline() {
+        let app = test_app();
+        let inheritance = majority_inheritance();
+        assert!(validate_inheritance(&inheritance));
+
+        let plan = compute_distribution_plan(&inheritance, 1_000_000);
+        let tx = distribution_tx(&app, &inheritance, 1_000_000, &plan);
+        let msg = signing_message(Operation::TriggerDistribution, &tx, &inheritance);
+        let sig = |i| guardian_sign(i, &msg).1;
+        let early = |beneficiary_signatures| {
+            Data::from(&DistributionWitness {
+                current_block: Some(1000),
+                beneficiary_signatures,
+                ..Default::default()
+            })
+        };
+        assert!(!is_distributable(&inheritance, 1000));
+
+        // 80% of the estate signs: enough to trigger before the deadline
+        assert!(can_trigger_distribution(&app, &tx, &early(vec![sig(0), sig(1), vec![]])));
+
+        // 50% isn't, and neither is nobody
+        assert!(!can_trigger_distribution(&app, &tx, &early(vec![sig(0), vec![], vec![]])));
+        assert!(!can_trigger_distribution(&app, &tx, &early(vec![])));
+
+        // Signatures for another operation don't count
+        let checkin = signing_message(Operation::Checkin, &tx, &inheritance);
+        let checkin_sigs = vec![guardian_sign(0, &checkin).1, guardian_sign(1, &checkin).1, vec![]];
+        assert!(!can_trigger_distribution(&app, &tx, &early(checkin_sigs)));
+
+        // Without the mode, the heirs have to wait for the deadline like anyone else
+        let mut no_mode = inheritance.clone();
+        no_mode.recovery_mode = None;
+        let tx = distribution_tx(&app, &no_mode, 1_000_000, &plan);
+        let msg = signing_message(Operation::TriggerDistribution, &tx, &no_mode);
+        let all = vec![guardian_sign(0, &msg).1, guardian_sign(1, &msg).1, guardian_sign(2, &msg).1];
+        assert!(!can_trigger_distribution(&app, &tx, &early(all.clone())));
+        assert!(can_trigger_distribution(&app, &tx, &at_block(5000)));
+
+        // Leaving the height out doesn't skip the deadline: without one nothing is distributable
+        let no_height = Data::from(&DistributionWitness::default());
+        assert!(!can_trigger_distribution_at(&app, &tx, None, &no_height));
+        let unattested = Data::from(&DistributionWitness {
+            beneficiary_signatures: all,
+            ..Default::default()
+        });
+        assert!(!can_trigger_distribution_at(&app, &tx, None, &unattested));
+        assert!(!can_trigger_distribution_at(&app, &tx, Some(1000), &no_height));
+    }
+
+    #[test]
+    fn test_recovery_mode_validation() {
+        let mut inheritance = majority_inheritance();
+        for (threshold_basis_points, valid) in [(5000, false), (5001, true), (FULL_SHARE, true), (FULL_SHARE + 1, false)] {
+            inheritance.recovery_mode = Some(ContractRecoveryMode::BeneficiaryMajority { threshold_basis_points });
+            assert_eq!(validate_inheritance(&inheritance), valid, "{threshold_basis_points}");
+        }
+
+        inheritance.recovery_mode = Some(ContractRecoveryMode::CoSignerOverride);
+        assert_eq!(inheritance.validate(), Err(ValidationError::InvalidRecoveryMode));
+        inheritance.guardians = GUARDIAN_KEYS.iter().map(|key| key.to_string()).collect();
+        inheritance.guardian_threshold = 2;
+        assert_eq!(inheritance.validate(), Ok(()));
+
+        let owner_key = OwnerPubkey::try_from(decode_hex(&test_inheritance().owner_pubkey).unwrap()).unwrap();
+        inheritance.recovery_mode = Some(ContractRecoveryMode::FullRecovery { owner_key });
+        assert_eq!(inheritance.validate(), Ok(()));
+        inheritance.recovery_mode = Some(ContractRecoveryMode::FullRecovery { owner_key: OwnerPubkey([4; 33]) });
+        assert_eq!(inheritance.validate(), Err(ValidationError::InvalidRecoveryMode));
+    }
+
+    #[test]
+    fn test_batch_checks_in_two_contracts() {
+        let first = test_app();
+        let second = App {
+            identity: hash(&test_utxo_id(1).to_string()),
+            ..test_app()
+        };
+        let input = test_inheritance();
+        let mut checked_in = input.clone();
+        checked_in.last_checkin_block += 10;
+
+        let x = block_height_input(CURRENT_BLOCK);
+        let tx = test_tx(
+            vec![nft_charms(&first, &input), nft_charms(&second, &input)],
+            vec![nft_charms(&first, &checked_in), nft_charms(&second, &checked_in)],
+        );
+        let checkin = operation_witness(Operation::Checkin, &checkin_witness(&tx, CURRENT_BLOCK));
+        let contracts = [(first.clone(), checkin.clone()), (second.clone(), checkin)];
+        assert_eq!(validate_batch(&contracts, &tx, &x), vec![Ok(()), Ok(())]);
+
+        // One contract breaking its rules doesn't affect the other
+        let mut rewound = input.clone();
+        rewound.last_checkin_block -= 10;
+        let mut bad_second = tx.clone();
+        bad_second.outs[1] = nft_charms(&second, &rewound);
+        assert_eq!(
+            validate_batch(&contracts, &bad_second, &x),
+            vec![Ok(()), Err(InheritanceError::InvalidTransaction)]
+        );
+
+        // Both NFTs in one output: neither contract can be spent alone any more
+        let mut merged = tx.clone();
+        let mut both = nft_charms(&first, &checked_in);
+        both.extend(nft_charms(&second, &checked_in));
+        merged.outs = vec![both];
+        assert_eq!(
+            validate_batch(&contracts, &merged, &x),
+            vec![Err(InheritanceError::SharedUtxo), Err(InheritanceError::SharedUtxo)]
+        );
+    }
+
+    #[test]
+    fn test_missing_output_nft_is_reported() {
+        let app = test_app();
+        let input = test_inheritance();
+        let mut output = input.clone();
+        output.last_checkin_block = CURRENT_BLOCK;
+        let x = block_height_input(CURRENT_BLOCK);
+
+        let tx = test_tx(vec![nft_charms(&app, &input)], vec![nft_charms(&app, &output)]);
+        let checkin = operation_witness(Operation::Checkin, &checkin_witness(&tx, CURRENT_BLOCK));
+        let update = operation_witness(Operation::UpdateBeneficiaries, &update_witness(&tx));
+        assert_eq!(validate_transaction(&app, &tx, &x, &checkin), Ok(()));
+
+        // The wallet forgot the continuation output
+        let mut forgot = tx.clone();
+        forgot.outs = vec![Charms::new()];
+        let missing = Err(InheritanceError::Contract(ContractError::MissingOutputNft));
+        assert_eq!(validate_transaction(&app, &forgot, &x, &checkin), missing);
+        assert_eq!(validate_transaction(&app, &forgot, &x, &update), missing);
+
+        let mut twice = tx.clone();
+        twice.outs.push(nft_charms(&app, &output));
+        assert_eq!(
+            validate_transaction(&app, &twice, &x, &checkin),
+            Err(InheritanceError::Contract(ContractError::ExtraOutputNfts { count: 2 }))
+        );
+
+        // Other failures are still only reported as invalid
+        let unsigned = operation_witness(Operation::Checkin, &create_multisig_checkin_witness(&[], CURRENT_BLOCK));
+        assert_eq!(validate_transaction(&app, &tx, &x, &unsigned), Err(InheritanceError::InvalidTransaction));
+    }
+
+    #[test]
+    fn test_evaluate_names_the_broken_rule() {
+        let app = test_app();
+        let input = test_inheritance();
+        let x = block_height_input(CURRENT_BLOCK);
+        let checkin = |output: &InheritanceContent| {
+            let tx = test_tx(vec![nft_charms(&app, &input)], vec![nft_charms(&app, output)]);
+            let w = operation_witness(Operation::Checkin, &checkin_witness(&tx, CURRENT_BLOCK));
+            evaluate(&app, &tx, &x, &w)
+        };
+
+        // A valid check-in names its operation
+        let mut output = input.clone();
+        output.last_checkin_block = CURRENT_BLOCK;
+        assert_eq!(checkin(&output), Ok(Operation::Checkin));
+        let tx = test_tx(vec![nft_charms(&app, &input)], vec![nft_charms(&app, &output)]);
+        assert!(app_contract(&app, &tx, &x, &operation_witness(Operation::Checkin, &checkin_witness(&tx, CURRENT_BLOCK))));
+
+        // Check-ins that go nowhere, or too far, or change something else
+        assert_eq!(checkin(&input), Err(ContractError::Checkin(RuleFailed::LastCheckinNotIncreased)));
+        output.last_checkin_block = CURRENT_BLOCK + 1;
+        assert_eq!(checkin(&output), Err(ContractError::Checkin(RuleFailed::CheckinInFuture)));
+        output.last_checkin_block = CURRENT_BLOCK;
+        output.trigger_delay_blocks += 1;
+        assert_eq!(checkin(&output), Err(ContractError::Checkin(RuleFailed::FieldChanged)));
+
+        // An unsigned check-in, and one without an attested height
+        output.trigger_delay_blocks = input.trigger_delay_blocks;
+        let tx = test_tx(vec![nft_charms(&app, &input)], vec![nft_charms(&app, &output)]);
+        let unsigned = operation_witness(Operation::Checkin, &create_multisig_checkin_witness(&[], CURRENT_BLOCK));
+        assert_eq!(evaluate(&app, &tx, &x, &unsigned), Err(ContractError::Checkin(RuleFailed::Signature)));
+        let w = operation_witness(Operation::Checkin, &checkin_witness(&tx, CURRENT_BLOCK));
+        assert_eq!(
+            evaluate(&app, &tx, &Data::empty(), &w),
+            Err(ContractError::Checkin(RuleFailed::HeightNotAttested))
+        );
+
+        // The same transaction, claimed as an update, breaks the update's rules
+        let w = operation_witness(Operation::UpdateBeneficiaries, &Data::empty());
+        assert_eq!(evaluate(&app, &tx, &x, &w), Err(ContractError::UpdateBeneficiaries(RuleFailed::Witness)));
+
+        // Distributing before the deadline
+        let plan = compute_distribution_plan(&input, 1_000_000);
+        let tx = distribution_tx(&app, &input, 1_000_000, &plan);
+        let w = operation_witness(Operation::TriggerDistribution, &at_block(1000));
+        assert_eq!(
+            evaluate(&app, &tx, &block_height_input(1000), &w),
+            Err(ContractError::TriggerDistribution(RuleFailed::DeadlineNotPassed))
+        );
+
+        // Creating a contract from UTXOs that don't make its identity
+        let (create_app, tx) = create_tx(&[test_utxo_id(0)], 1);
+        let w = operation_witness(Operation::Create, &Data::from(&CreateWitness::Single(test_utxo_id(1).to_string())));
+        assert_eq!(evaluate(&create_app, &tx, &x, &w), Err(ContractError::Create(RuleFailed::IdentityMismatch)));
+
+        // Failures before any operation is looked at
+        let w = operation_witness(Operation::Checkin, &checkin_witness(&tx, CURRENT_BLOCK));
+        assert_eq!(evaluate(&app, &tx, &Data::from(&"850000"), &w), Err(ContractError::InvalidPublicInput));
+        assert_eq!(evaluate(&app, &tx, &x, &Data::empty()), Err(ContractError::InvalidWitness));
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn test_failed_rules_are_traced() {
+        use std::sync::{Arc, Mutex};
+        use tracing::field::{Field, Visit};
+        use tracing::span::{Attributes, Id, Record};
+        use tracing::{Event, Metadata, Subscriber};
+
+        // Keeps every event's fields, as `name=value`
+        type Events = Arc<Mutex<Vec<Vec<String>>>>;
+        struct Recorder(Events);
+        struct Fields(Vec<String>);
+
+        impl Visit for Fields {
+            fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+                self.0.push(format!("{}={:?}", field.name(), value));
+            }
+        }
+
+        impl Subscriber for Recorder {
+            fn enabled(&self, _: &Metadata<'_>) -> bool {
+                true
+            }
+            fn new_span(&self, _: &Attributes<'_>) -> Id {
+                Id::from_u64(1)
+            }
+            fn record(&self, _: &Id, _: &Record<'_>) {}
+            fn record_follows_from(&self, _: &Id, _: &Id) {}
+            fn event(&self, event: &Event<'_>) {
+                let mut fields = Fields(Vec::new());
+                event.record(&mut fields);
+                self.0.lock().unwrap().push(fields.0);
+            }
+            fn enter(&self, _: &Id) {}
+            fn exit(&self, _: &Id) {}
+        }
+
+        // A check-in one block into the future
+        let app = test_app();
+        let input = test_inheritance();
+        let mut output = input.clone();
+        output.last_checkin_block = CURRENT_BLOCK + 1;
+        let tx = test_tx(vec![nft_charms(&app, &input)], vec![nft_charms(&app, &output)]);
+        let w = operation_witness(Operation::Checkin, &checkin_witness(&tx, CURRENT_BLOCK));
+
+        let events = Events::default();
+        let outcome = tracing::subscriber::with_default(Recorder(events.clone()), || {
+            evaluate(&app, &tx, &block_height_input(CURRENT_BLOCK), &w)
+        });
+        assert_eq!(outcome, Err(ContractError::Checkin(RuleFailed::CheckinInFuture)));
+
+        // One event for the broken rule, with the heights that broke it, then the rejection
+        let events = events.lock().unwrap();
+        assert_eq!(events.len(), 2, "{events:?}");
+        for field in ["message=rule failed", "rule=CheckinInFuture", "new_checkin_block=850001", "current_block=850000"] {
+            assert!(events[0].contains(&field.to_string()), "{field} not in {:?}", events[0]);
+        }
+        for field in ["message=operation rejected", "error=Checkin(CheckinInFuture)"] {
+            assert!(events[1].contains(&field.to_string()), "{field} not in {:?}", events[1]);
+        }
+    }
+
+    #[test]
+    fn test_detect_operation() {
+        let app = test_app();
+        let input = test_inheritance();
+        let x = block_height_input(CURRENT_BLOCK);
+        let mut output = input.clone();
+        output.last_checkin_block = CURRENT_BLOCK;
+        let tx = test_tx(vec![nft_charms(&app, &input)], vec![nft_charms(&app, &output)]);
+
+        // The same content change is a check-in or an update, depending on what the owner signed
+        assert_eq!(detect_operation(&app, &tx, &x, &checkin_witness(&tx, CURRENT_BLOCK)), Some(Operation::Checkin));
+        assert_eq!(detect_operation(&app, &tx, &x, &update_witness(&tx)), Some(Operation::UpdateBeneficiaries));
+
+        // A witness carrying both signatures satisfies both, and the check-in wins
+        #[derive(Serialize)]
+        struct BothWitness {
+            signatures: SignerSignatures,
+            current_block: u64,
+            signature: Vec<u8>,
+        }
+        let both = Data::from(&BothWitness {
+            signatures: vec![(owner_key(), owner_sign(&signing_message(Operation::Checkin, &tx, &output)))],
+            current_block: CURRENT_BLOCK,
+            signature: owner_sign(&signing_message(Operation::UpdateBeneficiaries, &tx, &output)),
+        });
+        assert!(can_checkin_at(&app, &tx, Some(CURRENT_BLOCK), &both));
+        assert!(can_update_beneficiaries(&app, &tx, &both));
+        assert_eq!(detect_operation(&app, &tx, &x, &both), Some(Operation::Checkin));
+
+        // Distributions and creations are told apart by their transactions
+        let plan = compute_distribution_plan(&input, 1_000_000);
+        let tx = distribution_tx(&app, &input, 1_000_000, &plan);
+        assert_eq!(
+            detect_operation(&app, &tx, &x, &at_block(CURRENT_BLOCK)),
+            Some(Operation::TriggerDistribution)
+        );
+        let (create_app, create) = create_tx(&[test_utxo_id(0)], 1);
+        let w = Data::from(&CreateWitness::Single(test_utxo_id(0).to_string()));
+        let created_at = block_height_input(100);
+        assert_eq!(detect_operation(&create_app, &create, &created_at, &w), Some(Operation::Create));
+
+        // Nothing matches an invalid spend, or a height-bound one without its height
+        let tx = test_tx(vec![nft_charms(&app, &input)], vec![nft_charms(&app, &output)]);
+        assert_eq!(detect_operation(&app, &tx, &x, &Data::empty()), None);
+        assert_eq!(detect_operation(&app, &tx, &Data::empty(), &checkin_witness(&tx, CURRENT_BLOCK)), None);
+    }
+
+    #[test]
+    fn test_diagnose_reports_every_broken_rule() {
+        let app = test_app();
+        let input = test_inheritance();
+        let x = block_height_input(CURRENT_BLOCK);
+
+        // An update that changes the delay AND leaves the shares summing to 95%
+        let mut output = input.clone();
+        output.trigger_delay_blocks += 1;
+        output.beneficiaries[0].allocation = Allocation::Share(5500);
+        let tx = test_tx(vec![nft_charms(&app, &input)], vec![nft_charms(&app, &output)]);
+        let w = operation_witness(Operation::UpdateBeneficiaries, &update_witness(&tx));
+
+        let report = diagnose(&app, &tx, &x, &w);
+        assert_eq!(report.outcome, Err(ContractError::UpdateBeneficiaries(RuleFailed::InvalidBeneficiaries)));
+        assert_eq!(report.outcome, evaluate(&app, &tx, &x, &w));
+
+        let update = report.operations.iter().find(|r| r.claimed).unwrap();
+        assert_eq!(update.operation, Operation::UpdateBeneficiaries);
+        let broken: Vec<RuleFailed> = update.rules.iter().filter(|c| !c.passed).map(|c| c.rule).collect();
+        assert_eq!(broken, vec![RuleFailed::InvalidBeneficiaries, RuleFailed::FieldChanged]);
+        assert_eq!(update.rules.last().map(|c| (c.rule, c.passed)), Some((RuleFailed::Signature, true)));
+
+        // The other operations were checked too, with the values they failed on
+        let checkin = report.operations.iter().find(|r| r.operation == Operation::Checkin).unwrap();
+        assert!(!checkin.claimed);
+        assert_eq!(checkin.broken, Some(RuleFailed::LastCheckinNotIncreased));
+        let check = checkin.rules.iter().find(|c| c.rule == RuleFailed::LastCheckinNotIncreased).unwrap();
+        assert_eq!(
+            check.context,
+            vec![
+                ("last_checkin_block".to_string(), "100".to_string()),
+                ("new_checkin_block".to_string(), "100".to_string()),
+            ]
+        );
+
+        // Frontends get it as JSON
+        let json = serde_json::to_string(&report).unwrap();
+        assert_eq!(serde_json::from_str::<ValidationReport>(&json).unwrap(), report);
+    }
+
+    #[test]
+    fn test_diagnose_agrees_with_evaluate() {
+        let app = test_app();
+        let input = test_inheritance();
+        let x = block_height_input(CURRENT_BLOCK);
+        let transition = |output: &InheritanceContent| test_tx(vec![nft_charms(&app, &input)], vec![nft_charms(&app, output)]);
+
+        let mut checked_in = input.clone();
+        checked_in.last_checkin_block = CURRENT_BLOCK;
+        let mut future = input.clone();
+        future.last_checkin_block = CURRENT_BLOCK + 1;
+        let mut reshuffled = input.clone();
+        reshuffled.beneficiaries.reverse();
+        let plan = compute_distribution_plan(&input, 1_000_000);
+        let distribution = distribution_tx(&app, &input, 1_000_000, &plan);
+        let (create_app, create) = create_tx(&[test_utxo_id(0)], 1);
+        let funding = Data::from(&CreateWitness::Single(test_utxo_id(0).to_string()));
+
+        let checkin = |tx: &Transaction, block| operation_witness(Operation::Checkin, &checkin_witness(tx, block));
+        let update = |tx: &Transaction| operation_witness(Operation::UpdateBeneficiaries, &update_witness(tx));
+        let corpus: Vec<(&App, Transaction, Data, Data)> = vec![
+            (&app, transition(&checked_in), x.clone(), checkin(&transition(&checked_in), CURRENT_BLOCK)),
+            (&app, transition(&checked_in), Data::empty(), checkin(&transition(&checked_in), CURRENT_BLOCK)),
+            (&app, transition(&future), x.clone(), checkin(&transition(&future), CURRENT_BLOCK)),
+            (&app, transition(&input), x.clone(), checkin(&transition(&input), CURRENT_BLOCK)),
+            (&app, transition(&reshuffled), x.clone(), update(&transition(&reshuffled))),
+            (&app, transition(&reshuffled), x.clone(), checkin(&transition(&reshuffled), CURRENT_BLOCK)),
+            (&app, transition(&checked_in), x.clone(), operation_witness(Operation::RecordMiss, &Data::empty())),
+            (&app, distribution.clone(), x.clone(), operation_witness(Operation::TriggerDistribution, &at_block(CURRENT_BLOCK))),
+            (&app, distribution.clone(), block_height_input(1000), operation_witness(Operation::TriggerDistribution, &at_block(1000))),
+            (&app, distribution, x.clone(), operation_witness(Operation::Migrate, &Data::empty())),
+            (&create_app, create.clone(), x.clone(), operation_witness(Operation::Create, &funding)),
+            (&create_app, create.clone(), x.clone(), operation_witness(Operation::Checkin, &funding)),
+            (&create_app, create, Data::from(&"850000"), operation_witness(Operation::Create, &funding)),
+            (&app, transition(&checked_in), x.clone(), Data::empty()),
+        ];
+
+        for (app, tx, x, w) in &corpus {
+            let report = diagnose(app, tx, x, w);
+            assert_eq!(report.outcome, evaluate(app, tx, x, w), "{tx:?}");
+
+            // Each operation's first broken rule is the one checking it alone would stop at
+            for operation in &report.operations {
+                let alone = evaluate(app, tx, x, &operation_witness(operation.operation, &w.value::<OperationWitness>().unwrap().witness));
+                assert_eq!(alone.err(), operation.broken.map(|rule| ContractError::rule_failed(operation.operation, rule)));
+            }
+        }
+    }
+
+    #[test]
+    fn test_batch_checkin_reports_each_contract() {
+        let apps: Vec<App> = (0..3)
+            .map(|i| App {
+                identity: hash(&test_utxo_id(i).to_string()),
+                ..test_app()
+            })
+            .collect();
+        let input = test_inheritance();
+        let mut checked_in = input.clone();
+        checked_in.last_checkin_block = CURRENT_BLOCK;
+
+        let tx = test_tx(
+            apps.iter().map(|app| nft_charms(app, &input)).collect(),
+            apps.iter().map(|app| nft_charms(app, &checked_in)).collect(),
+        );
+        let checkin_at = |current_block| {
+            let (signatures, current_block) = parse_multisig_checkin_witness(&checkin_witness(&tx, current_block)).unwrap();
+            WitnessData::CheckIn { signatures, current_block }
+        };
+        let ids: Vec<ContractId> = apps.iter().map(ContractId::from).collect();
+
+        // The second check-in claims a later height than the transaction's, which
+        // would let it through if it were checked at its own
+        let x = block_height_input(CURRENT_BLOCK);
+        let proofs = BatchCheckinProof {
+            contracts: vec![
+                (ids[0], checkin_at(CURRENT_BLOCK)),
+                (ids[1], checkin_at(CURRENT_BLOCK + 1)),
+                (ids[2], checkin_at(CURRENT_BLOCK)),
+            ],
+        };
+        assert_eq!(
+            validate_batch_checkin(&proofs, &tx, &x, &apps),
+            vec![(ids[0], true), (ids[1], false), (ids[2], true)]
+        );
+
+        // Without an attested height, none of them is
+        assert!(validate_batch_checkin(&proofs, &tx, &Data::empty(), &apps).iter().all(|(_, valid)| !valid));
+
+        // A contract the caller has no app for can't be checked
+        assert_eq!(validate_batch_checkin(&proofs, &tx, &x, &apps[..2])[2], (ids[2], false));
+
+        // The aggregate carries the witness each contract reads
+        let aggregate: Vec<(ContractId, Data)> = aggregate_batch_witness(&proofs).value().unwrap();
+        assert_eq!(aggregate.len(), 3);
+        assert_eq!(aggregate[2], (ids[2], operation_witness(Operation::Checkin, &proofs.contracts[2].1.to_data())));
+    }
+
+    // Frozen vector for canonical_bytes; other implementations must reproduce it exactly
+    fn canonical_vector() -> InheritanceContent {
+        InheritanceContent {
+            owner_pubkey: "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798".to_string(),
+            last_checkin_block: 100,
+            trigger_delay_blocks: 4320,
+            beneficiaries: vec![
+                Beneficiary {
+                    payee: Payee::from("tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx"),
+                    allocation: Allocation::Share(6000),
+                    vesting_delay_blocks: 0,
+                },
+                Beneficiary {
+                    payee: Payee::from("tb1pqqqqp399et2xygdj5xreqhjjvcmzhxw4aywxecjdzew6hylgvsesf3hn0c"),
+                    allocation: Allocation::Share(4000),
+                    vesting_delay_blocks: 144,
+                },
+            ],
+            status: InheritanceStatus::Active,
+            version: 3,
+            total_locked_sats: 1_000_000,
+            network: Network::Testnet,
+            created_at_block: 100,
+            metadata: BTreeMap::from([
+                ("note".to_string(), "for the kids".to_string()),
+                ("letter".to_string(), "see drawer".to_string()),
+            ]),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_canonical_bytes_vector() {
+        let expected = concat!(
+            "ab6673746174757366416374697665676e6574776f726b67546573746e65746776657273696f6e03686d657461646174",
+            "61a2646e6f74656c666f7220746865206b696473666c65747465726a736565206472617765726c6f776e65725f707562",
+            "6b6579784230323739626536363765663964636262616335356130363239356365383730623037303239626663646232",
+            "64636532386439353966323831356231366638313739386d62656e6566696369617269657382a2676164647265737378",
+            "2a7462317177353038643671656a7874646734793572337a6172766172793063357877376b78706a7a73786a616c6c6f",
+            "636174696f6ea1655368617265191770a36761646472657373783e746231707171717170333939657432787967646a35",
+            "78726571686a6a76636d7a687877346179777865636a647a65773668796c67767365736633686e30636a616c6c6f6361",
+            "74696f6ea1655368617265190fa07476657374696e675f64656c61795f626c6f636b73189070637265617465645f6174",
+            "5f626c6f636b186471746f74616c5f6c6f636b65645f736174731a000f424072677561726469616e5f7468726573686f",
+            "6c6400726c6173745f636865636b696e5f626c6f636b186474747269676765725f64656c61795f626c6f636b731910e0",
+        );
+        let bytes = canonical_vector().canonical_bytes();
+        assert_eq!(bytes, decode_hex(expected).unwrap());
+        assert_eq!(
+            B32(Sha256::digest(&bytes).into()).to_string(),
+            "1cb579d221f09433f2f6efc67131bb22518a910716d611c8cc1661796ce94ce4"
+        );
+        assert_eq!(
+            canonical_vector().content_hash().to_string(),
+            "39830d232996588e425edbe72db7dd9a975978e808087288d93d07f0c258e6ab"
+        );
+
+        // Decoding the canonical bytes and encoding them again reproduces them exactly
+        let decoded: InheritanceContent = ciborium::from_reader(bytes.as_slice()).unwrap();
+        assert_eq!(decoded, canonical_vector());
+        assert_eq!(decoded.canonical_bytes(), bytes);
+
+        // ...as does decoding the NFT payload, whose keys are in declaration order
+        let payload = Data::from(&canonical_vector()).bytes();
+        assert_ne!(payload, bytes);
+        let decoded: InheritanceContent = ciborium::from_reader(payload.as_slice()).unwrap();
+        assert_eq!(Data::from(&decoded).bytes(), payload);
+        assert_eq!(decoded.canonical_bytes(), bytes);
+    }
+
+    #[test]
+    fn test_plan_commitment_vector() {
+        let plan = compute_distribution_plan(&test_inheritance(), 1_000_000);
+        assert_eq!(
+            plan_commitment(&plan).to_string(),
+            "8487ac5996992ddbcef21381dfc7763ecf8ee3e825c957dd8fd0195de37bfbb1"
+        );
+
+        // Tagged, so it's never the plain SHA-256 of the same bytes
+        assert_ne!(plan_commitment(&plan), B32(Sha256::digest(canonical_cbor(&plan)).into()));
+    }
+
+    #[test]
+    fn test_min_expected_funding_rejects_dust_shares() {
+        // A 1% heir gets 10,000 sats of a million, but only 500 of 50,000
+        let mut inheritance = test_inheritance();
+        inheritance.beneficiaries[0].allocation = Allocation::Share(9900);
+        inheritance.beneficiaries[1].allocation = Allocation::Share(100);
+        assert_eq!(inheritance.validate(), Ok(()));
+
+        inheritance.min_expected_funding = 1_000_000;
+        assert_eq!(inheritance.validate(), Ok(()));
+
+        inheritance.min_expected_funding = 50_000;
+        assert_eq!(inheritance.validate(), Err(ValidationError::DustAtExpectedFunding { index: 1 }));
+
+        // Fixed amounts are paid first, so they shrink what the shares split
+        inheritance.min_expected_funding = 100_000;
+        assert_eq!(inheritance.validate(), Ok(()));
+        inheritance.total_locked_sats = 1_000_000;
+        inheritance.beneficiaries.push(Beneficiary {
+            payee: Payee::from("mipcBbFg9gMiCh81Kj8tqqdgoZub1ZJRfn"),
+            allocation: Allocation::Fixed(60_000),
+            vesting_delay_blocks: 0,
+        });
+        assert_eq!(inheritance.validate(), Err(ValidationError::DustAtExpectedFunding { index: 1 }));
+    }
+
+    #[test]
+    fn test_hash_beneficiary_list_vector() {
+        let beneficiaries = test_inheritance().beneficiaries;
+        assert_eq!(
+            hash_beneficiary_list(&beneficiaries).to_string(),
+            "00195ed79243f07baa5c5411bb95cc34200984630a29a2e2b0b8f84175048f10"
+        );
+
+        // Both orders of the same two beneficiaries hash alike
+        let reversed: Vec<Beneficiary> = beneficiaries.iter().rev().cloned().collect();
+        assert_eq!(hash_beneficiary_list(&reversed), hash_beneficiary_list(&beneficiaries));
+
+        // ...and so do addresses that only differ before sanitizing
+        let mut unsanitized = beneficiaries.clone();
+        unsanitized[0].payee = Payee::from(" TB1QW508D6QEJXTDG4Y5R3ZARVARY0C5XW7KXPJZSX ");
+        assert_eq!(hash_beneficiary_list(&unsanitized), hash_beneficiary_list(&beneficiaries));
+
+        // Any change to an entry changes the hash
+        let mut changed = beneficiaries.clone();
+        changed[0].allocation = Allocation::Share(6001);
+        assert_ne!(hash_beneficiary_list(&changed), hash_beneficiary_list(&beneficiaries));
+    }
+
+    #[test]
+    fn test_content_hash_ignores_construction_path() {
+        let expected = canonical_vector().content_hash();
+
+        // Decoded from the canonical bytes, from the NFT payload, and from JSON
+        let bytes = canonical_vector().canonical_bytes();
+        let canonical: InheritanceContent = ciborium::from_reader(bytes.as_slice()).unwrap();
+        let payload = migrate_to_latest(&Data::from(&canonical_vector())).unwrap();
+        let json = serde_json::to_string(&canonical_vector()).unwrap();
+        let json: InheritanceContent = serde_json::from_str(&json).unwrap();
+        for content in [canonical, payload, json] {
+            assert_eq!(content.content_hash(), expected);
+        }
+
+        // Built up field by field, with the metadata inserted in the other order
+        let mut built = InheritanceContent {
+            owner_pubkey: canonical_vector().owner_pubkey,
+            status: InheritanceStatus::Active,
+            version: 3,
+            ..Default::default()
+        };
+        built.metadata.insert("letter".to_string(), "see drawer".to_string());
+        built.metadata.insert("note".to_string(), "for the kids".to_string());
+        built.beneficiaries = canonical_vector().beneficiaries;
+        built.created_at_block = 100;
+        built.last_checkin_block = 100;
+        built.total_locked_sats = 1_000_000;
+        built.trigger_delay_blocks = 4320;
+        assert_eq!(built.content_hash(), expected);
+
+        // Any change to a field is a different contract
+        built.total_locked_sats += 1;
+        assert_ne!(built.content_hash(), expected);
+    }
+
+    #[test]
+    fn test_sanitize_beneficiary_address() {
+        let p2wpkh = "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx";
+        let taproot = "tb1pqqqqp399et2xygdj5xreqhjjvcmzhxw4aywxecjdzew6hylgvsesf3hn0c";
+        let legacy = "mipcBbFg9gMiCh81Kj8tqqdgoZub1ZJRfn";
+
+        // Pasted with a trailing newline, in uppercase, or with a capitalized prefix
+        assert_eq!(sanitize_beneficiary_address("tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx\n"), p2wpkh);
+        assert_eq!(sanitize_beneficiary_address(&p2wpkh.to_uppercase()), p2wpkh);
+        assert_eq!(sanitize_beneficiary_address("Tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx"), p2wpkh);
+
+        // Already canonical, and base58, which is case-sensitive
+        assert_eq!(sanitize_beneficiary_address(taproot), taproot);
+        assert_eq!(sanitize_beneficiary_address(&format!("  {legacy} ")), legacy);
+
+        // The contract reads a pasted address as the clean one...
+        let mut inheritance = test_inheritance();
+        inheritance.beneficiaries[0].payee = Payee::from("tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx\n");
+        assert!(validate_inheritance(&inheritance));
+        assert_eq!(inheritance.beneficiaries[0].payee.fixed_script(), Payee::from(p2wpkh).fixed_script());
+        assert!(address_on_network(&p2wpkh.to_uppercase(), Network::Testnet));
+
+        // ...and whitespace alone is still no address
+        inheritance.beneficiaries[0].payee = Payee::from(" \n");
+        assert!(!validate_inheritance(&inheritance));
+    }
+
+    #[test]
+    fn test_supported_address_classifier() {
+        let cases = [
+            ("1BgGZ9tcN4rm9KBzDn7KprQz87SZ26SAMH", Network::Mainnet, SupportedAddress::P2pkh),
+            ("mipcBbFg9gMiCh81Kj8tqqdgoZub1ZJRfn", Network::Testnet, SupportedAddress::P2pkh),
+            ("3J98t1WpEZ73CNmQviecrnyiWrnqRhWNLy", Network::Mainnet, SupportedAddress::P2sh),
+            ("2MzQwSSnBHWHqSAqtTVQ6v47XtaisrJa1Vc", Network::Regtest, SupportedAddress::P2sh),
+            ("bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4", Network::Mainnet, SupportedAddress::P2wpkh),
+            ("tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx", Network::Signet, SupportedAddress::P2wpkh),
+            (
+                "tb1qrp33g0q5c5txsp9arysrx4k6zdkfs4nce4xj0gdcccefvpysxf3q0sl5k7",
+                Network::Testnet,
+                SupportedAddress::P2wsh,
+            ),
+            (
+                "bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqzk5jj0",
+                Network::Mainnet,
+                SupportedAddress::P2tr,
+            ),
+            (
+                "bcrt1pqqqqp399et2xygdj5xreqhjjvcmzhxw4aywxecjdzew6hylgvsesyga46z",
+                Network::Regtest,
+                SupportedAddress::P2tr,
+            ),
+        ];
+        for (address, network, kind) in cases {
+            assert_eq!(SupportedAddress::classify(address, network), Some(kind), "{address}");
+
+            // ...as pasted, too, but only on its own network
+            assert_eq!(SupportedAddress::classify(&format!(" {address}\n"), network), Some(kind));
+            let other = if network == Network::Mainnet { Network::Testnet } else { Network::Mainnet };
+            assert_eq!(SupportedAddress::classify(address, other), None, "{address}");
+        }
+
+        // Witness versions and programs nothing pays to yet, and anything that isn't an address
+        assert_eq!(SupportedAddress::classify("bc1zw508d6qejxtdg4y5r3zarvaryvaxxpcs", Network::Mainnet), None);
+        assert_eq!(SupportedAddress::classify("tb1p123", Network::Testnet), None);
+        assert_eq!(SupportedAddress::classify("", Network::Testnet), None);
+
+        // Beneficiaries are held to it on the contract's network
+        let mut inheritance = test_inheritance();
+        inheritance.beneficiaries[1].payee = Payee::from("bc1zw508d6qejxtdg4y5r3zarvaryvaxxpcs");
+        assert_eq!(
+            inheritance.validate(),
+            Err(ValidationError::Beneficiary { index: 1, error: BeneficiaryError::UnsupportedAddress })
+        );
+        inheritance.beneficiaries[1].payee = Payee::from("tb1qrp33g0q5c5txsp9arysrx4k6zdkfs4nce4xj0gdcccefvpysxf3q0sl5k7");
+        assert_eq!(inheritance.validate(), Ok(()));
     }
 }
@@ -0,0 +1,99 @@
+//! How close a contract is to expiring, now and over time
+//!
+//! For wallets and monitoring dashboards that show an owner where they stand
+//! between check-ins. Nothing here is part of the on-chain contract.
+
+use crate::{deadline_block, is_expired, InheritanceContent, InheritanceStatus};
+use serde::{Deserialize, Serialize};
+
+// How close a contract is to being triggerable, by the share of the trigger delay left
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LivenessStatus {
+    Safe,      // More than half the delay left
+    Warning,   // 10-50% left
+    Critical,  // Under 10% left, up to and including the deadline block
+    Expired,   // Past the deadline, or no longer Active
+}
+
+// A contract's liveness at a given height
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct LivenessReport {
+    pub status: LivenessStatus,
+    pub deadline_block: u64,           // Expired once the chain is past this height
+    pub blocks_until_deadline: u64,    // 0 once the deadline has been reached
+}
+
+/// How close the contract is to expiring at `current_block`
+///
+/// The share left is measured against trigger_delay_blocks, as for check-in
+/// reminders, so a decaying delay only brings the deadline closer. Contracts that
+/// are no longer Active are Expired.
+pub fn check_contract_liveness(inheritance: &InheritanceContent, current_block: u64) -> LivenessReport {
+    let deadline = deadline_block(inheritance, current_block);
+    let blocks_until_deadline = deadline.saturating_sub(current_block);
+    let delay = inheritance.trigger_delay_blocks as u128;
+    let left = blocks_until_deadline as u128;
+
+    let status = if inheritance.status != InheritanceStatus::Active || is_expired(inheritance, current_block) {
+        LivenessStatus::Expired
+    } else if left * 2 > delay {
+        LivenessStatus::Safe
+    } else if left * 10 >= delay {
+        LivenessStatus::Warning
+    } else {
+        LivenessStatus::Critical
+    };
+
+    LivenessReport {
+        status,
+        deadline_block: deadline,
+        blocks_until_deadline,
+    }
+}
+
+/// The contract's liveness from `start_block` to `end_block` (inclusive), every `step` blocks
+///
+/// Besides the sampled heights, the timeline holds the exact block each status
+/// change happens at, so no transition falls between two samples. Nothing changes
+/// without a check-in, so statuses only ever get worse along it. Empty if
+/// `end_block < start_block`; a `step` of 0 counts as 1.
+pub fn simulate_inheritance_timeline(
+    inheritance: &InheritanceContent,
+    start_block: u64,
+    end_block: u64,
+    step: u64,
+) -> Vec<(u64, LivenessReport)> {
+    let mut timeline: Vec<(u64, LivenessReport)> = Vec::new();
+    let mut block = start_block;
+    while block <= end_block {
+        let report = check_contract_liveness(inheritance, block);
+
+        // Find every transition since the previous sample
+        while let Some((previous, previous_report)) = timeline.last() {
+            if previous_report.status == report.status {
+                break;
+            }
+            let (mut low, mut high) = (*previous, block);
+            let status = previous_report.status;
+            while high - low > 1 {
+                let mid = low + (high - low) / 2;
+                if check_contract_liveness(inheritance, mid).status == status {
+                    low = mid;
+                } else {
+                    high = mid;
+                }
+            }
+            if high == block {
+                break;
+            }
+            timeline.push((high, check_contract_liveness(inheritance, high)));
+        }
+
+        timeline.push((block, report));
+        match block.checked_add(step.max(1)) {
+            Some(next) => block = next,
+            None => break,
+        }
+    }
+    timeline
+}
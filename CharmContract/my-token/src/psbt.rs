@@ -0,0 +1,210 @@
+//! Carrying a contract inside a PSBT
+//!
+//! Wallets pass unsigned transactions around as PSBTs (BIP-174). The contract's
+//! content and the operation's witness ride along as proprietary global entries
+//! under the "charmvault" prefix, which PSBT-aware wallets keep intact. The PSBT
+//! itself is read and written with the bitcoin crate, so this module is only built
+//! with the `wallet` feature. Nothing here is part of the on-chain contract.
+
+use crate::{migrate_to_latest, parse_multisig_checkin_witness, InheritanceContent, WitnessData};
+use bitcoin::psbt::raw::ProprietaryKey;
+use bitcoin::psbt::Psbt;
+use charms_sdk::data::Data;
+
+/// Identifier of this contract's proprietary PSBT entries
+pub const VENDOR_PREFIX: &[u8] = b"charmvault";
+
+// Subtypes of the proprietary entries; the witness subtype says which operation it's for
+const SUBTYPE_CONTENT: u8 = 0;
+const SUBTYPE_CREATE_WITNESS: u8 = 1;
+const SUBTYPE_CHECKIN_WITNESS: u8 = 2;
+const SUBTYPE_DISTRIBUTION_WITNESS: u8 = 3;
+
+// Why a contract couldn't be read from (or written into) a PSBT
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PsbtError {
+    Malformed,              // Not a PSBT the bitcoin crate can deserialize
+    MissingCharmMetadata,   // No contract content under VENDOR_PREFIX
+    InvalidContent,         // The content entry doesn't decode as an inheritance
+    InvalidWitness,         // Not exactly one witness entry, or it doesn't decode
+}
+
+/// Writes the contract content and witness into a PSBT's global map
+///
+/// Replaces any contract entries already there; everything else is kept as-is.
+pub fn export_to_psbt_metadata(
+    psbt_bytes: &[u8],
+    content: &InheritanceContent,
+    witness: &WitnessData,
+) -> Result<Vec<u8>, PsbtError> {
+    let mut psbt = Psbt::deserialize(psbt_bytes).map_err(|_| PsbtError::Malformed)?;
+    let witness_subtype = match witness {
+        WitnessData::Create(_) => SUBTYPE_CREATE_WITNESS,
+        WitnessData::CheckIn { .. } => SUBTYPE_CHECKIN_WITNESS,
+        WitnessData::Distribution(_) => SUBTYPE_DISTRIBUTION_WITNESS,
+    };
+
+    psbt.proprietary.retain(|key, _| !is_charm_key(key));
+    psbt.proprietary.insert(charm_key(SUBTYPE_CONTENT), Data::from(content).bytes());
+    psbt.proprietary.insert(charm_key(witness_subtype), witness.to_data().bytes());
+    Ok(psbt.serialize())
+}
+
+/// Reads back the contract content and witness written by export_to_psbt_metadata
+///
+/// The content is migrated to the latest layout, as the contract would read it.
+pub fn contract_from_psbt(psbt_bytes: &[u8]) -> Result<(InheritanceContent, WitnessData), PsbtError> {
+    let psbt = Psbt::deserialize(psbt_bytes).map_err(|_| PsbtError::Malformed)?;
+    let charm_entries: Vec<(u8, &[u8])> = psbt
+        .proprietary
+        .iter()
+        .filter(|(key, _)| is_charm_key(key))
+        .map(|(key, value)| (key.subtype, value.as_slice()))
+        .collect();
+
+    let content = charm_entries
+        .iter()
+        .find(|(subtype, _)| *subtype == SUBTYPE_CONTENT)
+        .ok_or(PsbtError::MissingCharmMetadata)?
+        .1;
+    let content = decode_data(content)
+        .and_then(|data| migrate_to_latest(&data).ok())
+        .ok_or(PsbtError::InvalidContent)?;
+
+    let witnesses: Vec<&(u8, &[u8])> = charm_entries
+        .iter()
+        .filter(|(subtype, _)| *subtype != SUBTYPE_CONTENT)
+        .collect();
+    let [&(subtype, witness)] = witnesses[..] else {
+        return Err(PsbtError::InvalidWitness);
+    };
+    let witness = decode_data(witness)
+        .and_then(|data| match subtype {
+            SUBTYPE_CREATE_WITNESS => data.value().ok().map(WitnessData::Create),
+            SUBTYPE_CHECKIN_WITNESS => parse_multisig_checkin_witness(&data)
+                .map(|(signatures, current_block)| WitnessData::CheckIn { signatures, current_block }),
+            SUBTYPE_DISTRIBUTION_WITNESS => data.value().ok().map(WitnessData::Distribution),
+            _ => None,
+        })
+        .ok_or(PsbtError::InvalidWitness)?;
+
+    Ok((content, witness))
+}
+
+/// The key of one of our proprietary entries: our prefix and the subtype, no key data
+fn charm_key(subtype: u8) -> ProprietaryKey {
+    ProprietaryKey {
+        prefix: VENDOR_PREFIX.to_vec(),
+        subtype,
+        key: Vec::new(),
+    }
+}
+
+/// Whether a proprietary key is one of ours
+fn is_charm_key(key: &ProprietaryKey) -> bool {
+    key.prefix == VENDOR_PREFIX && key.key.is_empty()
+}
+
+fn decode_data(bytes: &[u8]) -> Option<Data> {
+    ciborium::from_reader(bytes).ok()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        Allocation, Beneficiary, CreateWitness, DistributionWitness, InheritanceStatus, OwnerPubkey, Payee,
+        CONTENT_VERSION,
+    };
+    use bitcoin::absolute::LockTime;
+    use bitcoin::transaction::Version;
+    use bitcoin::{Transaction, TxIn};
+
+    // A PSBT with only the unsigned transaction (version 2, one blank input, no outputs)
+    fn empty_psbt() -> Vec<u8> {
+        let tx = Transaction {
+            version: Version::TWO,
+            lock_time: LockTime::ZERO,
+            input: vec![TxIn::default()],
+            output: Vec::new(),
+        };
+        Psbt::from_unsigned_tx(tx).unwrap().serialize()
+    }
+
+    fn contract() -> InheritanceContent {
+        InheritanceContent {
+            owner_pubkey: "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798".to_string(),
+            last_checkin_block: 100,
+            trigger_delay_blocks: 4320,
+            beneficiaries: vec![Beneficiary {
+                payee: Payee::from("tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx"),
+                allocation: Allocation::Share(10_000),
+                vesting_delay_blocks: 0,
+            }],
+            status: InheritanceStatus::Active,
+            version: CONTENT_VERSION,
+            created_at_block: 100,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_psbt_round_trip() {
+        let witnesses = [
+            WitnessData::Create(CreateWitness::Single("00".repeat(32) + ":0")),
+            WitnessData::CheckIn {
+                signatures: vec![(OwnerPubkey([2; 33]), vec![1; 64])],
+                current_block: 850_000,
+            },
+            WitnessData::Distribution(DistributionWitness {
+                current_block: Some(900_000),
+                ..Default::default()
+            }),
+        ];
+
+        for witness in witnesses {
+            let psbt = export_to_psbt_metadata(&empty_psbt(), &contract(), &witness).unwrap();
+            assert_eq!(contract_from_psbt(&psbt), Ok((contract(), witness)));
+
+            // The unsigned transaction is still there
+            let original = Psbt::deserialize(&empty_psbt()).unwrap();
+            assert_eq!(Psbt::deserialize(&psbt).unwrap().unsigned_tx, original.unsigned_tx);
+        }
+    }
+
+    #[test]
+    fn test_psbt_export_replaces_previous_contract() {
+        let witness = WitnessData::Distribution(DistributionWitness::default());
+        let psbt = export_to_psbt_metadata(&empty_psbt(), &contract(), &witness).unwrap();
+
+        let mut checked_in = contract();
+        checked_in.last_checkin_block = 200;
+        let psbt = export_to_psbt_metadata(&psbt, &checked_in, &witness).unwrap();
+        assert_eq!(contract_from_psbt(&psbt), Ok((checked_in, witness)));
+        assert_eq!(Psbt::deserialize(&psbt).unwrap().proprietary.len(), 2);
+    }
+
+    #[test]
+    fn test_psbt_errors() {
+        assert_eq!(contract_from_psbt(&empty_psbt()), Err(PsbtError::MissingCharmMetadata));
+        assert_eq!(contract_from_psbt(b"not a psbt"), Err(PsbtError::Malformed));
+
+        let mut truncated = empty_psbt();
+        truncated.truncate(10);
+        assert_eq!(contract_from_psbt(&truncated), Err(PsbtError::Malformed));
+
+        // Content under another vendor's prefix isn't ours
+        let mut psbt = Psbt::deserialize(&empty_psbt()).unwrap();
+        let other = ProprietaryKey {
+            prefix: b"other".to_vec(),
+            ..charm_key(SUBTYPE_CONTENT)
+        };
+        psbt.proprietary.insert(other, Data::from(&contract()).bytes());
+        assert_eq!(contract_from_psbt(&psbt.serialize()), Err(PsbtError::MissingCharmMetadata));
+
+        // Content without a witness
+        let mut psbt = Psbt::deserialize(&empty_psbt()).unwrap();
+        psbt.proprietary.insert(charm_key(SUBTYPE_CONTENT), Data::from(&contract()).bytes());
+        assert_eq!(contract_from_psbt(&psbt.serialize()), Err(PsbtError::InvalidWitness));
+    }
+}
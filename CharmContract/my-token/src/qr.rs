@@ -0,0 +1,43 @@
+//! Paper backups: the whole contract as a URI for a printed QR code
+//!
+//! Nothing here is part of the on-chain contract.
+
+use crate::InheritanceContent;
+use base64::Engine;
+
+/// What every paper backup URI starts with, up to the encoded content
+pub const QR_URI_PREFIX: &str = "charm-inheritance://v1?data=";
+
+// Why a scanned paper backup couldn't be read back (see decode_inheritance_qr)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QrDecodeError {
+    InvalidUri,       // Not a charm-inheritance://v1?data=... URI
+    InvalidBase64,    // The data parameter isn't unpadded base64url
+    InvalidContent,   // The decoded bytes aren't an InheritanceContent
+}
+
+/// Encodes the whole contract as a URI for a printed QR code
+///
+/// The URI is QR_URI_PREFIX followed by the canonical bytes in unpadded base64url,
+/// returned as bytes to hand straight to a QR code library. A contract changes with
+/// every check-in, so a backup is only current until the next one.
+pub fn encode_inheritance_qr(inheritance: &InheritanceContent) -> Vec<u8> {
+    let data = base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(inheritance.canonical_bytes());
+    format!("{QR_URI_PREFIX}{data}").into_bytes()
+}
+
+/// Reads back a contract encoded by encode_inheritance_qr
+///
+/// The contract isn't validated: a backup restores whatever state it was taken in.
+pub fn decode_inheritance_qr(data: &[u8]) -> Result<InheritanceContent, QrDecodeError> {
+    let encoded = std::str::from_utf8(data)
+        .ok()
+        .and_then(|uri| uri.strip_prefix(QR_URI_PREFIX))
+        .filter(|encoded| !encoded.is_empty())
+        .ok_or(QrDecodeError::InvalidUri)?;
+    let bytes = base64::engine::general_purpose::URL_SAFE_NO_PAD
+        .decode(encoded)
+        .map_err(|_| QrDecodeError::InvalidBase64)?;
+
+    ciborium::from_reader(bytes.as_slice()).map_err(|_| QrDecodeError::InvalidContent)
+}
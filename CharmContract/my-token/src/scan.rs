@@ -0,0 +1,33 @@
+//! Finding live contracts in a wallet's transaction history
+//!
+//! Nothing here is part of the on-chain contract.
+
+use crate::{migrate_to_latest, InheritanceContent};
+use charms_sdk::data::{App, Transaction, TxId, UtxoId};
+
+/// Finds every live inheritance contract created by this app's verifying key
+///
+/// Transactions don't carry their own ID, so each comes paired with it. Outputs
+/// are matched on tag and vk (not identity), so one scan finds every vault; content
+/// that doesn't decode is skipped, and distributed contracts are left out.
+pub fn find_inheritance_utxos(txs: &[(TxId, Transaction)], app: &App) -> Vec<(UtxoId, InheritanceContent)> {
+    let mut found = Vec::new();
+
+    for (tx_id, tx) in txs {
+        for (vout, charms) in tx.outs.iter().enumerate() {
+            for (charm_app, data) in charms {
+                if charm_app.tag != app.tag || charm_app.vk != app.vk {
+                    continue;
+                }
+                let Ok(inheritance) = migrate_to_latest(data) else {
+                    continue;
+                };
+                if !inheritance.status.is_terminal() {
+                    found.push((UtxoId(*tx_id, vout as u32), inheritance));
+                }
+            }
+        }
+    }
+
+    found
+}
@@ -0,0 +1,212 @@
+//! Content schema versions, for tooling that persists contracts
+//!
+//! SCHEMA_VERSION is the layout this build writes (the same number as
+//! CONTENT_VERSION); anything from MIN_COMPATIBLE_VERSION up to it can be read
+//! and migrated to the latest layout.
+//!
+//! With the `schemars` feature, JSON Schemas of the content and witness types
+//! are generated from the Rust types themselves, so frontends can't drift from
+//! them. 32-byte hashes (B32) are described as strings.
+
+use crate::CONTENT_VERSION;
+#[cfg(feature = "schemars")]
+use crate::{
+    Beneficiary, CourtFreezeWitness, CourtUnfreezeWitness, CreateWitness, DistributionWitness, EmergencyExtendWitness,
+    InheritanceContent, MigrateWitness, MultisigCheckinWitness, OracleCheckinWitness,
+};
+
+/// Content layout version this build writes
+pub const SCHEMA_VERSION: u8 = CONTENT_VERSION;
+
+/// Oldest content layout this build can still read
+pub const MIN_COMPATIBLE_VERSION: u8 = 0;
+
+// Reading must always cover at least the layout being written
+const _: () = assert!(SCHEMA_VERSION.checked_sub(MIN_COMPATIBLE_VERSION).is_some());
+
+/// The content layout version this build writes
+pub fn current_schema_version() -> u8 {
+    SCHEMA_VERSION
+}
+
+/// Whether content written with version `v` can be read by this build
+pub fn is_compatible_version(v: u8) -> bool {
+    (MIN_COMPATIBLE_VERSION..=SCHEMA_VERSION).contains(&v)
+}
+
+/// JSON Schema of the inheritance NFT's content
+#[cfg(feature = "schemars")]
+pub fn inheritance_content_schema() -> serde_json::Value {
+    json_schema::<InheritanceContent>()
+}
+
+/// JSON Schema of a single beneficiary entry
+#[cfg(feature = "schemars")]
+pub fn beneficiary_schema() -> serde_json::Value {
+    json_schema::<Beneficiary>()
+}
+
+/// JSON Schema of the witness for creating a contract
+#[cfg(feature = "schemars")]
+pub fn create_witness_schema() -> serde_json::Value {
+    json_schema::<CreateWitness>()
+}
+
+/// JSON Schema of the witness for a (multi-sig) check-in
+#[cfg(feature = "schemars")]
+pub fn checkin_witness_schema() -> serde_json::Value {
+    json_schema::<MultisigCheckinWitness>()
+}
+
+/// JSON Schema of the witness for an oracle's check-in
+#[cfg(feature = "schemars")]
+pub fn oracle_checkin_witness_schema() -> serde_json::Value {
+    json_schema::<OracleCheckinWitness>()
+}
+
+/// JSON Schema of the witness for an emergency contact's extension
+#[cfg(feature = "schemars")]
+pub fn emergency_extend_witness_schema() -> serde_json::Value {
+    json_schema::<EmergencyExtendWitness>()
+}
+
+/// JSON Schema of the witness for a court freezing a contract
+#[cfg(feature = "schemars")]
+pub fn court_freeze_witness_schema() -> serde_json::Value {
+    json_schema::<CourtFreezeWitness>()
+}
+
+/// JSON Schema of the witness for a court lifting its freeze
+#[cfg(feature = "schemars")]
+pub fn court_unfreeze_witness_schema() -> serde_json::Value {
+    json_schema::<CourtUnfreezeWitness>()
+}
+
+/// JSON Schema of the witness for a distribution
+#[cfg(feature = "schemars")]
+pub fn distribution_witness_schema() -> serde_json::Value {
+    json_schema::<DistributionWitness>()
+}
+
+/// JSON Schema of the witness for migrating a contract's layout
+#[cfg(feature = "schemars")]
+pub fn migrate_witness_schema() -> serde_json::Value {
+    json_schema::<MigrateWitness>()
+}
+
+#[cfg(feature = "schemars")]
+fn json_schema<T: schemars::JsonSchema>() -> serde_json::Value {
+    serde_json::to_value(schemars::schema_for!(T)).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_is_compatible_version() {
+        assert!(is_compatible_version(0));
+        assert!(is_compatible_version(current_schema_version()));
+        assert!(!is_compatible_version(SCHEMA_VERSION + 1));
+        assert!(!is_compatible_version(255));
+    }
+
+    // Sorted keys of a JSON object
+    #[cfg(feature = "schemars")]
+    fn keys(value: &serde_json::Value) -> Vec<&str> {
+        let mut keys: Vec<&str> = value.as_object().unwrap().keys().map(String::as_str).collect();
+        keys.sort();
+        keys
+    }
+
+    // Snapshot of the content's shape: adding, renaming or un-defaulting a field
+    // (or adding a new type) must update this test, on purpose
+    #[cfg(feature = "schemars")]
+    #[test]
+    fn test_inheritance_content_schema_snapshot() {
+        let schema = inheritance_content_schema();
+        assert_eq!(
+            keys(&schema["properties"]),
+            vec![
+                "allowed_missed_checkins",
+                "beneficiaries",
+                "checkin_window_blocks",
+                "co_signers",
+                "court_pubkey",
+                "created_at_block",
+                "delay_decay",
+                "emergency_contact_pubkey",
+                "emergency_extension_used",
+                "fallback_address",
+                "grace_period",
+                "guardian_emergency_delay",
+                "guardian_threshold",
+                "guardians",
+                "last_checkin_block",
+                "leave_tombstone",
+                "metadata",
+                "min_expected_funding",
+                "missed_count",
+                "multisig_threshold",
+                "network",
+                "oracle_pubkey",
+                "owner_pubkey",
+                "policy_max_gap",
+                "recovery_mode",
+                "rounding_mode",
+                "rule_set",
+                "status",
+                "total_locked_sats",
+                "trigger_delay_blocks",
+                "trigger_height",
+                "version",
+                "will_document_hash",
+                "will_locked",
+            ]
+        );
+        assert_eq!(
+            schema["required"],
+            serde_json::json!(["beneficiaries", "last_checkin_block", "owner_pubkey", "status", "trigger_delay_blocks"])
+        );
+        assert_eq!(schema["additionalProperties"], serde_json::json!(false));
+        assert_eq!(
+            keys(&schema["definitions"]),
+            vec![
+                "Allocation",
+                "Beneficiary",
+                "CommittedPayee",
+                "ContractRecoveryMode",
+                "DelayDecay",
+                "GracePeriod",
+                "InheritanceStatus",
+                "LightningPayee",
+                "Network",
+                "Payee",
+                "RoundingMode",
+                "RuleSet",
+                "ScriptPayee",
+                "XpubPayee",
+            ]
+        );
+    }
+
+    #[cfg(feature = "schemars")]
+    #[test]
+    fn test_beneficiary_and_witness_schemas() {
+        let beneficiary = beneficiary_schema();
+        assert_eq!(keys(&beneficiary["properties"]), vec!["address", "allocation", "vesting_delay_blocks"]);
+        assert_eq!(beneficiary["required"], serde_json::json!(["address", "allocation"]));
+
+        assert_eq!(keys(&checkin_witness_schema()["properties"]), vec!["current_block", "signatures"]);
+        assert_eq!(keys(&oracle_checkin_witness_schema()["properties"]), vec!["attestation", "current_block"]);
+        assert_eq!(
+            keys(&distribution_witness_schema()["properties"]),
+            vec!["beneficiary_signatures", "current_block", "derived", "guardian_signatures", "revealed"]
+        );
+        assert_eq!(keys(&migrate_witness_schema()["properties"]), vec!["signature"]);
+        assert_eq!(keys(&emergency_extend_witness_schema()["properties"]), vec!["signature"]);
+        assert_eq!(keys(&court_freeze_witness_schema()["properties"]), vec!["court_freeze_sig"]);
+        assert_eq!(keys(&court_unfreeze_witness_schema()["properties"]), vec!["court_unfreeze_sig"]);
+        assert!(create_witness_schema()["anyOf"].is_array());
+    }
+}
@@ -0,0 +1,258 @@
+//! Dry-running transactions without a live node
+//!
+//! Lets off-chain tooling check a transaction against the contract before it's
+//! broadcast: whether it's valid, the state it leaves the contract in, what it
+//! did, and roughly what the spell and its proof add to the fee. Nothing here is
+//! part of the on-chain contract.
+
+use crate::{app_contract, content_version, deadline_block, migrate_to_latest, InheritanceContent, InheritanceStatus};
+use charms_sdk::data::{charm_values, App, Data, Transaction};
+
+/// Size of the proof a spell carries: a Groth16 proof over BN254 (two G1 points and
+/// one G2 point, uncompressed)
+pub const PROOF_SIZE_BYTES: usize = 256;
+
+/// Fee rate (sat/vB) a simulator starts with
+pub const DEFAULT_FEE_RATE: u64 = 1;
+
+// Something a transaction did to the contract
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ContractEvent {
+    Created { deadline_block: u64 },           // New contract, due at deadline_block
+    CheckedIn { deadline_block: u64 },         // The deadline moved out to deadline_block
+    BeneficiariesUpdated,                      // The owner changed who inherits
+    Triggered,                                 // The vault left Active
+    PartiallyDistributed { remaining: usize }, // Some beneficiaries paid, `remaining` still owed
+    Distributed,                               // Everyone paid; the NFT is gone (or a tombstone)
+    Migrated { from_version: u8 },             // Rewritten in the latest layout
+}
+
+// What a dry run of a transaction found
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimulationResult {
+    pub valid: bool,                               // Whether app_contract accepts the transaction
+    pub output_state: Option<InheritanceContent>,  // The contract after the transaction (None if invalid or burned)
+    pub events: Vec<ContractEvent>,                // What the transaction did (empty if invalid)
+    pub estimated_fee_sats: u64,                   // Fee for the spell and its proof at fee_rate
+}
+
+// Dry-runs transactions against one contract as of a given block height
+#[derive(Debug, Clone)]
+pub struct TransactionSimulator {
+    pub app: App,             // The contract transactions are checked against
+    pub current_block: u64,   // Height deadlines are computed at
+    pub fee_rate: u64,        // sat/vB used for estimated_fee_sats
+}
+
+impl TransactionSimulator {
+    pub fn new(app: App, current_block: u64) -> Self {
+        TransactionSimulator {
+            app,
+            current_block,
+            fee_rate: DEFAULT_FEE_RATE,
+        }
+    }
+
+    /// Runs `tx` through app_contract and reports what it would do
+    ///
+    /// `x` is the public input the spell would carry (see block_height_input).
+    pub fn simulate(&self, tx: &Transaction, x: &Data, w: &Data) -> SimulationResult {
+        let valid = app_contract(&self.app, tx, x, w);
+        let estimated_fee_sats = (estimate_proof_size(tx).div_ceil(4) as u64).saturating_mul(self.fee_rate);
+        if !valid {
+            return SimulationResult {
+                valid,
+                output_state: None,
+                events: Vec::new(),
+                estimated_fee_sats,
+            };
+        }
+
+        let input: Vec<&Data> = charm_values(&self.app, tx.ins.iter().map(|(_, v)| v)).collect();
+        let output: Vec<&Data> = charm_values(&self.app, tx.outs.iter()).collect();
+        let output_state = match output[..] {
+            [data] => migrate_to_latest(data).ok(),
+            _ => None,
+        };
+
+        let events = match (input.first(), &output_state) {
+            (None, Some(created)) => vec![ContractEvent::Created {
+                deadline_block: deadline_block(created, self.current_block),
+            }],
+            (Some(data), after) => match migrate_to_latest(data) {
+                Ok(before) => {
+                    let before_version = content_version(data);
+                    let after_version = output.first().copied().and_then(content_version);
+                    let migrated_from = before_version.filter(|_| after_version > before_version);
+                    self.events(&before, migrated_from, after.as_ref())
+                }
+                Err(_) => Vec::new(),
+            },
+            (None, None) => Vec::new(),
+        };
+
+        SimulationResult {
+            valid,
+            output_state,
+            events,
+            estimated_fee_sats,
+        }
+    }
+
+    /// Events for a transaction moving the contract from `before` to `after`, or
+    /// burning it if there's no `after`; `migrated_from` is the layout version
+    /// `before` was written in, if the transaction rewrote it in a newer one
+    fn events(
+        &self,
+        before: &InheritanceContent,
+        migrated_from: Option<u8>,
+        after: Option<&InheritanceContent>,
+    ) -> Vec<ContractEvent> {
+        let mut events = Vec::new();
+        let triggered = before.status == InheritanceStatus::Active
+            && after.is_none_or(|after| after.status == InheritanceStatus::Triggered);
+        if triggered {
+            events.push(ContractEvent::Triggered);
+        }
+
+        let Some(after) = after else {
+            events.push(ContractEvent::Distributed);
+            return events;
+        };
+
+        if let Some(from_version) = migrated_from {
+            events.push(ContractEvent::Migrated { from_version });
+        }
+        if after.status == InheritanceStatus::Triggered {
+            events.push(ContractEvent::PartiallyDistributed {
+                remaining: after.beneficiaries.len(),
+            });
+            return events;
+        }
+        if after.beneficiaries != before.beneficiaries {
+            events.push(ContractEvent::BeneficiariesUpdated);
+        }
+        if after.last_checkin_block > before.last_checkin_block {
+            events.push(ContractEvent::CheckedIn {
+                deadline_block: deadline_block(after, self.current_block),
+            });
+        }
+        events
+    }
+}
+
+/// Rough size in bytes of what proving `tx` puts on-chain: the proof, plus the
+/// spell's record of every charm the transaction creates
+///
+/// The spell rides in a taproot witness, so it costs a quarter of a vbyte per byte.
+pub fn estimate_proof_size(tx: &Transaction) -> usize {
+    let charms: usize = tx
+        .outs
+        .iter()
+        .flat_map(|charms| charms.values())
+        .map(|data| data.bytes().len())
+        .sum();
+    PROOF_SIZE_BYTES + charms
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        block_height_input, create_multisig_checkin_witness, operation_witness, signing_message, Allocation, Beneficiary,
+        Operation, OwnerPubkey, Payee, CONTENT_VERSION,
+    };
+    use charms_sdk::data::{Charms, TxId, UtxoId, B32, NFT};
+
+    fn app() -> App {
+        App {
+            tag: NFT,
+            identity: B32([3; 32]),
+            vk: B32([7; 32]),
+        }
+    }
+
+    fn contract() -> InheritanceContent {
+        InheritanceContent {
+            owner_pubkey: "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798".to_string(),
+            last_checkin_block: 100,
+            trigger_delay_blocks: 4320,
+            beneficiaries: vec![Beneficiary {
+                payee: Payee::from("tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx"),
+                allocation: Allocation::Share(10_000),
+                vesting_delay_blocks: 0,
+            }],
+            status: InheritanceStatus::Active,
+            version: CONTENT_VERSION,
+            created_at_block: 100,
+            ..Default::default()
+        }
+    }
+
+    // A check-in witness at `current_block`, signed by the owner (secret key 1) over `output`
+    fn checkin_witness(tx: &Transaction, output: &InheritanceContent, current_block: u64) -> Data {
+        let mut secret = [0u8; 32];
+        secret[31] = 1;
+        let signing_key = k256::schnorr::SigningKey::from_bytes(&secret).unwrap();
+        let message = signing_message(Operation::Checkin, tx, output);
+        let sig = signing_key.sign_raw(&message, &[0u8; 32]).unwrap().to_bytes().to_vec();
+        let owner = OwnerPubkey::try_from(crate::decode_hex(&output.owner_pubkey).unwrap()).unwrap();
+        operation_witness(Operation::Checkin, &create_multisig_checkin_witness(&[(owner, sig)], current_block))
+    }
+
+    fn transition(input: &InheritanceContent, output: &InheritanceContent) -> Transaction {
+        Transaction {
+            ins: vec![(UtxoId(TxId([1; 32]), 0), Charms::from([(app(), Data::from(input))]))],
+            refs: vec![],
+            outs: vec![Charms::from([(app(), Data::from(output))])],
+            coin_ins: None,
+            coin_outs: None,
+        }
+    }
+
+    #[test]
+    fn test_simulate_checkin() {
+        let simulator = TransactionSimulator::new(app(), 250);
+        let mut checked_in = contract();
+        checked_in.last_checkin_block = 200;
+        let tx = transition(&contract(), &checked_in);
+        let w = checkin_witness(&tx, &checked_in, 250);
+
+        let result = simulator.simulate(&tx, &block_height_input(250), &w);
+        assert!(result.valid);
+        assert_eq!(result.output_state, Some(checked_in));
+        assert_eq!(result.events, vec![ContractEvent::CheckedIn { deadline_block: 4520 }]);
+        assert_eq!(result.estimated_fee_sats, estimate_proof_size(&tx).div_ceil(4) as u64);
+
+        // The fee scales with the configured rate
+        let simulator = TransactionSimulator {
+            fee_rate: 20,
+            ..simulator
+        };
+        assert_eq!(simulator.simulate(&tx, &block_height_input(250), &w).estimated_fee_sats, result.estimated_fee_sats * 20);
+    }
+
+    #[test]
+    fn test_simulate_invalid_transaction() {
+        let simulator = TransactionSimulator::new(app(), 250);
+        let mut rewound = contract();
+        rewound.last_checkin_block = 50;
+        let tx = transition(&contract(), &rewound);
+        let w = checkin_witness(&tx, &rewound, 250);
+
+        let result = simulator.simulate(&tx, &block_height_input(250), &w);
+        assert!(!result.valid);
+        assert_eq!(result.output_state, None);
+        assert!(result.events.is_empty());
+        assert!(result.estimated_fee_sats > 0);
+
+        // Public inputs that aren't the height the witness gives are reported, not asserted on
+        let mut checked_in = contract();
+        checked_in.last_checkin_block = 200;
+        let tx = transition(&contract(), &checked_in);
+        let w = checkin_witness(&tx, &checked_in, 250);
+        assert!(simulator.simulate(&tx, &block_height_input(250), &w).valid);
+        assert!(!simulator.simulate(&tx, &Data::from(&1u8), &w).valid);
+        assert!(!simulator.simulate(&tx, &Data::from(&"250"), &w).valid);
+    }
+}
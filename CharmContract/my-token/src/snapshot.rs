@@ -0,0 +1,39 @@
+//! Off-chain backups of a contract's state
+//!
+//! Snapshots read the system clock, so nothing here is part of the on-chain
+//! contract.
+
+use crate::{ContractId, InheritanceContent};
+use charms_sdk::data::App;
+use serde::{Deserialize, Serialize};
+
+// A contract's state at a point in time, for wallets to back up off-chain
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ContractSnapshot {
+    pub contract_id: ContractId,
+    pub inheritance: InheritanceContent,
+    pub snapshot_block: u64,               // Chain height the snapshot was taken at
+    pub snapshot_timestamp_unix: u64,      // Wall-clock time the snapshot was taken (seconds)
+}
+
+/// Captures a contract's state for off-chain backup
+///
+/// Reads the system clock, so this is for wallets only, never for contract validation.
+pub fn create_snapshot(app: &App, inheritance: &InheritanceContent, current_block: u64) -> ContractSnapshot {
+    let snapshot_timestamp_unix = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0);
+
+    ContractSnapshot {
+        contract_id: ContractId::from(app),
+        inheritance: inheritance.clone(),
+        snapshot_block: current_block,
+        snapshot_timestamp_unix,
+    }
+}
+
+/// Recovers the contract state stored in a snapshot
+pub fn restore_snapshot(snapshot: &ContractSnapshot) -> InheritanceContent {
+    snapshot.inheritance.clone()
+}
@@ -0,0 +1,108 @@
+//! Off-chain storage of contract state
+//!
+//! Wallets and indexers keep the latest known state of each contract they follow.
+//! ContractStorage abstracts over where that lives; InMemoryStorage is enough for
+//! tests and short-lived tools. Nothing here is part of the on-chain contract.
+
+use crate::{ContractId, InheritanceContent};
+use std::collections::HashMap;
+
+// Failure in the underlying store
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StorageError {
+    Backend(String),   // The store couldn't be read or written
+}
+
+/// Keeps the latest known state of each contract
+pub trait ContractStorage {
+    /// Stores `state` as the contract's latest state, replacing any previous one
+    fn save(&mut self, id: ContractId, state: InheritanceContent) -> Result<(), StorageError>;
+
+    /// Returns the contract's latest state, or None if it was never saved
+    fn load(&self, id: &ContractId) -> Result<Option<InheritanceContent>, StorageError>;
+
+    /// Every contract not yet distributed
+    fn list_active(&self) -> Vec<(ContractId, InheritanceContent)>;
+}
+
+// ContractStorage backed by a HashMap
+#[derive(Debug, Clone, Default)]
+pub struct InMemoryStorage(pub HashMap<ContractId, InheritanceContent>);
+
+impl InMemoryStorage {
+    /// Forgets a contract, returning its last state
+    pub fn delete(&mut self, id: &ContractId) -> Option<InheritanceContent> {
+        self.0.remove(id)
+    }
+}
+
+impl ContractStorage for InMemoryStorage {
+    fn save(&mut self, id: ContractId, state: InheritanceContent) -> Result<(), StorageError> {
+        self.0.insert(id, state);
+        Ok(())
+    }
+
+    fn load(&self, id: &ContractId) -> Result<Option<InheritanceContent>, StorageError> {
+        Ok(self.0.get(id).cloned())
+    }
+
+    /// Sorted by contract ID, so results don't depend on hash order
+    fn list_active(&self) -> Vec<(ContractId, InheritanceContent)> {
+        let mut active: Vec<_> = self
+            .0
+            .iter()
+            .filter(|(_, state)| !state.status.is_terminal())
+            .map(|(id, state)| (*id, state.clone()))
+            .collect();
+        active.sort_by_key(|(id, _)| *id);
+        active
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::InheritanceStatus;
+    use charms_sdk::data::B32;
+
+    fn contract(last_checkin_block: u64, status: InheritanceStatus) -> InheritanceContent {
+        InheritanceContent {
+            last_checkin_block,
+            status,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_in_memory_crud() {
+        let mut storage = InMemoryStorage::default();
+        let id = ContractId(B32([1; 32]));
+        assert_eq!(storage.load(&id), Ok(None));
+
+        storage.save(id, contract(100, InheritanceStatus::Active)).unwrap();
+        assert_eq!(storage.load(&id), Ok(Some(contract(100, InheritanceStatus::Active))));
+
+        // Saving again replaces the state
+        storage.save(id, contract(200, InheritanceStatus::Active)).unwrap();
+        assert_eq!(storage.load(&id), Ok(Some(contract(200, InheritanceStatus::Active))));
+
+        assert_eq!(storage.delete(&id), Some(contract(200, InheritanceStatus::Active)));
+        assert_eq!(storage.load(&id), Ok(None));
+    }
+
+    #[test]
+    fn test_list_active_skips_distributed() {
+        let mut storage = InMemoryStorage::default();
+        storage.save(ContractId(B32([3; 32])), contract(1, InheritanceStatus::Triggered)).unwrap();
+        storage.save(ContractId(B32([2; 32])), contract(2, InheritanceStatus::Distributed)).unwrap();
+        storage.save(ContractId(B32([1; 32])), contract(3, InheritanceStatus::Active)).unwrap();
+
+        assert_eq!(
+            storage.list_active(),
+            vec![
+                (ContractId(B32([1; 32])), contract(3, InheritanceStatus::Active)),
+                (ContractId(B32([3; 32])), contract(1, InheritanceStatus::Triggered)),
+            ]
+        );
+    }
+}
@@ -0,0 +1,129 @@
+//! Configurable validation limits
+//!
+//! Jurisdictions differ on what a valid inheritance looks like (e.g. some require
+//! at least 2 beneficiaries), so the limits live in a RuleSet stored in each
+//! contract instead of being hard-coded.
+
+use serde::{Deserialize, Serialize};
+
+use crate::Network;
+
+/// Minimum trigger delay, in blocks
+pub const MINIMUM_DELAY_BLOCKS: u64 = 1;
+
+/// Fewest beneficiaries a contract may list
+pub const MIN_BENEFICIARIES: usize = 1;
+
+/// Most beneficiaries a contract may list
+pub const MAX_BENEFICIARIES: usize = 100;
+
+/// Maximum length of a free-text note, in bytes
+pub const MAX_NOTE_LENGTH: usize = 256;
+
+/// Shortest trigger delay on mainnet (~1 day)
+pub const MAINNET_MIN: u64 = 144;
+
+/// Longest trigger delay on mainnet (~5 years)
+pub const MAINNET_MAX: u64 = 262_980;
+
+/// Shortest trigger delay on the public test networks (~1 hour)
+pub const TESTNET_MIN: u64 = 6;
+
+/// Longest trigger delay on the public test networks
+pub const TESTNET_MAX: u64 = 262_980;
+
+/// Shortest trigger delay on regtest, where blocks are mined on demand
+pub const REGTEST_MIN: u64 = 1;
+
+/// Longest trigger delay on regtest
+pub const REGTEST_MAX: u64 = 262_980;
+
+// Why a trigger delay isn't usable on a network
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DelayValidationError {
+    TooShort { minimum: u64, provided: u64 },  // Could trigger before the owner notices
+    TooLong { maximum: u64, provided: u64 },   // Would lock funds for an unreasonable time
+}
+
+// The limits a contract is validated against
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(deny_unknown_fields)]
+pub struct RuleSet {
+    pub minimum_delay_blocks: u64,  // trigger_delay_blocks must be at least this
+    pub min_beneficiaries: usize,   // Fewest beneficiaries allowed
+    pub max_beneficiaries: usize,   // Most beneficiaries allowed
+    pub max_note_length: usize,     // Longest free-text note allowed, in bytes
+    #[serde(default)]
+    pub reject_owner_beneficiary: bool,  // Reject beneficiaries paid to the owner's own key
+}
+
+impl Default for RuleSet {
+    fn default() -> Self {
+        RuleSet {
+            minimum_delay_blocks: MINIMUM_DELAY_BLOCKS,
+            min_beneficiaries: MIN_BENEFICIARIES,
+            max_beneficiaries: MAX_BENEFICIARIES,
+            max_note_length: MAX_NOTE_LENGTH,
+            reject_owner_beneficiary: false,
+        }
+    }
+}
+
+impl RuleSet {
+    /// Tighter limits: at least 2 beneficiaries and roughly a month's delay
+    pub fn strict() -> Self {
+        RuleSet {
+            minimum_delay_blocks: 4320,
+            min_beneficiaries: 2,
+            max_beneficiaries: 20,
+            max_note_length: 140,
+            reject_owner_beneficiary: false,
+        }
+    }
+
+    /// Looser limits, mostly useful in tests
+    pub fn permissive() -> Self {
+        RuleSet {
+            minimum_delay_blocks: 1,
+            min_beneficiaries: 1,
+            max_beneficiaries: 100,
+            max_note_length: 1024,
+            reject_owner_beneficiary: false,
+        }
+    }
+
+    /// Whether these are the default limits (which are left out of serialized content)
+    pub fn is_default(&self) -> bool {
+        *self == RuleSet::default()
+    }
+
+    /// A rule set must itself be satisfiable
+    pub fn is_consistent(&self) -> bool {
+        self.minimum_delay_blocks > 0
+            && self.min_beneficiaries > 0
+            && self.min_beneficiaries <= self.max_beneficiaries
+    }
+}
+
+/// The (minimum, maximum) trigger delay allowed on a network, in blocks
+pub fn delay_bounds(network: Network) -> (u64, u64) {
+    match network {
+        Network::Mainnet => (MAINNET_MIN, MAINNET_MAX),
+        Network::Testnet | Network::Signet => (TESTNET_MIN, TESTNET_MAX),
+        Network::Regtest => (REGTEST_MIN, REGTEST_MAX),
+    }
+}
+
+/// Checks that a trigger delay is reasonable for the network the contract lives on
+pub fn validate_trigger_delay_against_network(delay: u64, network: Network) -> Result<(), DelayValidationError> {
+    let (minimum, maximum) = delay_bounds(network);
+    if delay < minimum {
+        return Err(DelayValidationError::TooShort { minimum, provided: delay });
+    }
+    if delay > maximum {
+        return Err(DelayValidationError::TooLong { maximum, provided: delay });
+    }
+    Ok(())
+}
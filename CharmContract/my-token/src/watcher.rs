@@ -0,0 +1,112 @@
+//! Off-chain monitoring of inheritance deadlines
+//!
+//! Lets a beneficiary running a node wait for the trigger deadline to pass
+//! instead of polling by hand. Enabled with the `watcher` feature; nothing
+//! here is part of the on-chain contract.
+
+use crate::{is_distributable, ContractId, InheritanceContent};
+use std::time::Duration;
+
+// Error returned by a Bitcoin RPC backend
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RpcError {
+    pub message: String,
+}
+
+/// The subset of Bitcoin node RPC the watcher needs
+#[allow(async_fn_in_trait)]
+pub trait BitcoinRpc {
+    /// Returns the current best block height
+    async fn get_block_count(&self) -> Result<u64, RpcError>;
+}
+
+// Returned once the contract's deadline has passed
+#[derive(Debug, Clone)]
+pub struct TriggerOpportunity {
+    pub contract: InheritanceContent,  // Contract state the deadline was computed from
+    pub current_block: u64,            // First polled height past the deadline
+}
+
+// Watches a single contract until it can be triggered
+#[derive(Debug, Clone)]
+pub struct TriggerWatcher {
+    pub contract_id: ContractId,         // Contract being watched
+    pub inheritance: InheritanceContent, // Latest known contract state
+}
+
+impl TriggerWatcher {
+    pub fn new(contract_id: ContractId, inheritance: InheritanceContent) -> Self {
+        TriggerWatcher {
+            contract_id,
+            inheritance,
+        }
+    }
+
+    /// Polls the block height every `poll_interval` until the vault is distributable
+    ///
+    /// RPC errors are treated as transient: the watcher keeps polling.
+    pub async fn watch_until_trigger(
+        self,
+        rpc_client: &impl BitcoinRpc,
+        poll_interval: Duration,
+    ) -> TriggerOpportunity {
+        loop {
+            if let Ok(current_block) = rpc_client.get_block_count().await {
+                if is_distributable(&self.inheritance, current_block) {
+                    return TriggerOpportunity {
+                        contract: self.inheritance,
+                        current_block,
+                    };
+                }
+            }
+
+            tokio::time::sleep(poll_interval).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{deadline_block, Allocation, Beneficiary, InheritanceStatus, Payee};
+    use charms_sdk::data::B32;
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    // Mines one block per call
+    struct MockRpc {
+        height: AtomicU64,
+    }
+
+    impl BitcoinRpc for MockRpc {
+        async fn get_block_count(&self) -> Result<u64, RpcError> {
+            Ok(self.height.fetch_add(1, Ordering::SeqCst))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_watch_until_trigger() {
+        let inheritance = InheritanceContent {
+            owner_pubkey: "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798".to_string(),
+            last_checkin_block: 100,
+            trigger_delay_blocks: 10,
+            beneficiaries: vec![Beneficiary {
+                payee: Payee::from("tb1p123"),
+                allocation: Allocation::Share(10_000),
+                vesting_delay_blocks: 0,
+            }],
+            status: InheritanceStatus::Active,
+            ..Default::default()
+        };
+        let rpc = MockRpc {
+            height: AtomicU64::new(100),
+        };
+
+        let watcher = TriggerWatcher::new(ContractId(B32([1; 32])), inheritance.clone());
+        let opportunity = watcher
+            .watch_until_trigger(&rpc, Duration::from_millis(1))
+            .await;
+
+        assert_eq!(opportunity.current_block, deadline_block(&inheritance, 0) + 1);
+        assert_eq!(opportunity.contract.last_checkin_block, 100);
+    }
+}
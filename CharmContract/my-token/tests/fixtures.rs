@@ -0,0 +1,166 @@
+//! Stored payloads, one per released content layout, in tests/fixtures.
+//!
+//! Each file is an NFT payload exactly as that version of the contract wrote it.
+//! Like the payloads in compat.rs, they're never regenerated from the structs: a
+//! failure here means a serde change broke vaults already on-chain. When
+//! CONTENT_VERSION is bumped, add a fixture for the new layout before releasing it.
+//!
+//! All of them hold the same contract: OWNER's vault, last checked in at block 100
+//! with a 4320-block delay, split 60/40 between HEIR_A and HEIR_B.
+
+use charms_sdk::data::Data;
+use my_token::diff::{diff_and_patch, diff_inheritance, FieldChange, PatchError};
+use my_token::{
+    migrate_to_latest, Allocation, DelayDecay, InheritanceContent, InheritanceContentV1, InheritanceContentV2,
+    InheritanceStatus, Network, ValidationError, CONTENT_VERSION,
+};
+
+const OWNER: &str = "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798";
+const HEIR_A: &str = "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx";
+const HEIR_B: &str = "tb1pqqqqp399et2xygdj5xreqhjjvcmzhxw4aywxecjdzew6hylgvsesf3hn0c";
+
+// (version the payload was written under, payload), oldest first
+const FIXTURES: [(u8, &[u8]); 4] = [
+    (0, include_bytes!("fixtures/v0.cbor")),   // Whole-percent shares, no `version` key
+    (1, include_bytes!("fixtures/v1.cbor")),   // As version 0, with a delay decay
+    (2, include_bytes!("fixtures/v2.cbor")),   // Basis-point shares
+    (3, include_bytes!("fixtures/v3.cbor")),   // Allocations, network, declared funding and metadata
+];
+
+fn decode(bytes: &[u8]) -> Data {
+    ciborium::from_reader(bytes).expect("fixture is valid CBOR")
+}
+
+// The payload, re-encoded through the layout it was written under
+fn reencoded(version: u8, data: &Data) -> Vec<u8> {
+    match version {
+        0 | 1 => Data::from(&data.value::<InheritanceContentV1>().unwrap()).bytes(),
+        2 => Data::from(&data.value::<InheritanceContentV2>().unwrap()).bytes(),
+        _ => Data::from(&data.value::<InheritanceContent>().unwrap()).bytes(),
+    }
+}
+
+#[test]
+fn test_fixtures_round_trip() {
+    for (version, bytes) in FIXTURES {
+        let data = decode(bytes);
+        assert_eq!(data.bytes(), bytes, "v{version} fixture doesn't re-encode as CBOR");
+        if version == 0 {
+            // Version 0 predates the `version` key, which its layout writes back as 0
+            let layout: InheritanceContentV1 = data.value().unwrap();
+            assert_eq!(layout.version, 0);
+            assert_eq!(decode(&reencoded(version, &data)).value::<InheritanceContentV1>().unwrap(), layout);
+        } else {
+            assert_eq!(reencoded(version, &data), bytes, "v{version} fixture no longer round-trips through its layout");
+        }
+    }
+}
+
+#[test]
+fn test_fixtures_migrate_to_latest() {
+    for (version, bytes) in FIXTURES {
+        let migrated = migrate_to_latest(&decode(bytes))
+            .unwrap_or_else(|error| panic!("v{version} fixture no longer migrates: {error:?}"));
+
+        assert_eq!(migrated.version, CONTENT_VERSION);
+        assert_eq!(migrated.owner_pubkey, OWNER);
+        assert_eq!(migrated.last_checkin_block, 100);
+        assert_eq!(migrated.trigger_delay_blocks, 4320);
+        assert_eq!(migrated.status, InheritanceStatus::Active);
+        assert_eq!(migrated.network, Network::Testnet);
+
+        let beneficiaries: Vec<_> = migrated
+            .beneficiaries
+            .iter()
+            .map(|b| (b.payee.label(), b.allocation))
+            .collect();
+        assert_eq!(
+            beneficiaries,
+            vec![
+                (HEIR_A.to_string(), Allocation::Share(6000)),
+                (HEIR_B.to_string(), Allocation::Share(4000)),
+            ],
+            "v{version}"
+        );
+
+        let decay = DelayDecay {
+            interval_blocks: 144,
+            step_blocks: 10,
+            floor_blocks: 1000,
+        };
+        assert_eq!(migrated.delay_decay, (version > 0).then_some(decay), "v{version}");
+    }
+}
+
+#[test]
+fn test_latest_fixture_is_current() {
+    // Fields added since the newest fixture must default to off
+    let (version, bytes) = FIXTURES[FIXTURES.len() - 1];
+    assert_eq!(version, CONTENT_VERSION, "add a fixture for content version {CONTENT_VERSION}");
+
+    let latest: InheritanceContent = decode(bytes).value().unwrap();
+    assert_eq!(latest.total_locked_sats, 1_000_000);
+    assert_eq!(latest.created_at_block, 100);
+    assert_eq!(latest.metadata.get("label").map(String::as_str), Some("Family vault"));
+    assert_eq!(latest.validate(), Ok(()));
+    assert_eq!(migrate_to_latest(&decode(bytes)).unwrap(), latest);
+}
+
+#[test]
+fn test_diff_and_patch_fixtures() {
+    // The oldest contract, brought up to date, and the same contract as written today
+    let (_, oldest) = FIXTURES[0];
+    let (_, latest) = FIXTURES[FIXTURES.len() - 1];
+    let base = migrate_to_latest(&decode(oldest)).unwrap();
+    let latest: InheritanceContent = decode(latest).value().unwrap();
+
+    let diff = diff_inheritance(&base, &latest);
+    let fields: Vec<&str> = diff.iter().map(|change| change.field.as_str()).collect();
+    assert!(fields.contains(&"delay_decay") && fields.contains(&"metadata"), "{fields:?}");
+    assert!(!fields.contains(&"owner_pubkey"), "{fields:?}");
+    assert_eq!(diff_and_patch(&base, &diff), Ok(latest.clone()));
+
+    // Going back clears what version 0 didn't have, down to its creation height
+    let back = diff_inheritance(&latest, &base);
+    assert!(back.iter().any(|change| change.field == "delay_decay" && change.value.is_none()));
+    assert_eq!(diff_and_patch(&latest, &back), Ok(base.clone()));
+    assert_eq!(diff_and_patch(&latest, &[]), Ok(latest.clone()));
+
+    // A contract checked in since its creation still takes patches, though creation would refuse it...
+    let mut checked_in = latest.clone();
+    checked_in.last_checkin_block = 500;
+    assert_eq!(checked_in.validate(), Err(ValidationError::CreationHeightMismatch));
+    let mut relabelled = checked_in.clone();
+    relabelled.metadata.insert("label".to_string(), "Grandma's vault".to_string());
+    relabelled.beneficiaries.reverse();
+    let relabel = diff_inheritance(&checked_in, &relabelled);
+    assert_eq!(diff_and_patch(&checked_in, &relabel), Ok(relabelled));
+
+    // ...but not one that dates the last check-in before the creation
+    let mut rewound = checked_in.clone();
+    rewound.last_checkin_block = 99;
+    assert_eq!(
+        diff_and_patch(&checked_in, &diff_inheritance(&checked_in, &rewound)),
+        Err(PatchError::Content(ValidationError::CheckinBeforeCreation))
+    );
+
+    // Two changes to one field conflict, whatever they set it to
+    let mut conflicting = diff.clone();
+    conflicting.push(diff[0].clone());
+    assert_eq!(
+        diff_and_patch(&base, &conflicting),
+        Err(PatchError::ConflictingChanges { field: diff[0].field.clone() })
+    );
+
+    // Changes only apply to fields the content has, with values they can hold
+    let unknown = FieldChange {
+        field: "nickname".to_string(),
+        value: Some(ciborium::Value::Text("Grandma".to_string())),
+    };
+    assert_eq!(diff_and_patch(&latest, &[unknown]), Err(PatchError::InvalidContent));
+    let mistyped = FieldChange {
+        field: "trigger_delay_blocks".to_string(),
+        value: Some(ciborium::Value::Text("4320".to_string())),
+    };
+    assert_eq!(diff_and_patch(&latest, &[mistyped]), Err(PatchError::InvalidContent));
+}
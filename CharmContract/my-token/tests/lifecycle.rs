@@ -0,0 +1,241 @@
+//! Walks one inheritance contract through its whole life: create, check in,
+//! update the beneficiaries, trigger, distribute. Every step goes through
+//! app_contract, the way the prover calls it, and the contract's state between
+//! steps is kept in InMemoryStorage, the way a wallet would keep it.
+
+use charms_sdk::data::{App, Charms, Data, NativeOutput, Transaction, TxId, UtxoId, B32, NFT};
+use my_token::storage::{ContractStorage, InMemoryStorage};
+use my_token::{
+    app_contract, block_height_input, composite_identity, compute_distribution_plan, create_multisig_checkin_witness,
+    operation_witness, signing_message, Allocation, Beneficiary, ContractId, CreateWitness, DistributionWitness, InheritanceContent,
+    InheritanceStatus, Operation, OwnerPubkey, Payee, UpdateBeneficiariesWitness, CONTENT_VERSION,
+};
+
+const VAULT_SATS: u64 = 1_000_000;
+
+fn funding_utxo() -> UtxoId {
+    UtxoId(TxId([1; 32]), 0)
+}
+
+fn vault_app() -> App {
+    App {
+        tag: NFT,
+        identity: composite_identity(&[funding_utxo()]),
+        vk: B32([7; 32]),
+    }
+}
+
+fn new_contract() -> InheritanceContent {
+    InheritanceContent {
+        owner_pubkey: "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798".to_string(),
+        last_checkin_block: 100,
+        trigger_delay_blocks: 4320,
+        beneficiaries: vec![
+            Beneficiary {
+                payee: Payee::from("tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx"),
+                allocation: Allocation::Share(6000),
+                vesting_delay_blocks: 0,
+            },
+            Beneficiary {
+                payee: Payee::from("tb1pqqqqp399et2xygdj5xreqhjjvcmzhxw4aywxecjdzew6hylgvsesf3hn0c"),
+                allocation: Allocation::Share(4000),
+                vesting_delay_blocks: 0,
+            },
+        ],
+        status: InheritanceStatus::Active,
+        version: CONTENT_VERSION,
+        total_locked_sats: VAULT_SATS,
+        created_at_block: 100,
+        ..Default::default()
+    }
+}
+
+fn nft(app: &App, content: &InheritanceContent) -> Charms {
+    Charms::from([(app.clone(), Data::from(content))])
+}
+
+fn coin(amount: u64, dest: Vec<u8>) -> NativeOutput {
+    NativeOutput { amount, dest }
+}
+
+fn vault_coin() -> NativeOutput {
+    coin(VAULT_SATS, vec![0x51, 0x20, 0xaa])
+}
+
+// Spends the vault UTXO holding `input` (or the bare funding UTXO, if None)
+fn spend_vault(
+    app: &App,
+    input: Option<&InheritanceContent>,
+    outs: Vec<Charms>,
+    coin_outs: Vec<NativeOutput>,
+) -> Transaction {
+    let charms = input.map_or_else(Charms::new, |content| nft(app, content));
+    Transaction {
+        ins: vec![(funding_utxo(), charms)],
+        refs: vec![],
+        outs,
+        coin_ins: Some(vec![vault_coin()]),
+        coin_outs: Some(coin_outs),
+    }
+}
+
+// Moves the vault to a new UTXO holding `output`
+fn transition(app: &App, input: Option<&InheritanceContent>, output: &InheritanceContent) -> Transaction {
+    spend_vault(app, input, vec![nft(app, output)], vec![vault_coin()])
+}
+
+// Pays out the whole plan for `input` and burns the NFT
+fn final_distribution(app: &App, input: &InheritanceContent) -> Transaction {
+    let payouts: Vec<NativeOutput> = compute_distribution_plan(input, VAULT_SATS)
+        .into_iter()
+        .map(|(payee, amount)| coin(amount, payee.fixed_script().unwrap()))
+        .collect();
+    spend_vault(app, Some(input), vec![Charms::new(); payouts.len()], payouts)
+}
+
+fn distribution_witness(current_block: u64) -> Data {
+    let witness = DistributionWitness {
+        current_block: Some(current_block),
+        ..Default::default()
+    };
+    operation_witness(Operation::TriggerDistribution, &Data::from(&witness))
+}
+
+fn create_witness() -> Data {
+    operation_witness(Operation::Create, &Data::from(&CreateWitness::Single(funding_utxo().to_string())))
+}
+
+// The owner's key (secret key 1) and their signature over `operation` in `tx`, leaving `output`
+fn owner_sign(operation: Operation, tx: &Transaction, output: &InheritanceContent) -> (OwnerPubkey, Vec<u8>) {
+    let mut secret = [0u8; 32];
+    secret[31] = 1;
+    let signing_key = k256::schnorr::SigningKey::from_bytes(&secret).unwrap();
+    let message = signing_message(operation, tx, output);
+    let signature = signing_key.sign_raw(&message, &[0u8; 32]).unwrap().to_bytes().to_vec();
+    let mut owner = [0x02; 33];
+    owner[1..].copy_from_slice(&signing_key.verifying_key().to_bytes());
+    (OwnerPubkey(owner), signature)
+}
+
+fn checkin_witness(tx: &Transaction, output: &InheritanceContent, current_block: u64) -> Data {
+    let signature = owner_sign(Operation::Checkin, tx, output);
+    operation_witness(Operation::Checkin, &create_multisig_checkin_witness(&[signature], current_block))
+}
+
+fn update_witness(tx: &Transaction, output: &InheritanceContent) -> Data {
+    let (_, signature) = owner_sign(Operation::UpdateBeneficiaries, tx, output);
+    operation_witness(Operation::UpdateBeneficiaries, &Data::from(&UpdateBeneficiariesWitness { signature }))
+}
+
+#[test]
+fn test_full_lifecycle() {
+    let app = vault_app();
+    let id = ContractId::from(&app);
+    let mut storage = InMemoryStorage::default();
+
+    // (1) Create, naming the funding UTXO as the identity witness
+    let created = new_contract();
+    let x = block_height_input(created.created_at_block);
+    assert!(app_contract(&app, &transition(&app, None, &created), &x, &create_witness()));
+    storage.save(id, created).unwrap();
+
+    // (2) Check in a hundred blocks later
+    let current = storage.load(&id).unwrap().unwrap();
+    let mut checked_in = current.clone();
+    checked_in.last_checkin_block = 200;
+    let tx = transition(&app, Some(&current), &checked_in);
+    let x = block_height_input(200);
+    assert!(app_contract(&app, &tx, &x, &checkin_witness(&tx, &checked_in, 200)));
+    storage.save(id, checked_in).unwrap();
+
+    // (3) Split the estate evenly instead
+    let current = storage.load(&id).unwrap().unwrap();
+    let mut updated = current.clone();
+    updated.beneficiaries[0].allocation = Allocation::Share(5000);
+    updated.beneficiaries[1].allocation = Allocation::Share(5000);
+    updated.last_checkin_block = 300;
+    let tx = transition(&app, Some(&current), &updated);
+    let x = block_height_input(300);
+    assert!(app_contract(&app, &tx, &x, &update_witness(&tx, &updated)));
+    storage.save(id, updated).unwrap();
+
+    // (4) The owner goes silent; once the deadline has passed the vault is triggered,
+    // fixing what each beneficiary is owed...
+    let current = storage.load(&id).unwrap().unwrap();
+    let trigger_block = current.last_checkin_block + current.trigger_delay_blocks + 1;
+    let mut triggered = current.clone();
+    triggered.status = InheritanceStatus::Triggered;
+    triggered.trigger_height = Some(trigger_block);
+    triggered.beneficiaries = compute_distribution_plan(&current, VAULT_SATS)
+        .into_iter()
+        .map(|(payee, amount)| Beneficiary {
+            payee,
+            allocation: Allocation::Fixed(amount),
+            vesting_delay_blocks: 0,
+        })
+        .collect();
+    let w = distribution_witness(trigger_block);
+    let x = block_height_input(trigger_block);
+    assert!(app_contract(&app, &transition(&app, Some(&current), &triggered), &x, &w));
+    storage.save(id, triggered).unwrap();
+
+    // ...and then paid out, burning the NFT
+    let current = storage.load(&id).unwrap().unwrap();
+    let tx = final_distribution(&app, &current);
+    assert_eq!(tx.coin_outs.as_ref().unwrap()[0].amount, 500_000);
+    let x = block_height_input(trigger_block + 1);
+    assert!(app_contract(&app, &tx, &x, &distribution_witness(trigger_block + 1)));
+
+    let mut distributed = current;
+    distributed.status = InheritanceStatus::Distributed;
+    storage.save(id, distributed).unwrap();
+    assert!(storage.list_active().is_empty());
+}
+
+#[test]
+fn test_lifecycle_steps_out_of_order() {
+    let app = vault_app();
+    let at = block_height_input;
+    let created = new_contract();
+
+    // Nothing can be checked in, updated or distributed before the contract exists
+    let mut checked_in = created.clone();
+    checked_in.last_checkin_block = 200;
+    let tx = transition(&app, None, &checked_in);
+    assert!(!app_contract(&app, &tx, &at(200), &checkin_witness(&tx, &checked_in, 200)));
+    let mut no_nft = final_distribution(&app, &created);
+    no_nft.ins[0].1 = Charms::new();
+    assert!(!app_contract(&app, &no_nft, &at(5000), &distribution_witness(5000)));
+
+    // A contract can't be created a second time on top of itself
+    let mut recreated = new_contract();
+    recreated.trigger_delay_blocks = 8640;
+    assert!(app_contract(&app, &transition(&app, None, &recreated), &at(100), &create_witness()));
+    assert!(!app_contract(&app, &transition(&app, Some(&created), &recreated), &at(100), &create_witness()));
+
+    // Once triggered, the owner can no longer check in or change the beneficiaries
+    let mut triggered = created.clone();
+    triggered.status = InheritanceStatus::Triggered;
+    triggered.trigger_height = Some(5000);
+
+    let mut late_checkin = triggered.clone();
+    late_checkin.last_checkin_block = 5001;
+    let tx = transition(&app, Some(&triggered), &late_checkin);
+    assert!(!app_contract(&app, &tx, &at(5001), &checkin_witness(&tx, &late_checkin, 5001)));
+    late_checkin.status = InheritanceStatus::Active;
+    let tx = transition(&app, Some(&triggered), &late_checkin);
+    assert!(!app_contract(&app, &tx, &at(5001), &checkin_witness(&tx, &late_checkin, 5001)));
+
+    let mut late_update = triggered.clone();
+    late_update.beneficiaries.pop();
+    late_update.beneficiaries[0].allocation = Allocation::Share(10_000);
+    let tx = transition(&app, Some(&triggered), &late_update);
+    assert!(!app_contract(&app, &tx, &at(5001), &update_witness(&tx, &late_update)));
+
+    // A distributed contract is finished: its NFT can't be brought back
+    let mut distributed = created.clone();
+    distributed.status = InheritanceStatus::Distributed;
+    assert!(!app_contract(&app, &final_distribution(&app, &distributed), &at(5000), &distribution_witness(5000)));
+    let tx = transition(&app, Some(&distributed), &created);
+    assert!(!app_contract(&app, &tx, &at(5001), &update_witness(&tx, &created)));
+}